@@ -2,6 +2,9 @@
 //!
 //! Generates factory boilerplate for test entities with automatic FK resolution.
 //!
+//! See the [README](https://github.com/YegorMy/factory-m8-derive/blob/main/README.md) for the
+//! full list of attributes, flags and generated methods.
+//!
 //! ## Example Usage
 //!
 //! ```ignore
@@ -50,12 +53,16 @@
 //!
 //! ## Attributes
 //!
-//! - `#[factory(entity = EntityType)]` - Specifies the entity type this factory creates
+//! - `#[factory(entity = EntityType, ...)]` - Specifies the entity type this factory creates,
+//!   plus optional factory-level keys (`table`, `partial`, `persist`, `parts`, ...)
 //! - `#[pk]` - Primary key field, uses Default::default()
 //! - `#[fk(Entity, "field", Factory)]` - FK field, optionality based on field type:
 //!   - `Option<T>`: auto-creates if None/unset, returns `Some(id)`
 //!   - `T` (non-Option): auto-creates if `is_unset()`, returns `id`
 //! - `#[fk(Entity, "field", Factory, no_default)]` - Don't auto-create, None stays None
+//! - `#[required]`, `#[range]`, `#[seq]`, ... - Per-field value attributes (see the README)
+//!
+//! Invalid attribute combinations are reported as compile errors on the offending field.
 //!
 //! ## FK Field Types
 //!
@@ -67,6 +74,9 @@
 //! - `IdType` (non-Option): Auto-creates if `is_sentinel()` returns true.
 //!   Default impl should set to sentinel value (e.g., `Id(0)`).
 //!
+//! - `Vec<IdType>`: Many-to-many ids staged by the test, never auto-created.
+//!   The field name must end in `_ids`.
+//!
 //! **Important**: Factory field type should match entity field type.
 //!
 //! ## Generated Methods
@@ -75,45 +85,186 @@
 //! - `with_<entity>(&Entity)` - Sets FK from entity reference
 //! - `with_<field>_id(Id)` - Sets FK ID directly
 //! - `with_<field>(value)` - Sets field value (for Option and non-Option fields)
+//! - `try_build()` - Like `build()`, but returns a `<Factory>Error` for unset required fields
 //! - `build()` - Creates entity in-memory (clones Option FK fields as-is)
 //! - `build_with_fks(pool)` - Creates entity, auto-creating FK dependencies if needed
 
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
+use std::collections::HashMap;
 use syn::{
-    parse_macro_input, Data, DeriveInput, Expr, Field, Fields, Ident, LitStr, Meta, Token, Type,
+    parse_macro_input, Data, DeriveInput, Expr, Field, Fields, Ident, LitInt, LitStr, Meta, Token,
+    Type,
 };
 
 // =============================================================================
 // MAIN DERIVE MACRO
 // =============================================================================
 
-#[proc_macro_derive(Factory, attributes(factory, fk, pk, required))]
+#[proc_macro_derive(
+    Factory,
+    attributes(
+        factory, fk, pk, required, parse, enum_repr, alias, env, each, range, clamp, group,
+        template, sequence, seq, nested_builder, has_many, from_fk
+    )
+)]
 pub fn derive_factory(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    TokenStream::from(expand_factory(&input))
+}
 
+/// Expands `#[derive(Factory)]` into the factory impl block.
+///
+/// Split out from `derive_factory` so the expansion can be inspected in unit tests.
+fn expand_factory(input: &DeriveInput) -> TokenStream2 {
     let factory_name = &input.ident;
 
     // Parse #[factory(entity = EntityType)]
-    let entity_type =
-        parse_factory_attr(&input).expect("Missing #[factory(entity = EntityType)] attribute");
+    // Usage errors are reported as compile errors on the offending tokens
+    let factory_info = match parse_factory_attr(input) {
+        Ok(factory_info) => factory_info,
+        Err(error) => return error.to_compile_error(),
+    };
+    let entity_type = &factory_info.entity_type;
+    // Struct literals need a turbofish for generic entities: Event::<String> { .. }
+    let entity_literal = expr_path(entity_type);
 
     // Get struct fields
     let fields = match &input.data {
         Data::Struct(s) => match &s.fields {
             Fields::Named(f) => &f.named,
-            _ => panic!("Factory only supports named fields"),
+            // Setters, FK relations and entity fields are all matched by field name
+            Fields::Unnamed(f) => {
+                return syn::Error::new_spanned(
+                    f,
+                    "tuple struct factories aren't supported yet: setters (`with_<field>`) and FK \
+                     relations are named after the fields, so name them: \
+                     `struct UserFactory { id: UserId, ... }`",
+                )
+                .to_compile_error();
+            }
+            // Nothing to set: just new() and build() for a unit entity
+            Fields::Unit => {
+                let generated = generate_unit_factory(factory_name, entity_type);
+                return if factory_info.test_only {
+                    cfg_test_only(generated)
+                } else {
+                    generated
+                };
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(
+                factory_name,
+                "Factory only works on structs, not enums or unions",
+            )
+            .to_compile_error();
+        }
+    };
+
+    let all_fields: Vec<&Field> = fields.iter().collect();
+
+    if let Some(error) = validate_field_attrs(&all_fields) {
+        return error.to_compile_error();
+    }
+
+    // Generic factories (`BufFactory<const N: usize>`) carry their type, lifetime and
+    // const params onto every impl; the opt-in extras that define their own types
+    // or `FactoryCreate` impls still need a concrete factory
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    if !generics.params.is_empty() {
+        let concrete_only = [
+            (factory_info.typestate, "typestate"),
+            (factory_info.handle, "handle"),
+            (factory_info.mock_pool.is_some(), "mock"),
+            (factory_info.upsert.is_some(), "upsert"),
+            (
+                cfg!(feature = "sqlx")
+                    && factory_info.table.is_some()
+                    && !factory_info.custom_create,
+                "table = \"...\" (without custom_create)",
+            ),
+            (factory_info.base, "base"),
+            (factory_info.extends.is_some(), "extends"),
+            (all_fields.iter().any(|f| has_attr(f, "each")), "#[each]"),
+            (all_fields.iter().any(|f| has_attr(f, "group")), "#[group]"),
+        ];
+        if let Some((_, what)) = concrete_only.iter().find(|(used, _)| *used) {
+            return syn::Error::new_spanned(
+                generics,
+                format!("{what} isn't supported on generic factories"),
+            )
+            .to_compile_error();
+        }
+    }
+
+    // #[factory(extends = BaseFactory)]: the field holding the base factory gets
+    // no setters of its own; the base's setters are delegated to it instead
+    let base_field: Option<&Field> = match &factory_info.extends {
+        Some(base) => match all_fields.iter().find(|f| is_type_named(&f.ty, base)) {
+            Some(field) => Some(*field),
+            None => {
+                return syn::Error::new_spanned(
+                    base,
+                    format!("extends = {base} requires a field of type {base}"),
+                )
+                .to_compile_error();
+            }
         },
-        _ => panic!("Factory only works on structs"),
+        None => None,
+    };
+    // #[each]: the field storing per-row generators isn't an entity field
+    let each_field: Option<&Field> = all_fields.iter().find(|f| has_attr(f, "each")).copied();
+
+    // #[factory(provenance)]: the `provenance` label isn't an entity field either
+    let provenance_field: Option<&Field> = if factory_info.provenance {
+        match all_fields
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|name| name == "provenance"))
+        {
+            Some(field) => Some(*field),
+            None => {
+                return syn::Error::new_spanned(
+                    factory_name,
+                    "provenance requires a `provenance: Option<String>` field",
+                )
+                .to_compile_error();
+            }
+        }
+    } else {
+        None
     };
 
-    let fields_vec: Vec<&Field> = fields.iter().collect();
+    // #[has_many(ChildFactory)]: queued child factories, created after the parent
+    let has_many_fields: Vec<&Field> = all_fields
+        .iter()
+        .filter(|f| has_attr(f, "has_many"))
+        .copied()
+        .collect();
+
+    let fields_vec: Vec<&Field> = all_fields
+        .iter()
+        .filter(|f| base_field.is_none_or(|b| b.ident != f.ident))
+        .filter(|f| !has_attr(f, "each"))
+        .filter(|f| !has_attr(f, "has_many"))
+        .filter(|f| provenance_field.is_none_or(|p| p.ident != f.ident))
+        .copied()
+        .collect();
 
     // Categorize fields
     let fk_fields: Vec<&Field> = fields_vec
         .iter()
         .filter(|f| parse_fk_attr(f).is_some())
+        .filter(|f| !is_vec_fk(f))
+        .copied()
+        .collect();
+
+    // Vec<Id> FK fields: many-to-many ids staged by the test, never auto-created
+    let vec_fk_fields: Vec<&Field> = fields_vec
+        .iter()
+        .filter(|f| is_vec_fk(f))
         .copied()
         .collect();
 
@@ -127,48 +278,253 @@ pub fn derive_factory(input: TokenStream) -> TokenStream {
         .collect();
 
     // Non-PK, non-FK fields that are NOT Option<T> (regular fields)
+    // PhantomData fields have nothing to set
     let regular_non_fk_fields: Vec<&Field> = fields_vec
         .iter()
         .filter(|f| !has_attr(f, "pk"))
         .filter(|f| parse_fk_attr(f).is_none())
         .filter(|f| !is_option_type(&f.ty))
+        .filter(|f| !is_phantom_data_type(&f.ty))
         .copied()
         .collect();
 
     // Generate with_* methods for FK fields (two versions: entity ref and direct ID)
     let fk_with_methods: Vec<TokenStream2> = fk_fields
         .iter()
-        .flat_map(|f| generate_fk_with_methods(f))
+        .flat_map(|f| generate_fk_with_methods(f, factory_info.into_setters))
+        .chain(
+            vec_fk_fields
+                .iter()
+                .flat_map(|f| generate_vec_fk_with_methods(f)),
+        )
         .collect();
 
     // Generate with_* methods for Option non-FK fields
+    // (#[nested_builder] fields take a closure over their builder instead)
     let option_with_methods: Vec<TokenStream2> = option_non_fk_fields
         .iter()
-        .map(|f| generate_option_with_method(f))
+        .map(|f| {
+            generate_nested_builder_with_method(f)
+                .unwrap_or_else(|| generate_option_with_method(f))
+        })
         .collect();
 
     // Generate with_* methods for regular (non-Option) non-FK fields
     let regular_with_methods: Vec<TokenStream2> = regular_non_fk_fields
         .iter()
-        .map(|f| generate_regular_with_method(f))
+        .map(|f| {
+            generate_nested_builder_with_method(f)
+                .unwrap_or_else(|| generate_regular_with_method(f))
+        })
+        .collect();
+
+    // Generate with_<field>_parse methods for #[parse] fields
+    let parse_with_methods: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| has_attr(f, "parse"))
+        .map(|f| generate_parse_with_method(f))
+        .collect();
+
+    // Generate with_<field>_empty methods for string/collection fields
+    let empty_with_methods: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| parse_fk_attr(f).is_none())
+        .filter_map(|f| generate_empty_with_method(f))
+        .collect();
+
+    // Generate with_<field>_num / with_<field>_display methods for String fields
+    let num_with_methods: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| parse_fk_attr(f).is_none())
+        .filter_map(|f| generate_num_with_method(f))
+        .collect();
+
+    // Generate with_<field>_epoch methods for timestamp fields
+    let epoch_with_methods: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| parse_fk_attr(f).is_none())
+        .filter_map(|f| generate_epoch_with_method(f))
+        .collect();
+
+    // Generate with_<field>_ok / with_<field>_err methods for Result fields
+    let result_with_methods: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| parse_fk_attr(f).is_none())
+        .filter_map(|f| generate_result_with_methods(f))
+        .collect();
+
+    // Generate with_<field>_raw methods for #[enum_repr] fields
+    let enum_repr_with_methods: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter_map(|f| generate_enum_repr_with_method(f))
+        .collect();
+
+    // Generate deprecated with_<alias> setters for #[alias("...")] fields
+    let alias_with_methods: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .flat_map(|f| generate_alias_with_methods(f, factory_info.into_setters))
         .collect();
 
+    // The base factory builds its own part of the entity
+    let base_assignment = base_field.map(|f| {
+        let field_name = f.ident.as_ref().unwrap();
+        quote! { #field_name: self.#field_name.build() }
+    });
+
     // Generate build() field assignments
     let build_assignments: Vec<TokenStream2> = fields_vec
         .iter()
         .map(|f| generate_build_assignment(f))
+        .chain(base_assignment.clone())
+        .collect();
+
+    // Read-only get_<field>() accessors for the same fields, pk included
+    let getters = fields_vec.iter().map(|f| {
+        let field_name = f.ident.as_ref().unwrap();
+        let field_type = &f.ty;
+        let getter_name = format_ident!("get_{}", field_name);
+        let doc = format!("The factory's current `{field_name}` value.");
+        quote! {
+            #[doc = #doc]
+            pub fn #getter_name(&self) -> &#field_type {
+                &self.#field_name
+            }
+        }
+    });
+
+    // try_build() reports every problem at once through <Factory>Error
+    let error_name = format_ident!("{}Error", factory_name);
+    let build_error = generate_build_error(&input.vis, factory_name, &error_name);
+
+    // into_parts() hands every configured field value out as <Factory>Parts
+    // (opt-in via #[factory(parts)])
+    let (parts_struct, into_parts_fn) = if factory_info.parts {
+        let parts_name = format_ident!("{}Parts", factory_name);
+        generate_parts(&input.vis, factory_name, &parts_name, generics, &all_fields)
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // Debug-build checks that required (non-Option) FKs aren't left at their sentinel
+    let (build_fk_checks, resolved_fk_checks): (Vec<TokenStream2>, Vec<TokenStream2>) = fk_fields
+        .iter()
+        .filter(|f| !is_option_type(&f.ty))
+        .map(|f| generate_sentinel_fk_checks(f, &error_name))
+        .unzip();
+
+    // Checks for unset #[required] fields, in declaration order
+    let required_checks: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter_map(|f| generate_required_check(f, &error_name))
         .collect();
 
+    // build_with_fks() returns unset #[required] fields as an error instead of
+    // panicking, before auto-creating any parent
+    let required_guard = if required_checks.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            let mut errors = Vec::new();
+            #(#required_checks)*
+            if !errors.is_empty() {
+                return Err(#error_name::from_errors(errors).into());
+            }
+        }
+    };
+
     // Generate build_with_fks() FK resolution
-    let fk_resolutions: Vec<TokenStream2> = fk_fields
+    // #[factory(batch_fks)]: plain FKs sharing a parent factory are created with one
+    // create_many() call, whose entities are handed out in field order
+    let (mut fk_batches, batched_fields) = if factory_info.batch_fks {
+        generate_fk_batches(&fk_fields)
+    } else {
+        (HashMap::new(), HashMap::new())
+    };
+
+    // #[fk(..., shared)]: parents created during this call, by factory type, so
+    // every shared FK to the same parent factory reuses one row
+    let shared_memo = fk_fields
+        .iter()
+        .any(|f| parse_fk_attr(f).is_some_and(|fk_info| fk_info.shared))
+        .then(|| {
+            quote! {
+                let mut shared_parents: std::collections::HashMap<
+                    std::any::TypeId,
+                    Box<dyn std::any::Any + Send>,
+                > = std::collections::HashMap::new();
+            }
+        });
+
+    // #[from_fk(parent = "fk", field = "..")]: fields copied off an auto-created
+    // parent, by the FK field creating it
+    let from_fk_fields: Vec<(&Field, FromFkAttrInfo)> = fields_vec
         .iter()
-        .map(|f| generate_fk_resolution(f))
+        .filter_map(|f| Some((*f, parse_from_fk_attr(f)?)))
+        .collect();
+    let inheriting = |fk_field: &Field| -> Vec<(Ident, Ident)> {
+        from_fk_fields
+            .iter()
+            .filter(|(_, info)| fk_field.ident.as_ref() == Some(&info.parent))
+            .map(|(f, info)| (f.ident.clone().unwrap(), info.field.clone()))
+            .collect()
+    };
+    let inherited_declarations = from_fk_fields.iter().map(|(f, _)| {
+        let inherited_var = format_ident!("inherited_{}", f.ident.as_ref().unwrap());
+        let field_type = &f.ty;
+        quote! {
+            #[allow(unused_mut)]
+            let mut #inherited_var: Option<#field_type> = None;
+        }
+    });
+
+    // #[factory(parallel_fks)]: FKs that don't depend on state shared between
+    // resolutions (shared memo, batches, use_last, max_depth counters, parents
+    // #[from_fk] fields copy from) are awaited concurrently
+    let (parallel_fields, serial_fields): (Vec<&Field>, Vec<&Field>) =
+        fk_fields.iter().copied().partition(|f| {
+            let fk_info = parse_fk_attr(f).unwrap();
+            factory_info.parallel_fks
+                && !fk_info.shared
+                && !fk_info.use_last
+                && fk_info.max_depth.is_none()
+                && !batched_fields.contains_key(f.ident.as_ref().unwrap())
+                && inheriting(f).is_empty()
+        });
+    let (parallel_fields, serial_fields) = if parallel_fields.len() < 2 {
+        (Vec::new(), fk_fields.clone())
+    } else {
+        (parallel_fields, serial_fields)
+    };
+
+    // Serial FKs resolve in declaration order, so auto-created parents get their
+    // ids in that order; a batch creates all of its parents at its first field
+    let fk_resolutions: Vec<TokenStream2> = shared_memo
+        .into_iter()
+        .chain(inherited_declarations)
+        .chain(serial_fields.iter().map(|f| {
+            let batch = batched_fields.get(f.ident.as_ref().unwrap());
+            let batch_creation = batch.and_then(|batch_var| fk_batches.remove(batch_var));
+            let resolution = generate_fk_resolution(f, batch, &inheriting(f), false);
+            quote! {
+                #batch_creation
+                #resolution
+            }
+        }))
+        .chain(generate_parallel_fk_resolutions(&parallel_fields))
         .collect();
 
     // Generate build_with_fks() field assignments
     let build_with_fks_assignments: Vec<TokenStream2> = fields_vec
         .iter()
         .map(|f| generate_build_with_fks_assignment(f))
+        .chain(base_assignment)
         .collect();
 
     // Collect FK factory types that need FactoryCreate<Pool> bounds
@@ -178,82 +534,341 @@ pub fn derive_factory(input: TokenStream) -> TokenStream {
         .iter()
         .filter_map(|f| {
             let fk_info = parse_fk_attr(f)?;
-            if fk_info.no_default {
+            if fk_info.require_existing {
+                // never auto-created; with sqlx, the pool is downcast to check the parent
+                cfg!(feature = "sqlx").then(|| quote! { Pool: 'static })
+            } else if fk_info.no_default {
                 None // no_default FKs don't auto-create, no bound needed
             } else {
                 let factory_type = fk_info.factory_type;
                 let entity_type = fk_info.entity_type;
+                // find = path and create_method = "name" downcast the pool to
+                // their own pool type
+                let downcasts_pool = fk_info.find.is_some() || fk_info.create_method.is_some();
+                let static_pool = downcasts_pool.then(|| quote! { , Pool: 'static });
                 // Constrain that the factory's Entity type matches the expected entity
-                Some(quote! { #factory_type: factory_m8::FactoryCreate<Pool, Entity = #entity_type> })
+                Some(quote! {
+                    #factory_type: factory_m8::FactoryCreate<Pool, Entity = #entity_type>
+                    #static_pool
+                })
             }
         })
         .collect();
 
-    // Generate the impl block
-    let expanded = if fk_factory_bounds.is_empty() {
-        // No FK auto-creation, simpler signature without bounds
+    // Compile-time check that build_with_fks() stays Send, for tokio::spawn
+    let send_assertion = generate_send_assertion(factory_name, generics, &fk_factory_bounds);
+
+    // Typestate builder (opt-in via #[factory(typestate)])
+    let typestate_builder = if factory_info.typestate {
+        generate_typestate_builder(
+            &input.vis,
+            factory_name,
+            entity_type,
+            &fields_vec,
+            factory_info.into_setters,
+        )
+    } else {
+        quote! {}
+    };
+
+    // create_tracked() is only useful when some FK can be auto-created
+    let create_tracked = if fk_factory_bounds.is_empty() {
+        quote! {}
+    } else {
+        generate_create_tracked(
+            &input.vis,
+            factory_name,
+            generics,
+            entity_type,
+            &fields_vec,
+            &fk_factory_bounds,
+            factory_info.batch_fks,
+        )
+    };
+
+    // Cache-aware create (opt-in via #[factory(cache)])
+    let create_cached = if factory_info.cache {
+        generate_create_cached(factory_name, generics, entity_type, &fk_fields)
+    } else {
+        quote! {}
+    };
+
+    // Value-deduplicating create (opt-in per FK via #[fk(..., dedup)])
+    let create_deduped = generate_create_deduped(factory_name, generics, entity_type, &fk_fields);
+
+    // In-memory FactoryCreate impl (opt-in via #[factory(mock)])
+    let mock_create = match &factory_info.mock_pool {
+        Some(mock_pool) => generate_mock_create(factory_name, entity_type, mock_pool, &fields_vec),
+        None => quote! {},
+    };
+
+    // Provenance label setter (opt-in via #[factory(provenance)])
+    let provenance_method = provenance_field.map(|_| {
         quote! {
-            impl #factory_name {
-                /// Create a new factory with default values.
-                pub fn new() -> Self {
-                    Self::default()
-                }
+            /// Label where this factory's rows come from (e.g. the test name).
+            /// Only kept on the factory for `Debug` output; not part of the entity.
+            pub fn with_provenance(mut self, label: impl Into<String>) -> Self {
+                self.provenance = Some(label.into());
+                self
+            }
+        }
+    });
 
-                #(#fk_with_methods)*
+    // proptest strategy (opt-in via #[factory(arb)], needs the `proptest` feature)
+    let arb_fn = if factory_info.arb {
+        generate_arb_fn(&fields_vec)
+    } else {
+        quote! {}
+    };
+
+    // Named constructors (opt-in via #[factory(preset(name = |f| ...))])
+    let preset_methods = factory_info.presets.iter().map(|(name, apply)| {
+        let doc = format!("`new()` with the `{name}` preset applied.");
+        quote! {
+            #[doc = #doc]
+            pub fn #name() -> Self {
+                let preset: fn(Self) -> Self = #apply;
+                preset(Self::new())
+            }
+        }
+    });
+
+    // Variants of one base factory (opt-in via #[factory(variants)], needs Clone)
+    let variants_method = factory_info.variants.then(|| {
+        quote! {
+            /// Build one entity per override, each applied to a clone of `base`.
+            pub fn build_variants(base: Self, overrides: Vec<fn(Self) -> Self>) -> Vec<#entity_type> {
+                overrides
+                    .into_iter()
+                    .map(|apply| apply(base.clone()).build())
+                    .collect()
+            }
+        }
+    });
 
-                #(#option_with_methods)*
+    // Per-row generators (opt-in via an #[each] storage field)
+    let (each_struct, each_methods) = match each_field {
+        Some(storage) => generate_each(&input.vis, factory_name, storage, &fields_vec),
+        None => (quote! {}, quote! {}),
+    };
 
-                #(#regular_with_methods)*
+    // Scoped setters for #[group("name")] fields
+    let (group_structs, group_methods) = generate_groups(
+        &input.vis,
+        factory_name,
+        &fields_vec,
+        factory_info.into_setters,
+    );
 
-                /// Build an in-memory entity without DB insert.
-                /// Panics if required FK fields are None.
-                pub fn build(&self) -> #entity_type {
-                    #entity_type {
-                        #(#build_assignments),*
-                    }
+    // Created-entity handle (opt-in via #[factory(handle)])
+    let (handle_struct, create_handle_fn) = if factory_info.handle {
+        generate_handle(&input.vis, entity_type)
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // Child rows (#[has_many(ChildFactory)]): with_<child>() and create_with_children()
+    let has_many_methods = if has_many_fields.is_empty() {
+        quote! {}
+    } else {
+        generate_has_many(entity_type, &fields_vec, &has_many_fields)
+    };
+
+    // <Factory>Persist: `factory.persist(&pool).await` creates the entity
+    // (opt-in via #[factory(persist)])
+    let (persist_struct, persist_fn) = if factory_info.persist {
+        generate_persist(&input.vis, factory_name, generics, entity_type)
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // `ChildFactory: From<&Parent>` for handles' as_fk(), one per parent entity type
+    // (opt-in via #[factory(fk_from)], so hand-written From impls don't conflict)
+    let fk_from_impls = if factory_info.fk_from {
+        generate_fk_from_impls(factory_name, generics, &fk_fields)
+    } else {
+        quote! {}
+    };
+
+    // Partial update struct (opt-in via #[factory(updates)])
+    let update_struct = if factory_info.updates {
+        generate_update_struct(&input.vis, entity_type, &fields_vec)
+    } else {
+        quote! {}
+    };
+
+    // Base factories expose their setters for child factories to delegate to
+    let base_delegation = if factory_info.base {
+        generate_base_delegation(factory_name, &fields_vec, factory_info.into_setters)
+    } else {
+        quote! {}
+    };
+
+    // Child factories pull in the base's delegating setters
+    let extends_delegation = match (&factory_info.extends, base_field) {
+        (Some(base), Some(field)) => {
+            let delegate_macro = delegate_macro_name(base);
+            let field_name = field.ident.as_ref().unwrap();
+            quote! { #delegate_macro!(#factory_name, #field_name); }
+        }
+        _ => quote! {},
+    };
+
+    // Primary key type for generic code (`pk-trait` feature, needs factory_m8::FactoryPk)
+    let pk_trait_impl = match fields_vec.iter().find(|f| has_attr(f, "pk")) {
+        Some(pk_field) if cfg!(feature = "pk-trait") => {
+            let pk_type = &pk_field.ty;
+            quote! {
+                #[automatically_derived]
+                impl #impl_generics factory_m8::FactoryPk for #factory_name #ty_generics #where_clause {
+                    type Pk = #pk_type;
                 }
+            }
+        }
+        _ => quote! {},
+    };
+
+    // sqlx create (opt-in via #[factory(table = "...")] and the `sqlx` feature):
+    // a plain INSERT, or with upsert = "..." an INSERT ... ON CONFLICT
+    let sqlx_create = match (&factory_info.table, &factory_info.upsert) {
+        (None, _) => quote! {},
+        // Without the feature, table = "..." only names the table (TABLE)
+        (Some(_), None) if !cfg!(feature = "sqlx") || factory_info.custom_create => quote! {},
+        (Some(table), upsert) => {
+            let columns = match insert_columns(&fields_vec, factory_info.column_order.as_deref()) {
+                Ok(columns) => columns,
+                Err(error) => return error.to_compile_error(),
+            };
+            generate_sqlx_create(factory_name, entity_type, table, upsert.as_ref(), &columns)
+        }
+    };
+
+    // table = "...": also exposed as TABLE, e.g. for require_existing FKs of child factories
+    let table_const = factory_info.table.as_ref().map(|table| {
+        quote! {
+            /// The table the entity is stored in (`table = "..."`)
+            pub const TABLE: &'static str = #table;
+        }
+    });
+
+    // phantom(a, b): entity-only PhantomData fields; partial factories fill
+    // the remaining entity fields from Default
+    let phantom_fields = &factory_info.phantom;
+    let struct_rest = if factory_info.partial {
+        quote! { #(#phantom_fields: std::marker::PhantomData,)* ..Default::default() }
+    } else {
+        quote! { #(#phantom_fields: std::marker::PhantomData,)* }
+    };
+
+    // #[factory(defaults)]: new() goes through the generated defaults() instead of Default
+    let (new_body, defaults_fn) = if factory_info.defaults {
+        (
+            quote! { Self::defaults() },
+            generate_defaults_fn(&all_fields),
+        )
+    } else {
+        (quote! { Self::default() }, quote! {})
+    };
+
+    // #[factory(derive_default)]: generate the Default impl new() goes through
+    let default_impl = if factory_info.derive_default {
+        generate_default_impl(factory_name, generics, &all_fields)
+    } else {
+        quote! {}
+    };
+
+    // TryFrom<HashMap<String, String>> for data-driven tests (opt-in via #[factory(from_map)])
+    let from_map_impl = if factory_info.from_map {
+        generate_from_map_impl(factory_name, generics, &fields_vec)
+    } else {
+        quote! {}
+    };
+
+    // normalize() resets sentinel FKs and fills unset #[required] fields
+    let normalize_fn = generate_normalize_fn(&fields_vec);
+
+    // #[factory(invariant = check)]: check(&Entity) -> Result<(), String> runs on every
+    // built entity; try_build() and build_with_fks() return its error, build() panics
+    let invariant_check = factory_info.invariant.as_ref().map(|check| {
+        quote! {
+            if let Err(message) = #check(&entity) {
+                return Err(#error_name::Invariant(message).into());
+            }
+        }
+    });
+
+    // Without FK auto-creation the pool is never used
+    let pool_param = if fk_factory_bounds.is_empty() {
+        quote! { _pool }
+    } else {
+        quote! { pool }
+    };
 
+    // #[fk(..., optional_create)]: build_with_fks_opts() takes the per-call options,
+    // and build_with_fks() calls it with the defaults
+    let has_optional_create = fk_fields
+        .iter()
+        .any(|f| parse_fk_attr(f).is_some_and(|fk_info| fk_info.optional_create));
+    let build_options_name = format_ident!("{}BuildOptions", factory_name);
+    let (build_with_fks_fn, build_options) = if has_optional_create {
+        let vis = &input.vis;
+        (
+            quote! {
                 /// Build entity with automatic FK resolution.
-                /// Generic over the database pool type.
+                /// If FK fields are sentinel values, creates dependencies via their factories.
+                /// `optional_create` FKs stay None; use `build_with_fks_opts()` to create them.
+                ///
+                /// Generic over the database pool type - works with any backend
+                /// (sqlx::PgPool, sqlx::SqlitePool, mongodb::Database, etc.)
                 pub async fn build_with_fks<Pool>(
                     &self,
-                    _pool: &Pool,
+                    pool: &Pool,
                 ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
                 where
                     Pool: Sync,
+                    #(#fk_factory_bounds,)*
                 {
-                    // No FK resolutions needed
-                    #(#fk_resolutions)*
-
-                    Ok(#entity_type {
-                        #(#build_with_fks_assignments),*
-                    })
-                }
-            }
-        }
-    } else {
-        // Has FK auto-creation, need bounds for FK factories
-        quote! {
-            impl #factory_name {
-                /// Create a new factory with default values.
-                pub fn new() -> Self {
-                    Self::default()
+                    self.build_with_fks_opts(pool, #build_options_name::default())
+                        .await
                 }
 
-                #(#fk_with_methods)*
-
-                #(#option_with_methods)*
+                /// Build entity with automatic FK resolution, with per-call options
+                /// controlling which `optional_create` FKs are auto-created.
+                pub async fn build_with_fks_opts<Pool>(
+                    &self,
+                    pool: &Pool,
+                    options: #build_options_name,
+                ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
+                where
+                    Pool: Sync,
+                    #(#fk_factory_bounds,)*
+                {
+                    #required_guard
 
-                #(#regular_with_methods)*
+                    // Resolve all FK dependencies
+                    #(#fk_resolutions)*
+                    #(#resolved_fk_checks)*
 
-                /// Build an in-memory entity without DB insert.
-                /// Panics if required FK fields are None.
-                pub fn build(&self) -> #entity_type {
-                    #entity_type {
-                        #(#build_assignments),*
-                    }
+                    let entity = #entity_literal {
+                        #(#build_with_fks_assignments,)*
+                        #struct_rest
+                    };
+                    #invariant_check
+                    Ok(entity)
                 }
-
+            },
+            quote! {
+                /// Per-call options for `build_with_fks_opts()`.
+                #[derive(Debug, Clone, Copy, Default)]
+                #vis struct #build_options_name {
+                    /// Auto-create unset `#[fk(..., optional_create)]` FKs
+                    pub create_optionals: bool,
+                }
+            },
+        )
+    } else {
+        (
+            quote! {
                 /// Build entity with automatic FK resolution.
                 /// If FK fields are sentinel values, creates dependencies via their factories.
                 ///
@@ -261,471 +876,5747 @@ pub fn derive_factory(input: TokenStream) -> TokenStream {
                 /// (sqlx::PgPool, sqlx::SqlitePool, mongodb::Database, etc.)
                 pub async fn build_with_fks<Pool>(
                     &self,
-                    pool: &Pool,
+                    #pool_param: &Pool,
                 ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
                 where
                     Pool: Sync,
                     #(#fk_factory_bounds,)*
                 {
+                    #required_guard
+
                     // Resolve all FK dependencies
                     #(#fk_resolutions)*
+                    #(#resolved_fk_checks)*
 
-                    Ok(#entity_type {
-                        #(#build_with_fks_assignments),*
-                    })
+                    let entity = #entity_literal {
+                        #(#build_with_fks_assignments,)*
+                        #struct_rest
+                    };
+                    #invariant_check
+                    Ok(entity)
                 }
-            }
+            },
+            quote! {},
+        )
+    };
+
+    // #[factory(after_create = "name")]: create_and_after() / build_with_fks_and_after()
+    let after_create_fns = factory_info
+        .after_create
+        .as_ref()
+        .map(|hook| generate_after_create(hook, entity_type, &fk_factory_bounds));
+
+    // clear_non_fk() resets every non-FK, non-pk field from the factory defaults
+    let cleared_fields: Vec<&Ident> = option_non_fk_fields
+        .iter()
+        .chain(&regular_non_fk_fields)
+        .map(|f| f.ident.as_ref().unwrap())
+        .collect();
+    let clear_non_fk_body = if cleared_fields.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            let defaults: Self = #new_body;
+            #(self.#cleared_fields = defaults.#cleared_fields;)*
         }
     };
 
-    TokenStream::from(expanded)
-}
+    // Row `i` of create_many(): a clone of the factory with #[template] fields filled in
+    let template_assignments: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter_map(|f| generate_template_assignment(f))
+        .collect();
+    let (row_index, row_factory) = if template_assignments.is_empty() {
+        (quote! { _ }, quote! { let factory = self.clone(); })
+    } else {
+        (
+            quote! { i },
+            quote! {
+                let mut factory = self.clone();
+                #(#template_assignments)*
+            },
+        )
+    };
 
-// =============================================================================
-// ATTRIBUTE PARSING
-// =============================================================================
+    // #[sequence] fields: one process-global counter per factory and field
+    let sequence_fns: Vec<TokenStream2> = fields_vec
+        .iter()
+        .filter_map(|f| generate_sequence_fn(f))
+        .collect();
 
-/// Parses #[factory(entity = EntityType)]
-fn parse_factory_attr(input: &DeriveInput) -> Option<Ident> {
-    for attr in &input.attrs {
-        if attr.path().is_ident("factory") {
-            let nested = attr
-                .parse_args_with(syn::punctuated::Punctuated::<Meta, Token![,]>::parse_terminated)
-                .ok()?;
-
-            for meta in nested {
-                if let Meta::NameValue(nv) = meta {
-                    if nv.path.is_ident("entity") {
-                        if let Expr::Path(expr_path) = &nv.value {
-                            return expr_path.path.get_ident().cloned();
-                        }
-                    }
-                }
+    // #[seq] fields: one process-global counter per factory
+    let seq_fn = if fields_vec.iter().any(|f| parse_seq_attr(f).is_some()) {
+        quote! {
+            /// The next value of this factory's `#[seq]` counter.
+            fn next_seq() -> u64 {
+                static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+                NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
             }
         }
+    } else {
+        quote! {}
+    };
+
+    // build_many() only clones the factory when rows differ by their templates
+    let build_many_fn = if template_assignments.is_empty() {
+        quote! {
+            /// Build `n` in-memory entities (see `build()`). `n == 0` returns an
+            /// empty Vec without checking `#[required]` fields.
+            pub fn build_many(&self, n: usize) -> Vec<#entity_type> {
+                (0..n).map(|_| self.build()).collect()
+            }
+        }
+    } else {
+        quote! {
+            /// Build `n` in-memory entities (see `build()`), each from a clone of this
+            /// factory with `#[template("...{i}...")]` fields filled in for row `i`,
+            /// like `create_many()`. `n == 0` returns an empty Vec without checking
+            /// `#[required]` fields.
+            pub fn build_many(&self, n: usize) -> Vec<#entity_type>
+            where
+                // higher-ranked so non-Clone factories still compile without it
+                for<'a> Self: Clone,
+            {
+                let mut entities = Vec::with_capacity(n);
+                for #row_index in 0..n {
+                    #row_factory
+                    entities.push(factory.build());
+                }
+                entities
+            }
+        }
+    };
+
+    // Generate the impl block
+    let factory_impl = quote! {
+        #[automatically_derived]
+        impl #impl_generics #factory_name #ty_generics #where_clause {
+            // setters of #[deprecated] fields are deprecated too; delegating between them is fine
+            #![allow(deprecated)]
+
+            /// Create a new factory with default values.
+            pub fn new() -> Self {
+                #new_body
+            }
+
+            #defaults_fn
+
+            #table_const
+
+            /// Reset every non-FK, non-pk field to its `new()` default, keeping the
+            /// FK fields (and pk) as set, e.g. to vary children of the same parents.
+            pub fn clear_non_fk(&mut self) {
+                #clear_non_fk_body
+            }
+
+            #normalize_fn
+
+            #(#preset_methods)*
+
+            #(#fk_with_methods)*
+
+            #(#option_with_methods)*
+
+            #(#regular_with_methods)*
+
+            #(#parse_with_methods)*
+
+            #(#empty_with_methods)*
+
+            #(#num_with_methods)*
+
+            #(#epoch_with_methods)*
+
+            #(#result_with_methods)*
+
+            #(#enum_repr_with_methods)*
+
+            #(#alias_with_methods)*
+
+            #(#getters)*
+
+            #each_methods
+
+            #group_methods
+
+            #provenance_method
+
+            #(#sequence_fns)*
+
+            #seq_fn
+
+            /// Build an in-memory entity without DB insert, returning every unset
+            /// `#[required]` field (and, in debug builds, every non-Option FK still
+            /// at its sentinel) in one error instead of stopping at the first.
+            pub fn try_build(&self) -> Result<#entity_type, #error_name> {
+                let mut errors = Vec::new();
+                #(#required_checks)*
+                #(#build_fk_checks)*
+                if !errors.is_empty() {
+                    return Err(#error_name::from_errors(errors));
+                }
+
+                let entity = #entity_literal {
+                    #(#build_assignments,)*
+                    #struct_rest
+                };
+                #invariant_check
+                Ok(entity)
+            }
+
+            /// Build an in-memory entity without DB insert.
+            /// Panics listing all unset required fields (see `try_build()`).
+            /// In debug builds, also panics if a required FK is still the sentinel.
+            pub fn build(&self) -> #entity_type {
+                self.try_build().unwrap_or_else(|error| panic!("{error}"))
+            }
+
+            #build_many_fn
+
+            #variants_method
+
+            #into_parts_fn
+
+            #create_handle_fn
+
+            #has_many_methods
+
+            #persist_fn
+
+            #arb_fn
+
+            #build_with_fks_fn
+
+            #after_create_fns
+
+            /// Create `n` entities via the `FactoryCreate` impl, each from a clone of
+            /// this factory, so every row auto-creates its own unset FK parents.
+            /// `#[template("...{i}...")]` fields get the row index substituted.
+            /// Used by `#[factory(batch_fks)]` factories to create their parents.
+            pub async fn create_many<Pool>(
+                self,
+                n: usize,
+                pool: &Pool,
+            ) -> Result<Vec<#entity_type>, Box<dyn std::error::Error + Send + Sync>>
+            where
+                Pool: Sync,
+                Self: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+                // higher-ranked so non-Clone factories still compile without it
+                for<'a> Self: Clone,
+            {
+                use factory_m8::FactoryCreate;
+
+                let mut entities = Vec::with_capacity(n);
+                for #row_index in 0..n {
+                    #row_factory
+                    entities.push(factory.create(pool).await?);
+                }
+                Ok(entities)
+            }
+
+            /// Create the entity via its `FactoryCreate` impl and remember it as the
+            /// last-created entity on this thread, which `#[fk(..., use_last)]` FKs
+            /// of other factories reuse instead of creating a new parent.
+            pub async fn create_last<Pool, Entity>(
+                self,
+                pool: &Pool,
+            ) -> Result<Entity, Box<dyn std::error::Error + Send + Sync>>
+            where
+                Pool: Sync,
+                Self: factory_m8::FactoryCreate<Pool, Entity = Entity>,
+                Entity: Clone + 'static,
+            {
+                use factory_m8::FactoryCreate;
+
+                let entity = self.create(pool).await?;
+                Self::__last_created()
+                    .with(|last| *last.borrow_mut() = Some(Box::new(entity.clone())));
+                Ok(entity)
+            }
+
+            /// Forget the last-created entity remembered for `#[fk(..., use_last)]`
+            pub fn clear_last() {
+                Self::__last_created().with(|last| *last.borrow_mut() = None);
+            }
+
+            #[doc(hidden)]
+            pub fn __last_created() -> &'static std::thread::LocalKey<
+                std::cell::RefCell<Option<Box<dyn std::any::Any>>>,
+            > {
+                thread_local! {
+                    static LAST: std::cell::RefCell<Option<Box<dyn std::any::Any>>> =
+                        const { std::cell::RefCell::new(None) };
+                }
+                &LAST
+            }
+        }
+    };
+
+    let generated = quote! {
+        #factory_impl
+        #send_assertion
+        #default_impl
+        #from_map_impl
+        #build_error
+        #parts_struct
+        #pk_trait_impl
+        #build_options
+        #create_tracked
+        #create_cached
+        #create_deduped
+        #mock_create
+        #sqlx_create
+        #typestate_builder
+        #each_struct
+        #group_structs
+        #handle_struct
+        #persist_struct
+        #fk_from_impls
+        #update_struct
+        #base_delegation
+        #extends_delegation
+    };
+
+    // #[factory(test_only)]: keep the generated code out of production builds
+    if factory_info.test_only {
+        cfg_test_only(generated)
+    } else {
+        generated
     }
-    None
+}
+
+/// Puts `#[cfg(any(test, feature = "factories"))]` on every generated item
+fn cfg_test_only(generated: TokenStream2) -> TokenStream2 {
+    let file: syn::File = syn::parse2(generated).expect("generated code should parse as items");
+    let items = file.items;
+    quote! {
+        #(
+            #[cfg(any(test, feature = "factories"))]
+            #items
+        )*
+    }
+}
+
+/// Rejects attribute combinations where one attribute would be silently ignored.
+/// In the build assignments, `#[pk]` is checked first (always `Default`, or its
+/// `unset_value`), then `#[fk]` (the id as set, or resolved by `build_with_fks()`),
+/// and only then `#[required]`, so on an FK or pk field the later ones never apply.
+/// A malformed `#[fk(...)]` (e.g. a misspelt flag) is rejected too, rather than
+/// leaving the field a plain one.
+/// Field attributes that don't fit the field's type are rejected here as well,
+/// so code generation can rely on them.
+/// Every offending field is reported, each spanned at its name (or the bad token).
+fn validate_field_attrs(fields: &[&Field]) -> Option<syn::Error> {
+    let mut errors = fields
+        .iter()
+        .filter_map(|f| validate_field(f, fields).err());
+    let mut first = errors.next()?;
+    for error in errors {
+        first.combine(error);
+    }
+    Some(first)
+}
+
+/// The checks of `validate_field_attrs` on one field, stopping at the first error;
+/// `fields` are all of the factory's fields
+fn validate_field(field: &Field, fields: &[&Field]) -> syn::Result<()> {
+    let Some(field_name) = field.ident.as_ref() else {
+        return Ok(());
+    };
+    if let Some(fk_info) = try_parse_fk_attr(field) {
+        fk_info?;
+    }
+    if let Some(attr) = find_attr(field, "pk") {
+        try_parse_pk_attr(attr)?;
+    }
+    if has_attr(field, "pk") && has_attr(field, "fk") {
+        return Err(syn::Error::new_spanned(
+            field_name,
+            format!(
+                "{field_name}: #[pk] and #[fk] can't be combined: built entities always get the \
+                 pk's default, so the FK would never be resolved"
+            ),
+        ));
+    }
+    if has_attr(field, "fk") && has_attr(field, "required") {
+        return Err(syn::Error::new_spanned(
+            field_name,
+            format!(
+                "{field_name}: #[fk] and #[required] can't be combined: an unset FK is \
+                 auto-created, so #[required] would be ignored. For an FK that must be set, use \
+                 #[fk(..., no_default, not_null)] on an Option field"
+            ),
+        ));
+    }
+
+    let value_type = extract_option_inner_type(&field.ty).unwrap_or(&field.ty);
+    if let Some(attr) = find_attr(field, "range") {
+        if !is_integer_type(value_type) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("{field_name}: #[range] is only supported on integer fields"),
+            ));
+        }
+        try_parse_range_attr(attr)?;
+    }
+    if let Some(attr) = find_attr(field, "clamp") {
+        if !is_integer_type(value_type) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("{field_name}: #[clamp] is only supported on integer fields"),
+            ));
+        }
+        if has_attr(field, "range") {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("{field_name}: #[clamp] and #[range] can't be combined"),
+            ));
+        }
+        try_parse_clamp_attr(attr)?;
+    }
+    if let Some(attr) = find_attr(field, "sequence")
+        && !(is_option_type(&field.ty) && is_integer_type(value_type))
+    {
+        return Err(syn::Error::new_spanned(
+            attr,
+            format!("{field_name}: #[sequence] is only supported on Option<integer> fields"),
+        ));
+    }
+    if let Some(attr) = find_attr(field, "group")
+        && has_attr(field, "pk")
+    {
+        return Err(syn::Error::new_spanned(
+            attr,
+            format!("{field_name}: #[group] can't be used on #[pk] fields"),
+        ));
+    }
+    if let Some(attr) = find_attr(field, "template") {
+        if !is_string_type(value_type) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("{field_name}: #[template] is only supported on String fields"),
+            ));
+        }
+        attr.parse_args::<LitStr>()
+            .map_err(|e| syn::Error::new(e.span(), format!("invalid #[template]: {e}")))?;
+    }
+    if let Some(attr) = find_attr(field, "seq") {
+        if !is_string_type(value_type) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!("{field_name}: #[seq] is only supported on String fields"),
+            ));
+        }
+        try_parse_seq_attr(attr)?;
+    }
+    if let Some(attr) = find_attr(field, "from_fk") {
+        let info = try_parse_from_fk_attr(attr)?;
+        let parent_is_fk = fields.iter().any(|f| {
+            f.ident.as_ref() == Some(&info.parent) && parse_fk_attr(f).is_some() && !is_vec_fk(f)
+        });
+        if !parent_is_fk {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!(
+                    "{field_name}: #[from_fk] parent `{}` isn't an FK field of this factory",
+                    info.parent
+                ),
+            ));
+        }
+    }
+    if let Some(attr) = find_attr(field, "has_many") {
+        if extract_vec_inner_type(&field.ty).is_none() {
+            return Err(syn::Error::new_spanned(
+                field_name,
+                format!("{field_name}: #[has_many] is only supported on Vec<ChildFactory> fields"),
+            ));
+        }
+        if !fields.iter().any(|f| has_attr(f, "pk")) {
+            return Err(syn::Error::new_spanned(
+                field_name,
+                format!(
+                    "{field_name}: #[has_many] requires a #[pk] field to point the children at"
+                ),
+            ));
+        }
+        try_parse_has_many_attr(attr)?;
+    }
+    Ok(())
+}
+
+/// Generates the impl for a unit struct factory (`struct PingFactory;`), whose
+/// entity is a unit struct too: `new()` and a `build()` that can't fail.
+fn generate_unit_factory(factory_name: &Ident, entity_type: &syn::Path) -> TokenStream2 {
+    let entity_literal = expr_path(entity_type);
+    quote! {
+        #[automatically_derived]
+        impl #factory_name {
+            /// Create the factory (a unit struct has nothing to configure).
+            pub fn new() -> Self {
+                Self
+            }
+
+            /// Build the unit entity.
+            pub fn build(&self) -> #entity_type {
+                #entity_literal
+            }
+        }
+    }
+}
+
+// =============================================================================
+// ATTRIBUTE PARSING
+// =============================================================================
+
+/// Factory attribute info
+struct FactoryAttrInfo {
+    /// Entity type path, including any generic args (`Event<String>`)
+    entity_type: syn::Path,
+    /// When true, also generate a typestate builder whose `build()` is only
+    /// callable once every `#[required]` field has been set
+    typestate: bool,
+    /// When true, entity fields the factory doesn't declare are filled from
+    /// the entity's `Default` impl
+    partial: bool,
+    /// Pool type to generate an in-memory `FactoryCreate` impl for
+    /// (`mock` uses `MockPool`, `mock = PoolType` names it explicitly)
+    mock_pool: Option<syn::Path>,
+    /// When true, also generate `create_cached()`, which reuses FK parents
+    /// from a cache threaded through the whole FK chain
+    cache: bool,
+    /// When true, this factory can be extended by other factories
+    base: bool,
+    /// Base factory whose setters this factory delegates to
+    extends: Option<Ident>,
+    /// When true, also generate `<Entity>Update`, a partial update struct
+    updates: bool,
+    /// When true, generate `defaults()` and have `new()` use it instead of `Default`
+    defaults: bool,
+    /// When true, also generate the factory's `impl Default` (sentinel pk and FKs)
+    derive_default: bool,
+    /// When true, the factory's `provenance: Option<String>` field is a label for
+    /// debugging (set via `with_provenance`) rather than an entity field
+    provenance: bool,
+    /// When true, also generate `build_variants()` (factory must implement `Clone`)
+    variants: bool,
+    /// When true, also generate a proptest `arb()` strategy (`proptest` feature)
+    arb: bool,
+    /// When true, also implement `TryFrom<HashMap<String, String>>` for the factory
+    from_map: bool,
+    /// Database table the entity is stored in (`sqlx` feature)
+    table: Option<LitStr>,
+    /// Unique column for a generated `INSERT ... ON CONFLICT` create (`sqlx` feature)
+    upsert: Option<LitStr>,
+    /// When true, `table = "..."` only names the table and `create` is hand-written
+    custom_create: bool,
+    /// Column order of generated `INSERT`s (`sqlx` feature); declaration order if unset
+    column_order: Option<Vec<Ident>>,
+    /// Named constructors: `preset(admin = |f| ..., ...)`
+    presets: Vec<(Ident, Expr)>,
+    /// `invariant = path`: a `fn(&Entity) -> Result<(), String>` every built entity must pass
+    invariant: Option<syn::Path>,
+    /// `after_create = "name"`: an async `Self::name(&Entity, &ConcretePool)` run
+    /// after `create_and_after()` / `build_with_fks_and_after()`
+    after_create: Option<Ident>,
+    /// When true, all generated code is behind `#[cfg(any(test, feature = "factories"))]`
+    test_only: bool,
+    /// When true, also generate `<Entity>Handle` and `create_handle()`
+    handle: bool,
+    /// When true, also generate `<Factory>Persist` and `persist()`
+    persist: bool,
+    /// When true, also generate `<Factory>Parts` and `into_parts()`
+    parts: bool,
+    /// When true, implement `From<&Parent>` for the factory's FK parents, for `as_fk()`
+    fk_from: bool,
+    /// When true, unset FKs to the same parent factory are created with one
+    /// `create_many()` call on it (parent factory must implement `Clone`)
+    batch_fks: bool,
+    /// When true, `build_with_fks()` resolves independent FKs concurrently
+    parallel_fks: bool,
+    /// When true, FK id setters take `impl Into<Id>` (`with_practice_id(42)`)
+    into_setters: bool,
+    /// `phantom(a, b)`: `PhantomData` entity fields the factory doesn't declare,
+    /// filled with `PhantomData` in every build
+    phantom: Vec<Ident>,
+}
+
+/// Parses #[factory(entity = EntityType)], optionally followed by the flags
+/// `typestate`, `partial`, `cache`, `base`, `updates`, `defaults`, `derive_default`, `provenance`,
+/// `variants`, `arb`, `from_map`,
+/// `test_only`, `batch_fks`, `parallel_fks`, `into_setters`, `handle`, `persist`, `parts`, `fk_from`, `extends = BaseFactory`, `mock` / `mock = PoolType`, `table = "..."`,
+/// `upsert = "..."`, `custom_create`, `column_order(a, b, ...)`, `phantom(a, b, ...)`, `preset(name = |f| ..., ...)`,
+/// `invariant = path` and `after_create = "name"`.
+/// Malformed attributes, unknown keys and a missing `entity` are errors.
+fn parse_factory_attr(input: &DeriveInput) -> syn::Result<FactoryAttrInfo> {
+    let mut entity_type = None;
+    let mut typestate = false;
+    let mut partial = false;
+    let mut mock_pool = None;
+    let mut cache = false;
+    let mut base = false;
+    let mut extends = None;
+    let mut updates = false;
+    let mut defaults = false;
+    let mut derive_default = false;
+    let mut provenance = false;
+    let mut variants = false;
+    let mut arb = false;
+    let mut from_map = false;
+    let mut test_only = false;
+    let mut batch_fks = false;
+    let mut parallel_fks = false;
+    let mut into_setters = false;
+    let mut handle = false;
+    let mut persist = false;
+    let mut parts = false;
+    let mut fk_from = false;
+    let mut table = None;
+    let mut upsert = None;
+    let mut custom_create = false;
+    let mut column_order = None;
+    let mut column_order_key = None;
+    let mut phantom = Vec::new();
+    let mut presets = Vec::new();
+    let mut invariant = None;
+    let mut after_create = None;
+
+    for attr in &input.attrs {
+        if attr.path().is_ident("factory") {
+            let items = attr.parse_args_with(parse_factory_attr_items)?;
+
+            for (key, value) in items {
+                use FactoryAttrValue::{List, Named, Str, Type as Ty};
+                match (key.to_string().as_str(), value) {
+                    ("entity", Some(Ty(Type::Path(type_path)))) => {
+                        entity_type = Some(type_path.path);
+                    }
+                    ("typestate", None) => typestate = true,
+                    ("partial", None) => partial = true,
+                    ("cache", None) => cache = true,
+                    ("base", None) => base = true,
+                    ("updates", None) => updates = true,
+                    ("defaults", None) => defaults = true,
+                    ("derive_default", None) => derive_default = true,
+                    ("provenance", None) => provenance = true,
+                    ("variants", None) => variants = true,
+                    ("arb", None) if !cfg!(feature = "proptest") => {
+                        return Err(syn::Error::new_spanned(
+                            &key,
+                            "#[factory(arb)] requires the `proptest` feature of factory-m8-derive",
+                        ));
+                    }
+                    ("arb", None) => arb = true,
+                    ("from_map", None) => from_map = true,
+                    ("test_only", None) => test_only = true,
+                    ("batch_fks", None) => batch_fks = true,
+                    ("parallel_fks", None) => parallel_fks = true,
+                    ("into_setters", None) => into_setters = true,
+                    ("handle", None) => handle = true,
+                    ("persist", None) => persist = true,
+                    ("parts", None) => parts = true,
+                    ("fk_from", None) => fk_from = true,
+                    ("extends", Some(Ty(Type::Path(type_path)))) => {
+                        let base = type_path.path.get_ident().cloned().ok_or_else(|| {
+                            syn::Error::new_spanned(
+                                &type_path,
+                                "extends expects a single identifier, e.g. `extends = BaseFactory`",
+                            )
+                        })?;
+                        extends = Some(base);
+                    }
+                    ("mock", None) => mock_pool = Some(syn::parse_quote! { MockPool }),
+                    ("mock", Some(Ty(Type::Path(type_path)))) => mock_pool = Some(type_path.path),
+                    ("table", Some(Str(lit))) => table = Some(lit),
+                    ("upsert", Some(Str(lit))) => upsert = Some(lit),
+                    ("custom_create", None) => custom_create = true,
+                    ("column_order", Some(List(columns))) => {
+                        column_order = Some(columns);
+                        column_order_key = Some(key.clone());
+                    }
+                    ("phantom", Some(List(fields))) => phantom.extend(fields),
+                    ("preset", Some(Named(named))) => presets.extend(named),
+                    ("invariant", Some(Ty(Type::Path(type_path)))) => {
+                        invariant = Some(type_path.path);
+                    }
+                    ("after_create", Some(Str(lit))) => {
+                        let method = lit.parse::<Ident>().map_err(|_| {
+                            syn::Error::new_spanned(&lit, "after_create expects a method name")
+                        })?;
+                        after_create = Some(method);
+                    }
+                    (name, _) => {
+                        let message = if FACTORY_KEYS.contains(&name) {
+                            format!("unexpected value for #[factory] key `{name}`")
+                        } else {
+                            format!("unknown #[factory] key `{name}`")
+                        };
+                        return Err(syn::Error::new_spanned(&key, message));
+                    }
+                }
+            }
+        }
+    }
+
+    let entity_type = entity_type.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "Factory needs a `#[factory(entity = EntityType)]` attribute naming the entity it builds",
+        )
+    })?;
+    if let Some(upsert) = &upsert {
+        if custom_create {
+            return Err(syn::Error::new_spanned(
+                upsert,
+                "upsert = \"...\" generates create, so it can't be combined with custom_create",
+            ));
+        }
+        if table.is_none() {
+            return Err(syn::Error::new_spanned(
+                upsert,
+                "upsert = \"...\" requires table = \"...\"",
+            ));
+        }
+        if !cfg!(feature = "sqlx") {
+            return Err(syn::Error::new_spanned(
+                upsert,
+                "upsert requires the `sqlx` feature of factory-m8-derive",
+            ));
+        }
+    }
+    if let Some(key) = column_order_key
+        && table.is_none()
+    {
+        return Err(syn::Error::new_spanned(
+            key,
+            "column_order(...) requires table = \"...\"",
+        ));
+    }
+
+    Ok(FactoryAttrInfo {
+        entity_type,
+        typestate,
+        partial,
+        mock_pool,
+        cache,
+        base,
+        extends,
+        updates,
+        defaults,
+        derive_default,
+        provenance,
+        variants,
+        arb,
+        from_map,
+        test_only,
+        batch_fks,
+        parallel_fks,
+        into_setters,
+        handle,
+        persist,
+        parts,
+        fk_from,
+        table,
+        upsert,
+        custom_create,
+        column_order,
+        phantom,
+        presets,
+        invariant,
+        after_create,
+    })
+}
+
+/// Every key #[factory(...)] takes, to tell unknown keys from known ones given
+/// the wrong kind of value
+const FACTORY_KEYS: &[&str] = &[
+    "entity",
+    "typestate",
+    "partial",
+    "cache",
+    "base",
+    "updates",
+    "defaults",
+    "derive_default",
+    "provenance",
+    "variants",
+    "arb",
+    "from_map",
+    "test_only",
+    "batch_fks",
+    "parallel_fks",
+    "into_setters",
+    "handle",
+    "persist",
+    "parts",
+    "fk_from",
+    "extends",
+    "mock",
+    "table",
+    "upsert",
+    "custom_create",
+    "column_order",
+    "phantom",
+    "preset",
+    "invariant",
+    "after_create",
+];
+
+/// Value of a `key = value` item in #[factory(...)]
+#[allow(clippy::large_enum_variant)] // short-lived, only while parsing
+enum FactoryAttrValue {
+    Type(Type),
+    Str(LitStr),
+    /// `key(a, b, c)`
+    List(Vec<Ident>),
+    /// `key(name = expr, ...)`
+    Named(Vec<(Ident, Expr)>),
+}
+
+/// Parses the comma-separated `key` / `key = value` / `key(a, b)` items of #[factory(...)]
+/// (`preset(name = expr, ...)` for presets).
+/// Values are string literals or types (not expressions), so generic entities
+/// like `entity = Event<String>` work.
+fn parse_factory_attr_items(
+    input: syn::parse::ParseStream,
+) -> syn::Result<Vec<(Ident, Option<FactoryAttrValue>)>> {
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        let key: Ident = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            if input.peek(LitStr) {
+                Some(FactoryAttrValue::Str(input.parse()?))
+            } else {
+                Some(FactoryAttrValue::Type(input.parse()?))
+            }
+        } else if input.peek(syn::token::Paren) && key == "preset" {
+            let content;
+            syn::parenthesized!(content in input);
+            let mut named = Vec::new();
+            while !content.is_empty() {
+                let name: Ident = content.parse()?;
+                content.parse::<Token![=]>()?;
+                named.push((name, content.parse::<Expr>()?));
+                if !content.is_empty() {
+                    content.parse::<Token![,]>()?;
+                }
+            }
+            Some(FactoryAttrValue::Named(named))
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let list = content.parse_terminated(<Ident as syn::parse::Parse>::parse, Token![,])?;
+            Some(FactoryAttrValue::List(list.into_iter().collect()))
+        } else {
+            None
+        };
+        items.push((key, value));
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok(items)
 }
 
 /// FK attribute info
 struct FkAttrInfo {
-    entity_type: Ident,
+    /// Parent entity type, optionally path-qualified (`crate::models::Practice`)
+    entity_type: syn::Path,
     entity_field: Ident,
-    factory_type: Ident,
+    /// Reads the referenced value off a parent entity: `id`, or `id()` for a
+    /// getter (`#[fk(Entity, "id()", Factory)]`, e.g. when the field is private)
+    entity_accessor: TokenStream2,
+    /// `by = path`: an `fn(&Entity) -> Id` reading the referenced value instead
+    /// of `entity_accessor`
+    by: Option<syn::Path>,
+    /// Parent factory type, optionally path-qualified (`factories::PracticeFactory`)
+    factory_type: syn::Path,
     /// When true, don't auto-create FK dependency (None stays None for Option fields)
     no_default: bool,
+    /// When true, a failed auto-create leaves the FK as None instead of
+    /// returning the error (Option fields only)
+    null_on_error: bool,
+    /// When true, `create_deduped()` shares one auto-created parent between
+    /// all children whose parent factory config hashes the same
+    dedup: bool,
+    /// When true, a `no_default` FK left unset makes `build_with_fks()` return an
+    /// error instead of resolving to None (for NOT NULL columns)
+    not_null: bool,
+    /// When true, the FK is only auto-created by `build_with_fks_opts()` with
+    /// `create_optionals` set (Option fields only)
+    optional_create: bool,
+    /// When true, an unset FK reuses the parent last created on this thread
+    /// (see `create_last()`) before falling back to auto-creating one
+    use_last: bool,
+    /// `find = path`: an `async fn(&ConcretePool) -> Option<Id>` tried before
+    /// auto-creating, when `build_with_fks` is called with that pool type
+    find: Option<syn::Path>,
+    /// `is_unset = path`: an `fn(&Id) -> bool` deciding when a non-Option FK is
+    /// unset, in place of `is_sentinel()`
+    is_unset: Option<syn::Path>,
+    /// `unset_when = "method"`: a `fn(&self) -> bool` method of the id type
+    /// deciding when a non-Option FK is unset, in place of `is_sentinel()`
+    unset_when: Option<Ident>,
+    /// When true, an auto-created parent is reused by every other `shared` FK to
+    /// the same parent factory within one `build_with_fks()` call
+    shared: bool,
+    /// When true, the FK never auto-creates: it must reference an existing
+    /// parent, which (`sqlx` feature, `sqlx::PgPool`) is fetched to check it
+    require_existing: bool,
+    /// `create_method = "name"`: an async method of the parent factory creating
+    /// the parent instead of `create()`, e.g. a `find_or_create` deduping by a
+    /// natural key
+    create_method: Option<Ident>,
+    /// When true, the `Option<Id>` FK fills a `Vec<Id>` entity field: `Some(id)`
+    /// becomes `vec![id]`, unset becomes empty (Option fields only)
+    into_vec: bool,
+    /// `max_depth = N`: how many parents this FK auto-creates in a chain before
+    /// it resolves to None (or, for non-Option FKs, an error), so self-referential
+    /// FKs terminate
+    max_depth: Option<usize>,
 }
 
-/// Parses #[fk(EntityType, "field", FactoryType)] or #[fk(EntityType, "field", FactoryType, no_default)]
+/// Parses #[fk(EntityType, "field", FactoryType)], optionally followed by the
+/// flags `no_default`, `null_on_error`, `dedup`, `not_null`, `optional_create`, `use_last`,
+/// `into_vec`, `shared`, `require_existing`, `create_method = "name"`, `max_depth = N` and
+/// `find = path` / `is_unset = path` / `unset_when = "method"` / `by = path`
 ///
 /// The optionality of the FK is determined by the field type:
 /// - `Option<T>`: Optional FK, auto-creates if None/sentinel (unless `no_default` is set)
 /// - `T` (non-Option): Required FK, auto-creates if is_sentinel()
 ///
 /// The `no_default` flag prevents auto-creation: None/sentinel stays None for Option fields.
+/// The `null_on_error` flag makes auto-creation best-effort: if the parent's `create`
+/// fails, the FK resolves to None.
+/// The `not_null` flag makes an unset `no_default` FK an error in `build_with_fks()`.
+/// The `optional_create` flag only auto-creates when `build_with_fks_opts()` is
+/// called with `create_optionals: true`; otherwise it behaves like `no_default`.
+/// The `use_last` flag reuses the parent factory's last-created entity on this thread.
+/// The `shared` flag creates one parent per call for all `shared` FKs to the same factory.
+/// The `require_existing` flag never auto-creates and checks that the parent exists.
+/// `find = path` looks up an existing parent id before auto-creating.
+/// `is_unset = path` (or `unset_when = "method"`, called on the id) replaces
+/// `is_sentinel()` as the unset check of a non-Option FK.
+/// `by = path` reads the referenced value off a parent entity with a function.
+/// The `into_vec` flag maps an `Option<Id>` FK onto a `Vec<Id>` entity field.
+/// `create_method = "name"` creates the parent with that factory method instead of `create()`.
+/// `max_depth = N` stops auto-creating after N nested parents in this task.
+/// A malformed #[fk] isn't an FK; `validate_field_attrs` reports it.
 fn parse_fk_attr(field: &Field) -> Option<FkAttrInfo> {
+    try_parse_fk_attr(field)?.ok()
+}
+
+/// Parses the field's #[fk(...)], if it has one, keeping the parse error
+fn try_parse_fk_attr(field: &Field) -> Option<syn::Result<FkAttrInfo>> {
     for attr in &field.attrs {
         if attr.path().is_ident("fk") {
             let result = attr.parse_args_with(|input: syn::parse::ParseStream| {
-                let entity_type: Ident = input.parse()?;
+                let entity_type: syn::Path = input.parse()?;
                 input.parse::<Token![,]>()?;
                 let field_name_lit: LitStr = input.parse()?;
-                let entity_field = Ident::new(&field_name_lit.value(), field_name_lit.span());
+                let field_name = field_name_lit.value();
+                let (entity_field, entity_accessor) = match field_name.strip_suffix("()") {
+                    Some(getter) => {
+                        let getter = Ident::new(getter, field_name_lit.span());
+                        (getter.clone(), quote! { #getter() })
+                    }
+                    None => {
+                        let field = Ident::new(&field_name, field_name_lit.span());
+                        (field.clone(), quote! { #field })
+                    }
+                };
                 input.parse::<Token![,]>()?;
-                let factory_type: Ident = input.parse()?;
+                let factory_type: syn::Path = input.parse()?;
 
-                // Check for no_default flag
-                let no_default = if input.peek(Token![,]) {
+                // Check for flags
+                let mut no_default = false;
+                let mut null_on_error = false;
+                let mut dedup = false;
+                let mut not_null = false;
+                let mut optional_create = false;
+                let mut use_last = false;
+                let mut find = None;
+                let mut is_unset = None;
+                let mut unset_when = None;
+                let mut by = None;
+                let mut into_vec = false;
+                let mut create_method = None;
+                let mut shared = false;
+                let mut require_existing = false;
+                let mut max_depth = None;
+                while input.peek(Token![,]) {
                     input.parse::<Token![,]>()?;
                     let flag: Ident = input.parse()?;
-                    flag == "no_default"
-                } else {
-                    false
-                };
+                    if flag == "no_default" {
+                        no_default = true;
+                    } else if flag == "null_on_error" {
+                        null_on_error = true;
+                    } else if flag == "dedup" {
+                        dedup = true;
+                    } else if flag == "not_null" {
+                        not_null = true;
+                    } else if flag == "optional_create" {
+                        optional_create = true;
+                    } else if flag == "use_last" {
+                        use_last = true;
+                    } else if flag == "into_vec" {
+                        into_vec = true;
+                    } else if flag == "shared" {
+                        shared = true;
+                    } else if flag == "require_existing" {
+                        require_existing = true;
+                    } else if flag == "find" {
+                        input.parse::<Token![=]>()?;
+                        find = Some(input.parse::<syn::Path>()?);
+                    } else if flag == "is_unset" {
+                        input.parse::<Token![=]>()?;
+                        is_unset = Some(input.parse::<syn::Path>()?);
+                    } else if flag == "unset_when" {
+                        input.parse::<Token![=]>()?;
+                        let method: LitStr = input.parse()?;
+                        unset_when = Some(method.parse::<Ident>()?);
+                    } else if flag == "by" {
+                        input.parse::<Token![=]>()?;
+                        by = Some(input.parse::<syn::Path>()?);
+                    } else if flag == "create_method" {
+                        input.parse::<Token![=]>()?;
+                        let method: LitStr = input.parse()?;
+                        create_method = Some(method.parse::<Ident>()?);
+                    } else if flag == "max_depth" {
+                        input.parse::<Token![=]>()?;
+                        max_depth = Some(input.parse::<LitInt>()?.base10_parse()?);
+                    } else {
+                        return Err(syn::Error::new_spanned(
+                            &flag,
+                            format!("unknown #[fk] flag `{flag}`"),
+                        ));
+                    }
+                }
 
                 Ok(FkAttrInfo {
                     entity_type,
                     entity_field,
+                    entity_accessor,
+                    by,
                     factory_type,
                     no_default,
+                    null_on_error,
+                    dedup,
+                    not_null,
+                    optional_create,
+                    use_last,
+                    find,
+                    is_unset,
+                    unset_when,
+                    into_vec,
+                    create_method,
+                    shared,
+                    require_existing,
+                    max_depth,
                 })
             });
-            return result.ok();
+            let result = result.and_then(|fk_info| match fk_flag_conflict(field, &fk_info) {
+                Some(message) => Err(syn::Error::new_spanned(attr, message)),
+                None => Ok(fk_info),
+            });
+            return Some(result);
         }
     }
     None
 }
 
-/// Checks if field has a specific attribute
-fn has_attr(field: &Field, name: &str) -> bool {
-    field.attrs.iter().any(|a| a.path().is_ident(name))
+/// Why the flags of a parsed #[fk(...)] don't fit together or don't fit the
+/// field's type, if they don't
+fn fk_flag_conflict(field: &Field, fk_info: &FkAttrInfo) -> Option<String> {
+    let field_name = field.ident.as_ref()?;
+    let custom_unset_check = fk_info.is_unset.is_some() || fk_info.unset_when.is_some();
+    if custom_unset_check && is_option_type(&field.ty) {
+        return Some(format!(
+            "{field_name}: is_unset and unset_when are only supported on non-Option FK fields"
+        ));
+    }
+    if !is_option_type(&field.ty) {
+        let option_only = [
+            (fk_info.null_on_error, "null_on_error"),
+            (fk_info.optional_create, "optional_create"),
+            (fk_info.into_vec, "into_vec"),
+        ];
+        if let Some((_, flag)) = option_only.iter().find(|(used, _)| *used) {
+            return Some(format!(
+                "{field_name}: {flag} is only supported on Option<T> FK fields"
+            ));
+        }
+    }
+    if fk_info.require_existing {
+        let creating = [
+            (fk_info.no_default, "no_default"),
+            (fk_info.null_on_error, "null_on_error"),
+            (fk_info.optional_create, "optional_create"),
+            (fk_info.dedup, "dedup"),
+            (fk_info.use_last, "use_last"),
+            (fk_info.shared, "shared"),
+            (fk_info.find.is_some(), "find"),
+            (fk_info.create_method.is_some(), "create_method"),
+        ];
+        if let Some((_, flag)) = creating.iter().find(|(used, _)| *used) {
+            return Some(format!(
+                "{field_name}: require_existing can't be combined with {flag}: the parent is \
+                 never created"
+            ));
+        }
+    }
+    if extract_vec_inner_type(&field.ty).is_some() && !field_name.to_string().ends_with("_ids") {
+        return Some(format!(
+            "{field_name}: Vec FK field names must end in `_ids`"
+        ));
+    }
+    if fk_info.use_last {
+        let conflicting = [
+            (fk_info.no_default, "no_default"),
+            (fk_info.null_on_error, "null_on_error"),
+            (fk_info.optional_create, "optional_create"),
+            (fk_info.dedup, "dedup"),
+            (fk_info.shared, "shared"),
+        ];
+        if let Some((_, flag)) = conflicting.iter().find(|(used, _)| *used) {
+            return Some(format!(
+                "{field_name}: use_last can't be combined with {flag}"
+            ));
+        }
+    }
+    if fk_info.find.is_some() {
+        let conflicting = [
+            (fk_info.no_default, "no_default"),
+            (fk_info.null_on_error, "null_on_error"),
+            (fk_info.optional_create, "optional_create"),
+            (fk_info.dedup, "dedup"),
+        ];
+        if let Some((_, flag)) = conflicting.iter().find(|(used, _)| *used) {
+            return Some(format!("{field_name}: find can't be combined with {flag}"));
+        }
+    }
+    if fk_info.max_depth.is_some() {
+        let conflicting = [
+            (fk_info.no_default, "no_default"),
+            (fk_info.null_on_error, "null_on_error"),
+            (fk_info.optional_create, "optional_create"),
+            (fk_info.use_last, "use_last"),
+        ];
+        if let Some((_, flag)) = conflicting.iter().find(|(used, _)| *used) {
+            return Some(format!(
+                "{field_name}: max_depth can't be combined with {flag}"
+            ));
+        }
+    }
+    if fk_info.dedup && fk_info.no_default {
+        return Some(format!(
+            "{field_name}: dedup can't be combined with no_default"
+        ));
+    }
+    if fk_info.is_unset.is_some() && fk_info.unset_when.is_some() {
+        return Some(format!(
+            "{field_name}: use either is_unset or unset_when, not both"
+        ));
+    }
+    None
 }
 
-// =============================================================================
-// CODE GENERATION: with_* methods for FK fields
-// =============================================================================
-
-/// Generates two with methods for FK fields:
-/// - with_<entity>(&Entity) - sets ID from entity reference
-/// - with_<field>_id(Id) - sets ID directly
-///
-/// Supports both Option<IdType> and IdType FK fields.
-fn generate_fk_with_methods(field: &Field) -> Vec<TokenStream2> {
-    let field_name = field.ident.as_ref().unwrap();
-    let fk_info = parse_fk_attr(field).unwrap();
-
-    let entity_type = &fk_info.entity_type;
-    let entity_field = &fk_info.entity_field;
+/// Reads the referenced value off `entity` (a parent entity or a reference to
+/// one): through `by = path` if set, otherwise the field or getter named in the
+/// attribute.
+fn fk_read_id(fk_info: &FkAttrInfo, entity: TokenStream2) -> TokenStream2 {
+    match &fk_info.by {
+        Some(by) => quote! { #by(std::borrow::Borrow::borrow(&#entity)) },
+        None => {
+            let entity_accessor = &fk_info.entity_accessor;
+            quote! { #entity.#entity_accessor }
+        }
+    }
+}
 
-    // Method name: practice_id -> with_practice
-    let entity_method_name = fk_method_name(field_name);
-    // Method name: practice_id -> with_practice_id
-    let id_method_name = format_ident!("with_{}", field_name);
+/// The awaited call creating an FK's parent, evaluating to its `Result`:
+/// `FactoryCreate::create()`, or with `create_method = "name"` that method of
+/// the parent factory, which takes a concrete pool type (inferred from its
+/// signature) and is only called when `Pool` is that type.
+fn fk_create_call(fk_info: &FkAttrInfo) -> TokenStream2 {
+    let factory_type = &fk_info.factory_type;
+    let create = quote! {
+        {
+            use factory_m8::FactoryCreate;
+            #factory_type::new().create(pool).await
+        }
+    };
+    match &fk_info.create_method {
+        Some(method) => quote! {
+            match (pool as &dyn std::any::Any).downcast_ref() {
+                Some(typed_pool) => #factory_type::new().#method(typed_pool).await,
+                None => #create,
+            }
+        },
+        None => create,
+    }
+}
 
-    // Check if FK field is Option<IdType> or just IdType
-    if let Some(id_type) = extract_option_inner_type(&field.ty) {
-        // Option<IdType> - wrap in Some
-        vec![
-            quote! {
-                /// Set FK from entity reference.
-                pub fn #entity_method_name(mut self, entity: &#entity_type) -> Self {
-                    self.#field_name = Some(entity.#entity_field);
-                    self
-                }
-            },
-            quote! {
-                /// Set FK ID directly.
-                pub fn #id_method_name(mut self, id: #id_type) -> Self {
-                    self.#field_name = Some(id);
-                    self
-                }
-            },
-        ]
-    } else {
-        // Non-Option IdType - use directly
-        let field_type = &field.ty;
-        vec![
-            quote! {
-                /// Set FK from entity reference.
-                pub fn #entity_method_name(mut self, entity: &#entity_type) -> Self {
-                    self.#field_name = entity.#entity_field;
-                    self
-                }
-            },
-            quote! {
-                /// Set FK ID directly.
-                pub fn #id_method_name(mut self, id: #field_type) -> Self {
-                    self.#field_name = id;
-                    self
-                }
-            },
-        ]
+/// The condition under which an FK field counts as unset: its `is_unset = path`
+/// predicate or `unset_when = "method"` (non-Option FKs only), or
+/// `Sentinel::is_sentinel()`, which for `Option<T>` also covers `None`.
+fn fk_unset_check(field: &Field, fk_info: &FkAttrInfo, value: TokenStream2) -> TokenStream2 {
+    match (&fk_info.is_unset, &fk_info.unset_when) {
+        (Some(is_unset), _) => quote! { #is_unset(&#value) },
+        (None, Some(unset_when)) => quote! { #value.#unset_when() },
+        (None, None) if is_pointer_fk(field) && is_option_type(&field.ty) => {
+            fk_option_unset_check(field, value)
+        }
+        (None, None) if is_pointer_fk(field) => {
+            quote! { factory_m8::Sentinel::is_sentinel(&*#value) }
+        }
+        (None, None) => quote! { factory_m8::Sentinel::is_sentinel(&#value) },
     }
 }
 
-/// Converts FK field name to entity method name:
-/// - practice_id -> with_practice
-/// - procedure_id_origin -> with_procedure_origin (replaces _id_ with _)
-/// - tenant_id -> with_tenant
-fn fk_method_name(field_name: &Ident) -> Ident {
-    let name = field_name.to_string();
-    // First try stripping _id suffix (common case like practice_id)
-    if let Some(stripped) = name.strip_suffix("_id") {
-        return format_ident!("with_{}", stripped);
+/// Parses #[enum_repr] or #[enum_repr(ReprType)], returning the repr type
+/// (defaults to `i32`)
+fn parse_enum_repr_attr(field: &Field) -> Option<Type> {
+    let attr = field
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident("enum_repr"))?;
+    match &attr.meta {
+        Meta::List(_) => attr.parse_args::<Type>().ok(),
+        _ => Some(syn::parse_quote! { i32 }),
     }
-    // Otherwise replace _id_ with _ (for fields like procedure_id_origin)
-    let stripped = name.replace("_id_", "_");
-    format_ident!("with_{}", stripped)
 }
 
-// =============================================================================
-// CODE GENERATION: with_* methods for Option non-FK fields
-// =============================================================================
+/// Bounds from #[range(min = 0, max = 120)]; either may be omitted
+struct RangeAttrInfo {
+    min: Option<Expr>,
+    max: Option<Expr>,
+}
 
-fn generate_option_with_method(field: &Field) -> TokenStream2 {
-    let field_name = field.ident.as_ref().unwrap();
-    let field_type = &field.ty;
-    let method_name = format_ident!("with_{}", field_name);
+/// Parses #[range(min = .., max = ..)] on an integer field
+fn parse_range_attr(field: &Field) -> Option<RangeAttrInfo> {
+    try_parse_range_attr(find_attr(field, "range")?).ok()
+}
+
+/// Parses the bounds of a #[range(...)] attribute, keeping the parse error
+fn try_parse_range_attr(attr: &syn::Attribute) -> syn::Result<RangeAttrInfo> {
+    let nested = attr
+        .parse_args_with(syn::punctuated::Punctuated::<Meta, Token![,]>::parse_terminated)
+        .map_err(|e| syn::Error::new(e.span(), format!("invalid #[range]: {e}")))?;
+
+    let mut range = RangeAttrInfo {
+        min: None,
+        max: None,
+    };
+    for meta in nested {
+        match meta {
+            Meta::NameValue(nv) if nv.path.is_ident("min") => range.min = Some(nv.value),
+            Meta::NameValue(nv) if nv.path.is_ident("max") => range.max = Some(nv.value),
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `min = ..` or `max = ..`",
+                ));
+            }
+        }
+    }
+    if range.min.is_none() && range.max.is_none() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "#[range] needs `min`, `max` or both",
+        ));
+    }
+    Ok(range)
+}
+
+/// Parses #[clamp(min, max)] on an integer field, returning both bounds
+fn parse_clamp_attr(field: &Field) -> Option<(Expr, Expr)> {
+    try_parse_clamp_attr(find_attr(field, "clamp")?).ok()
+}
+
+/// Parses the bounds of a #[clamp(min, max)] attribute
+fn try_parse_clamp_attr(attr: &syn::Attribute) -> syn::Result<(Expr, Expr)> {
+    let bounds = attr
+        .parse_args_with(syn::punctuated::Punctuated::<Expr, Token![,]>::parse_terminated)
+        .map_err(|e| syn::Error::new(e.span(), format!("invalid #[clamp]: {e}")))?;
+    let mut bounds = bounds.into_iter();
+    match (bounds.next(), bounds.next(), bounds.next()) {
+        (Some(min), Some(max), None) => Ok((min, max)),
+        _ => Err(syn::Error::new_spanned(
+            attr,
+            "#[clamp] takes exactly two bounds, #[clamp(min, max)]",
+        )),
+    }
+}
+
+/// Parses every #[alias("old_name")] on a field
+fn parse_alias_attrs(field: &Field) -> Vec<LitStr> {
+    field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("alias"))
+        .filter_map(|a| a.parse_args::<LitStr>().ok())
+        .collect()
+}
+
+/// Parses #[env("VAR_NAME")], returning the environment variable name
+fn parse_env_attr(field: &Field) -> Option<LitStr> {
+    field
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident("env"))
+        .and_then(|a| a.parse_args::<LitStr>().ok())
+}
+
+/// Parses #[template("user-{i}")], returning the template
+fn parse_template_attr(field: &Field) -> Option<LitStr> {
+    find_attr(field, "template")?.parse_args::<LitStr>().ok()
+}
+
+/// Parses #[seq] or #[seq("{n}")], returning the placeholder the counter
+/// replaces (defaults to `{}`)
+fn parse_seq_attr(field: &Field) -> Option<LitStr> {
+    try_parse_seq_attr(find_attr(field, "seq")?).ok()
+}
+
+/// Parses a #[seq] or #[seq("{n}")] attribute, keeping the parse error
+fn try_parse_seq_attr(attr: &syn::Attribute) -> syn::Result<LitStr> {
+    match &attr.meta {
+        Meta::List(_) => attr
+            .parse_args::<LitStr>()
+            .map_err(|e| syn::Error::new(e.span(), format!("invalid #[seq]: {e}"))),
+        _ => Ok(LitStr::new("{}", proc_macro2::Span::call_site())),
+    }
+}
+
+/// Parses #[nested_builder(BuilderType)], returning the builder type
+fn parse_nested_builder_attr(field: &Field) -> Option<syn::Path> {
+    field
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident("nested_builder"))
+        .and_then(|a| a.parse_args::<syn::Path>().ok())
+}
+
+/// Parses #[group("name")], returning the group name
+fn parse_group_attr(field: &Field) -> Option<LitStr> {
+    field
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident("group"))
+        .and_then(|a| a.parse_args::<LitStr>().ok())
+}
+
+/// FK field and parent entity field from #[from_fk(parent = "practice_id", field = "tenant_id")]
+struct FromFkAttrInfo {
+    parent: Ident,
+    field: Ident,
+}
+
+/// Parses #[from_fk(parent = "fk_field", field = "parent_field")]
+fn parse_from_fk_attr(field: &Field) -> Option<FromFkAttrInfo> {
+    try_parse_from_fk_attr(find_attr(field, "from_fk")?).ok()
+}
+
+/// Parses a #[from_fk(...)] attribute, keeping the parse error
+fn try_parse_from_fk_attr(attr: &syn::Attribute) -> syn::Result<FromFkAttrInfo> {
+    let nested = attr
+        .parse_args_with(syn::punctuated::Punctuated::<Meta, Token![,]>::parse_terminated)
+        .map_err(|e| syn::Error::new(e.span(), format!("invalid #[from_fk]: {e}")))?;
+
+    let mut parent = None;
+    let mut parent_field = None;
+    for meta in nested {
+        if let Meta::NameValue(nv) = meta
+            && let Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) = &nv.value
+        {
+            if nv.path.is_ident("parent") {
+                parent = Some(lit.parse::<Ident>()?);
+            } else if nv.path.is_ident("field") {
+                parent_field = Some(lit.parse::<Ident>()?);
+            }
+        }
+    }
+    match (parent, parent_field) {
+        (Some(parent), Some(field)) => Ok(FromFkAttrInfo { parent, field }),
+        _ => Err(syn::Error::new_spanned(
+            attr,
+            "#[from_fk] needs `parent = \"fk_field\"` and `field = \"parent_field\"`",
+        )),
+    }
+}
+
+/// Child factory and back-reference from #[has_many(NoteFactory, fk = patient_id)]
+struct HasManyAttrInfo {
+    factory_type: syn::Path,
+    /// The child's FK field pointing at the parent; `<entity>_id` if omitted
+    fk: Option<Ident>,
+}
+
+/// Parses #[has_many(ChildFactory)] or #[has_many(ChildFactory, fk = field)]
+fn parse_has_many_attr(field: &Field) -> Option<HasManyAttrInfo> {
+    try_parse_has_many_attr(find_attr(field, "has_many")?).ok()
+}
+
+/// Parses a #[has_many(...)] attribute, keeping the parse error
+fn try_parse_has_many_attr(attr: &syn::Attribute) -> syn::Result<HasManyAttrInfo> {
+    attr.parse_args_with(|input: syn::parse::ParseStream| {
+        let factory_type: syn::Path = input.parse()?;
+        let mut fk = None;
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            let key: Ident = input.parse()?;
+            if key != "fk" {
+                return Err(syn::Error::new_spanned(&key, "expected `fk = field`"));
+            }
+            input.parse::<Token![=]>()?;
+            fk = Some(input.parse::<Ident>()?);
+        }
+        Ok(HasManyAttrInfo { factory_type, fk })
+    })
+    .map_err(|e| syn::Error::new(e.span(), format!("invalid #[has_many]: {e}")))
+}
+
+/// The field's `///` doc comments, followed by a blank doc line, for prefixing
+/// the docs of its generated setters (empty if the field has no docs)
+fn field_docs(field: &Field) -> TokenStream2 {
+    let docs: Vec<&syn::Attribute> = field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .collect();
+    if docs.is_empty() {
+        return quote! {};
+    }
+    quote! {
+        #(#docs)*
+        #[doc = ""]
+    }
+}
+
+/// A field's `#[deprecated]` attribute, re-emitted on its generated setters so
+/// callers get the deprecation warning
+fn field_deprecation(field: &Field) -> TokenStream2 {
+    let deprecated = field.attrs.iter().filter(|a| a.path().is_ident("deprecated"));
+    quote! { #(#deprecated)* }
+}
+
+/// Checks if field has a specific attribute
+fn has_attr(field: &Field, name: &str) -> bool {
+    field.attrs.iter().any(|a| a.path().is_ident(name))
+}
+
+/// The field's first attribute with the given name
+fn find_attr<'a>(field: &'a Field, name: &str) -> Option<&'a syn::Attribute> {
+    field.attrs.iter().find(|a| a.path().is_ident(name))
+}
+
+/// Parses #[pk(unset_value = expr)], the pk of entities that aren't inserted yet
+fn parse_pk_unset_value(field: &Field) -> Option<Expr> {
+    try_parse_pk_attr(find_attr(field, "pk")?).ok()?
+}
+
+/// Parses a #[pk] or #[pk(unset_value = expr)] attribute, keeping the parse error
+fn try_parse_pk_attr(attr: &syn::Attribute) -> syn::Result<Option<Expr>> {
+    let Meta::List(_) = &attr.meta else {
+        return Ok(None);
+    };
+    let nv = attr
+        .parse_args::<syn::MetaNameValue>()
+        .map_err(|e| syn::Error::new(e.span(), format!("invalid #[pk(...)]: {e}")))?;
+    if !nv.path.is_ident("unset_value") {
+        return Err(syn::Error::new_spanned(
+            &nv.path,
+            "expected #[pk(unset_value = expr)]",
+        ));
+    }
+    Ok(Some(nv.value))
+}
+
+/// The pk value of a built entity: `unset_value`, else `Default::default()`
+fn pk_unset_value(field: &Field) -> TokenStream2 {
+    match parse_pk_unset_value(field) {
+        Some(value) => quote! { #value },
+        None => quote! { Default::default() },
+    }
+}
+
+/// Generates `set_<name>(&mut self, ..) -> &mut Self`, the in-place twin of the
+/// consuming `with_<name>(mut self, ..) -> Self` setter, running the same `body`.
+/// Handy when a factory is configured across several `if` blocks.
+fn generate_set_method(
+    with_method_name: &Ident,
+    attrs: &TokenStream2,
+    params: TokenStream2,
+    body: TokenStream2,
+) -> TokenStream2 {
+    let name = with_method_name.to_string();
+    let set_method_name = format_ident!("set_{}", name.strip_prefix("with_").unwrap());
+    let doc = format!("Same as `{name}`, but sets the value in place.");
+    quote! {
+        #attrs
+        #[doc = #doc]
+        pub fn #set_method_name(&mut self, #params) -> &mut Self {
+            #body
+            self
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: with_* methods for FK fields
+// =============================================================================
+
+/// Generates two with methods for FK fields:
+/// - with_<entity>(impl Borrow<Entity>) - sets ID from an entity (`&Entity`, `Box<Entity>`, ...)
+/// - with_<field>_id(Id) - sets ID directly
+///
+/// Option FK fields also get with_<field>_id_opt(Option<Id>), assigned as-is.
+/// Every FK gets with_<entity>_entity(Option<Entity>), which sets the ID from
+/// the entity when one is given and otherwise leaves the FK to normal resolution.
+/// The first two also get in-place `set_<entity>` / `set_<field>_id` twins.
+///
+/// Supports both Option<IdType> and IdType FK fields. Ids stored behind a
+/// `Box`/`Arc`/`Rc` are still set from the plain id type.
+fn generate_fk_with_methods(field: &Field, into_setters: bool) -> Vec<TokenStream2> {
+    let field_name = field.ident.as_ref().unwrap();
+    let fk_info = parse_fk_attr(field).unwrap();
+
+    let entity_type = &fk_info.entity_type;
+    let entity_field = &fk_info.entity_field;
+    let entity_read = fk_read_id(&fk_info, quote! { entity });
+
+    // Method name: practice_id -> with_practice
+    let entity_method_name = fk_method_name(field_name, entity_field);
+    // Method name: practice_id -> with_practice_id
+    let id_method_name = format_ident!("with_{}", field_name);
+    let id_opt_method_name = format_ident!("with_{}_opt", field_name);
+    let entity_opt_method_name = format_ident!("{}_entity", entity_method_name);
+
+    // Reading the id out of the borrowed entity needs a clone for non-Copy id types.
+    // Ids behind a pointer (`Arc<Id>`) are set from plain ids and wrapped here
+    let id_type = fk_id_type(field);
+    let entity_id = if needs_clone(id_type) {
+        wrap_fk_id(field, quote! { #entity_read.clone() })
+    } else {
+        wrap_fk_id(field, quote! { #entity_read })
+    };
+
+    // #[factory(into_setters)]: with_<field>(impl Into<Id>), e.g. from a raw i64
+    let (id_param, id_value) = if into_setters {
+        (
+            quote! { impl Into<#id_type> },
+            wrap_fk_id(field, quote! { id.into() }),
+        )
+    } else {
+        (quote! { #id_type }, wrap_fk_id(field, quote! { id }))
+    };
+    let id_opt_value = if is_pointer_fk(field) {
+        let slot_type = fk_slot_type(field);
+        quote! { id.map(<#slot_type>::from) }
+    } else {
+        quote! { id }
+    };
+
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+    let attrs = quote! { #docs #deprecation };
+
+    let entity_opt_method = quote! {
+        #docs
+        #deprecation
+        /// Set FK from an optional parent entity; `None` keeps the current FK,
+        /// so an unset FK is auto-created as usual.
+        pub fn #entity_opt_method_name(self, entity: Option<#entity_type>) -> Self {
+            match entity {
+                Some(entity) => self.#entity_method_name(&entity),
+                None => self,
+            }
+        }
+    };
+
+    // Check if FK field is Option<IdType> or just IdType
+    if is_option_type(&field.ty) {
+        // Option<IdType> - wrap in Some
+        let set_entity_method = generate_set_method(
+            &entity_method_name,
+            &attrs,
+            quote! { entity: impl std::borrow::Borrow<#entity_type> },
+            quote! {
+                let entity: &#entity_type = entity.borrow();
+                self.#field_name = Some(#entity_id);
+            },
+        );
+        let set_id_method = generate_set_method(
+            &id_method_name,
+            &attrs,
+            quote! { id: #id_param },
+            quote! { self.#field_name = Some(#id_value); },
+        );
+        vec![
+            quote! {
+                #docs
+                #deprecation
+                /// Set FK from an entity (`&Entity`, `Box<Entity>`, or anything else
+                /// that borrows as one).
+                pub fn #entity_method_name(
+                    mut self,
+                    entity: impl std::borrow::Borrow<#entity_type>,
+                ) -> Self {
+                    let entity: &#entity_type = entity.borrow();
+                    self.#field_name = Some(#entity_id);
+                    self
+                }
+            },
+            quote! {
+                #docs
+                #deprecation
+                /// Set FK ID directly.
+                pub fn #id_method_name(mut self, id: #id_param) -> Self {
+                    self.#field_name = Some(#id_value);
+                    self
+                }
+            },
+            quote! {
+                #docs
+                #deprecation
+                /// Set FK ID from an already-optional id; `None` leaves it for auto-creation.
+                pub fn #id_opt_method_name(mut self, id: Option<#id_type>) -> Self {
+                    self.#field_name = #id_opt_value;
+                    self
+                }
+            },
+            entity_opt_method,
+            set_entity_method,
+            set_id_method,
+        ]
+    } else {
+        // Non-Option IdType - use directly
+        let set_entity_method = generate_set_method(
+            &entity_method_name,
+            &attrs,
+            quote! { entity: impl std::borrow::Borrow<#entity_type> },
+            quote! {
+                let entity: &#entity_type = entity.borrow();
+                self.#field_name = #entity_id;
+            },
+        );
+        let set_id_method = generate_set_method(
+            &id_method_name,
+            &attrs,
+            quote! { id: #id_param },
+            quote! { self.#field_name = #id_value; },
+        );
+        vec![
+            quote! {
+                #docs
+                #deprecation
+                /// Set FK from an entity (`&Entity`, `Box<Entity>`, or anything else
+                /// that borrows as one).
+                pub fn #entity_method_name(
+                    mut self,
+                    entity: impl std::borrow::Borrow<#entity_type>,
+                ) -> Self {
+                    let entity: &#entity_type = entity.borrow();
+                    self.#field_name = #entity_id;
+                    self
+                }
+            },
+            quote! {
+                #docs
+                #deprecation
+                /// Set FK ID directly.
+                pub fn #id_method_name(mut self, id: #id_param) -> Self {
+                    self.#field_name = #id_value;
+                    self
+                }
+            },
+            entity_opt_method,
+            set_entity_method,
+            set_id_method,
+        ]
+    }
+}
+
+/// Generates the setters of a `Vec<Id>` FK field (`tag_ids: Vec<TagId>`):
+/// - with_<field>(Vec<Id>) - sets the IDs directly
+/// - with_<entities>(&[&Entity]) - sets the IDs of all entities (tag_ids -> with_tags)
+/// - add_<entity>(&Entity) - appends one entity's ID (tag_ids -> add_tag)
+fn generate_vec_fk_with_methods(field: &Field) -> Vec<TokenStream2> {
+    let field_name = field.ident.as_ref().unwrap();
+    let fk_info = parse_fk_attr(field).unwrap();
+    let entity_type = &fk_info.entity_type;
+    let entity_read = fk_read_id(&fk_info, quote! { entity });
+    let id_type = extract_vec_inner_type(&field.ty).unwrap();
+
+    // `fk_flag_conflict` checks the `_ids` suffix
+    let field_name_str = field_name.to_string();
+    let relation = field_name_str
+        .strip_suffix("_ids")
+        .unwrap_or(&field_name_str);
+    let ids_method_name = format_ident!("with_{}", field_name);
+    let entities_method_name = format_ident!("with_{}s", relation);
+    let add_method_name = format_ident!("add_{}", relation);
+
+    let entity_id = if needs_clone(id_type) {
+        quote! { #entity_read.clone() }
+    } else {
+        quote! { #entity_read }
+    };
+
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+
+    vec![
+        quote! {
+            #docs
+            #deprecation
+            /// Set FK IDs directly.
+            pub fn #ids_method_name(mut self, ids: Vec<#id_type>) -> Self {
+                self.#field_name = ids;
+                self
+            }
+        },
+        quote! {
+            #docs
+            #deprecation
+            /// Set FK IDs from entity references, in order.
+            pub fn #entities_method_name(mut self, entities: &[&#entity_type]) -> Self {
+                self.#field_name = entities.iter().map(|entity| #entity_id).collect();
+                self
+            }
+        },
+        quote! {
+            #docs
+            #deprecation
+            /// Append one entity's ID to the FK IDs.
+            pub fn #add_method_name(
+                mut self,
+                entity: impl std::borrow::Borrow<#entity_type>,
+            ) -> Self {
+                let entity: &#entity_type = entity.borrow();
+                self.#field_name.push(#entity_id);
+                self
+            }
+        },
+    ]
+}
+
+/// Converts FK field name to entity method name:
+/// - practice_id -> with_practice
+/// - procedure_id_origin -> with_procedure_origin (replaces _id_ with _)
+/// - tenant_id -> with_tenant
+fn fk_method_name(field_name: &Ident, entity_field: &Ident) -> Ident {
+    format_ident!("with_{}", fk_relation_name(field_name, entity_field))
+}
+
+/// Converts FK field name to the relation name used in generated identifiers:
+/// - practice_id -> practice
+/// - department_code -> department (FK to a non-pk `code` column)
+/// - practice_ids -> practice (an `into_vec` FK named after its `Vec` column)
+/// - procedure_id_origin -> procedure_origin (replaces _id_ with _)
+fn fk_relation_name(field_name: &Ident, entity_field: &Ident) -> String {
+    let name = field_name.to_string();
+    // First try stripping _id suffix (common case like practice_id)
+    if let Some(stripped) = name.strip_suffix("_id") {
+        return stripped.to_string();
+    }
+    // Then the referenced column, so the entity setter doesn't clash with the id setter
+    if let Some(stripped) = name.strip_suffix(&format!("_{entity_field}")) {
+        return stripped.to_string();
+    }
+    // Or its plural, for into_vec FKs filling a Vec column
+    if let Some(stripped) = name.strip_suffix(&format!("_{entity_field}s")) {
+        return stripped.to_string();
+    }
+    // Otherwise replace _id_ with _ (for fields like procedure_id_origin)
+    name.replace("_id_", "_")
+}
+
+// =============================================================================
+// CODE GENERATION: with_* methods for Option non-FK fields
+// =============================================================================
+
+fn generate_option_with_method(field: &Field) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+    let field_type = &field.ty;
+    let method_name = format_ident!("with_{}", field_name);
+    let merge_method_name = format_ident!("merge_{}", field_name);
+    let opt_method_name = format_ident!("with_{}_opt", field_name);
+
+    let inner_type = extract_option_inner_type(field_type).expect("Option field must be Option<T>");
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+
+    // merge_<field>(Option<T>) goes through with_<field>, so conversions and
+    // #[range] checks apply to merged values too
+    let merge_method = quote! {
+        #docs
+        #deprecation
+        /// Set optional field value only if `value` is `Some`, keeping the
+        /// current value otherwise.
+        pub fn #merge_method_name(self, value: Option<#inner_type>) -> Self {
+            match value {
+                Some(value) => self.#method_name(value),
+                None => self,
+            }
+        }
+    };
+
+    let attrs = quote! { #docs #deprecation };
+    let with_methods = if is_string_type(inner_type) {
+        let set_method = generate_set_method(
+            &method_name,
+            &attrs,
+            quote! { value: impl Into<String> },
+            quote! { self.#field_name = Some(value.into()); },
+        );
+        quote! {
+            #docs
+            #deprecation
+            /// Set optional field value.
+            pub fn #method_name(mut self, value: impl Into<String>) -> Self {
+                self.#field_name = Some(value.into());
+                self
+            }
+
+            #set_method
+        }
+    } else if is_integer_type(inner_type) {
+        generate_integer_with_methods(field, inner_type, true, &attrs)
+    } else if let Some(item_type) = extract_vec_inner_type(inner_type) {
+        // Option<Vec<T>>: collect any iterator, plus add_<item> to push one at a time
+        let add_method_name = format_ident!("add_{}", singular_name(field_name));
+        let set_method = generate_set_method(
+            &method_name,
+            &attrs,
+            quote! { values: impl IntoIterator<Item = #item_type> },
+            quote! { self.#field_name = Some(values.into_iter().collect()); },
+        );
+        quote! {
+            #docs
+            #deprecation
+            /// Set optional field value from any iterator of items.
+            pub fn #method_name(mut self, values: impl IntoIterator<Item = #item_type>) -> Self {
+                self.#field_name = Some(values.into_iter().collect());
+                self
+            }
+
+            #set_method
+
+            #docs
+            #deprecation
+            /// Push an item, initializing the field to `Some(vec![])` if it is `None`.
+            pub fn #add_method_name(mut self, value: #item_type) -> Self {
+                self.#field_name.get_or_insert_with(Vec::new).push(value);
+                self
+            }
+        }
+    } else if needs_clone(inner_type) {
+        let set_method = generate_set_method(
+            &method_name,
+            &attrs,
+            quote! { value: impl Into<#inner_type> },
+            quote! { self.#field_name = Some(value.into()); },
+        );
+        quote! {
+            #docs
+            #deprecation
+            /// Set optional field value, converting with `Into`.
+            pub fn #method_name(mut self, value: impl Into<#inner_type>) -> Self {
+                self.#field_name = Some(value.into());
+                self
+            }
+
+            #set_method
+        }
+    } else {
+        // bool, floats and char take the exact type, so literals keep inferring
+        let set_method = generate_set_method(
+            &method_name,
+            &attrs,
+            quote! { value: #inner_type },
+            quote! { self.#field_name = Some(value); },
+        );
+        quote! {
+            #docs
+            #deprecation
+            /// Set optional field value.
+            pub fn #method_name(mut self, value: #inner_type) -> Self {
+                self.#field_name = Some(value);
+                self
+            }
+
+            #set_method
+        }
+    };
+
+    // with_<field>_opt(Option<T>) assigns as-is, so `None` clears the field
+    let opt_method = quote! {
+        #docs
+        #deprecation
+        /// Set optional field value from an already-optional value, as-is.
+        pub fn #opt_method_name(mut self, value: Option<#inner_type>) -> Self {
+            self.#field_name = value;
+            self
+        }
+    };
+
+    quote! {
+        #with_methods
+        #merge_method
+        #opt_method
+    }
+}
+
+/// Generates the setters for integer fields (`T` or `Option<T>`):
+/// - with_<field>(T) - the exact type, so literals infer as `T` (`with_big(5_000_000_000)`)
+/// - with_<field>_from(impl TryInto<T>) - converts, panicking if the value doesn't fit
+/// - try_with_<field>(V) - converts, returning the conversion error instead
+///
+/// The last two take values of other integer types (`usize`, `u64`, ...) without
+/// `as` casts.
+///
+/// With `#[range(min = .., max = ..)]`, every setter also checks the bounds:
+/// `try_with_<field>` returns an error and the others panic.
+fn generate_integer_with_methods(
+    field: &Field,
+    int_type: &Type,
+    wrap_some: bool,
+    docs: &TokenStream2,
+) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+    if let Some(range) = parse_range_attr(field) {
+        return generate_range_with_methods(field_name, int_type, wrap_some, docs, range);
+    }
+    if let Some((min, max)) = parse_clamp_attr(field) {
+        return generate_clamp_with_methods(field_name, int_type, wrap_some, docs, min, max);
+    }
+
+    let method_name = format_ident!("with_{}", field_name);
+    let from_method_name = format_ident!("with_{}_from", field_name);
+    let try_method_name = format_ident!("try_with_{}", field_name);
+    let assigned = if wrap_some {
+        quote! { Some(value) }
+    } else {
+        quote! { value }
+    };
+    let error_msg = format!(
+        "{field_name} value out of range for {}",
+        quote! { #int_type }
+    );
+    let set_method = generate_set_method(
+        &method_name,
+        docs,
+        quote! { value: #int_type },
+        quote! { self.#field_name = #assigned; },
+    );
+
+    quote! {
+        #docs
+        /// Set integer field value.
+        pub fn #method_name(mut self, value: #int_type) -> Self {
+            self.#field_name = #assigned;
+            self
+        }
+
+        #set_method
+
+        #docs
+        /// Set integer field value, converting from any integer type.
+        /// Panics if the value doesn't fit.
+        pub fn #from_method_name(self, value: impl TryInto<#int_type>) -> Self {
+            let value: #int_type = value.try_into().unwrap_or_else(|_| panic!(#error_msg));
+            self.#method_name(value)
+        }
+
+        #docs
+        /// Set integer field value, converting from any integer type.
+        /// Returns the conversion error if the value doesn't fit.
+        pub fn #try_method_name<V: TryInto<#int_type>>(
+            mut self,
+            value: V,
+        ) -> Result<Self, V::Error> {
+            let value: #int_type = value.try_into()?;
+            self.#field_name = #assigned;
+            Ok(self)
+        }
+    }
+}
+
+/// Integer setters for `#[range]` fields: like `generate_integer_with_methods`,
+/// plus a bounds check in each. `try_with_<field>` returns a boxed error so it can
+/// report both conversion and range failures.
+fn generate_range_with_methods(
+    field_name: &Ident,
+    int_type: &Type,
+    wrap_some: bool,
+    docs: &TokenStream2,
+    range: RangeAttrInfo,
+) -> TokenStream2 {
+    let method_name = format_ident!("with_{}", field_name);
+    let from_method_name = format_ident!("with_{}_from", field_name);
+    let try_method_name = format_ident!("try_with_{}", field_name);
+    let assigned = if wrap_some {
+        quote! { Some(value) }
+    } else {
+        quote! { value }
+    };
+    let conversion_msg = format!(
+        "{field_name} value out of range for {}",
+        quote! { #int_type }
+    );
+
+    let (range_fmt, bounds, in_range) = match (&range.min, &range.max) {
+        (Some(min), Some(max)) => (
+            "{}..={}",
+            quote! { #min, #max },
+            quote! { (#min..=#max).contains(&value) },
+        ),
+        (Some(min), None) => ("{}..", quote! { #min }, quote! { value >= #min }),
+        (None, Some(max)) => ("..={}", quote! { #max }, quote! { value <= #max }),
+        (None, None) => unreachable!("validate_field_attrs requires a #[range] bound"),
+    };
+    let range_msg = format!("{field_name} value {{}} is out of range {range_fmt}");
+    let set_method = generate_set_method(
+        &method_name,
+        docs,
+        quote! { value: #int_type },
+        quote! {
+            if !(#in_range) {
+                panic!(#range_msg, value, #bounds);
+            }
+            self.#field_name = #assigned;
+        },
+    );
+
+    quote! {
+        #docs
+        /// Set integer field value.
+        /// Panics if the value is outside the field's `#[range]`.
+        pub fn #method_name(mut self, value: #int_type) -> Self {
+            if !(#in_range) {
+                panic!(#range_msg, value, #bounds);
+            }
+            self.#field_name = #assigned;
+            self
+        }
+
+        #set_method
+
+        #docs
+        /// Set integer field value, converting from any integer type.
+        /// Panics if the value doesn't fit or is outside the field's `#[range]`.
+        pub fn #from_method_name(self, value: impl TryInto<#int_type>) -> Self {
+            let value: #int_type = value.try_into().unwrap_or_else(|_| panic!(#conversion_msg));
+            self.#method_name(value)
+        }
+
+        #docs
+        /// Set integer field value, converting from any integer type.
+        /// Returns an error if the value doesn't fit or is outside the field's `#[range]`.
+        pub fn #try_method_name<V>(
+            mut self,
+            value: V,
+        ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>>
+        where
+            V: TryInto<#int_type>,
+            V::Error: std::error::Error + Send + Sync + 'static,
+        {
+            let value: #int_type = value.try_into()?;
+            if !(#in_range) {
+                return Err(format!(#range_msg, value, #bounds).into());
+            }
+            self.#field_name = #assigned;
+            Ok(self)
+        }
+    }
+}
+
+/// Integer setters for `#[clamp(min, max)]` fields: the value goes through
+/// `i128` and saturates into the bounds, so any integer is accepted and no
+/// `try_with_<field>` is generated. Only a `u128` above `i128::MAX` fails the
+/// first conversion, and it saturates to `max` like any other large value.
+fn generate_clamp_with_methods(
+    field_name: &Ident,
+    int_type: &Type,
+    wrap_some: bool,
+    docs: &TokenStream2,
+    min: Expr,
+    max: Expr,
+) -> TokenStream2 {
+    let method_name = format_ident!("with_{}", field_name);
+    let assigned = if wrap_some {
+        quote! { Some(value) }
+    } else {
+        quote! { value }
+    };
+    let clamp = quote! {
+        let value: i128 = value.try_into().unwrap_or(i128::MAX);
+        let value = value.clamp((#min) as i128, (#max) as i128) as #int_type;
+    };
+    let set_method = generate_set_method(
+        &method_name,
+        docs,
+        quote! { value: impl TryInto<i128> },
+        quote! {
+            #clamp
+            self.#field_name = #assigned;
+        },
+    );
+
+    quote! {
+        #docs
+        /// Set integer field value, converting from any integer type and
+        /// saturating into the field's `#[clamp]` bounds.
+        pub fn #method_name(mut self, value: impl TryInto<i128>) -> Self {
+            #clamp
+            self.#field_name = #assigned;
+            self
+        }
+
+        #set_method
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: with_* methods for regular (non-Option) non-FK fields
+// =============================================================================
+
+fn generate_regular_with_method(field: &Field) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+    let field_type = &field.ty;
+    let method_name = format_ident!("with_{}", field_name);
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+
+    let attrs = quote! { #docs #deprecation };
+    let setter = if is_string_type(field_type) {
+        let set_method = generate_set_method(
+            &method_name,
+            &attrs,
+            quote! { value: impl Into<String> },
+            quote! { self.#field_name = value.into(); },
+        );
+        quote! {
+            #docs
+            #deprecation
+            /// Set field value.
+            pub fn #method_name(mut self, value: impl Into<String>) -> Self {
+                self.#field_name = value.into();
+                self
+            }
+
+            #set_method
+        }
+    } else if is_integer_type(field_type) {
+        generate_integer_with_methods(field, field_type, false, &attrs)
+    } else if needs_clone(field_type) {
+        let set_method = generate_set_method(
+            &method_name,
+            &attrs,
+            quote! { value: impl Into<#field_type> },
+            quote! { self.#field_name = value.into(); },
+        );
+        quote! {
+            #docs
+            #deprecation
+            /// Set field value, converting with `Into`.
+            pub fn #method_name(mut self, value: impl Into<#field_type>) -> Self {
+                self.#field_name = value.into();
+                self
+            }
+
+            #set_method
+        }
+    } else {
+        // bool, floats and char take the exact type, so literals keep inferring
+        let set_method = generate_set_method(
+            &method_name,
+            &attrs,
+            quote! { value: #field_type },
+            quote! { self.#field_name = value; },
+        );
+        quote! {
+            #docs
+            #deprecation
+            /// Set field value.
+            pub fn #method_name(mut self, value: #field_type) -> Self {
+                self.#field_name = value;
+                self
+            }
+
+            #set_method
+        }
+    };
+
+    // Vec<T> fields also get a setter that clones from a borrowed slice
+    let slice_setter = extract_vec_inner_type(field_type).map(|item_type| {
+        let slice_method_name = format_ident!("with_{}_slice", field_name);
+        quote! {
+            #docs
+            #deprecation
+            /// Set field value by cloning the items of a slice.
+            pub fn #slice_method_name(mut self, values: &[#item_type]) -> Self {
+                self.#field_name = values.to_vec();
+                self
+            }
+        }
+    });
+
+    quote! {
+        #setter
+        #slice_setter
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: with_* methods for #[nested_builder] fields
+// =============================================================================
+
+/// Generates `with_<field>(|builder| ...)` (and its `set_<field>` twin) for a
+/// `#[nested_builder(Builder)]` value-object field: the closure gets
+/// `Builder::default()` and returns the value (wrapped in `Some` for `Option` fields)
+fn generate_nested_builder_with_method(field: &Field) -> Option<TokenStream2> {
+    let builder = parse_nested_builder_attr(field)?;
+    let field_name = field.ident.as_ref().unwrap();
+    let value_type = extract_option_inner_type(&field.ty).unwrap_or(&field.ty);
+    let method_name = format_ident!("with_{}", field_name);
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+
+    let value = quote! { build(<#builder as Default>::default()) };
+    let assigned = if is_option_type(&field.ty) {
+        quote! { Some(#value) }
+    } else {
+        value
+    };
+
+    let attrs = quote! { #docs #deprecation };
+    let set_method = generate_set_method(
+        &method_name,
+        &attrs,
+        quote! { build: impl FnOnce(#builder) -> #value_type },
+        quote! { self.#field_name = #assigned; },
+    );
+    Some(quote! {
+        #docs
+        #deprecation
+        /// Set field value from a builder: `build` gets a default builder and
+        /// returns the value.
+        pub fn #method_name(mut self, build: impl FnOnce(#builder) -> #value_type) -> Self {
+            self.#field_name = #assigned;
+            self
+        }
+
+        #set_method
+    })
+}
+
+// =============================================================================
+// CODE GENERATION: with_<field>_empty methods for string/collection fields
+// =============================================================================
+
+/// Generates `with_<field>_empty()` for `String`, `Vec`, map and set fields,
+/// which sets an empty value. For `Option<T>` fields this is `Some(empty)`,
+/// so "present but empty" stays distinguishable from `None`.
+fn generate_empty_with_method(field: &Field) -> Option<TokenStream2> {
+    let field_name = field.ident.as_ref().unwrap();
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+    let value_type = extract_option_inner_type(&field.ty).unwrap_or(&field.ty);
+    if !is_emptyable_type(value_type) {
+        return None;
+    }
+
+    let method_name = format_ident!("with_{}_empty", field_name);
+    let empty = if is_option_type(&field.ty) {
+        quote! { Some(Default::default()) }
+    } else {
+        quote! { Default::default() }
+    };
+
+    Some(quote! {
+        #docs
+        #deprecation
+        /// Set field to an empty value.
+        pub fn #method_name(mut self) -> Self {
+            self.#field_name = #empty;
+            self
+        }
+    })
+}
+
+// =============================================================================
+// CODE GENERATION: with_<field>_num / with_<field>_display methods for String fields
+// =============================================================================
+
+/// Generates `with_<field>_num(impl ToString)` for `String` / `Option<String>`
+/// fields holding numeric codes, which stringifies the value and passes it
+/// to `with_<field>`, and `with_<field>_display(impl Display)`, which does the
+/// same for any `Display` type (enums, ids, ...).
+fn generate_num_with_method(field: &Field) -> Option<TokenStream2> {
+    let field_name = field.ident.as_ref().unwrap();
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+    let value_type = extract_option_inner_type(&field.ty).unwrap_or(&field.ty);
+    if !is_string_type(value_type) {
+        return None;
+    }
+
+    let method_name = format_ident!("with_{}_num", field_name);
+    let display_method_name = format_ident!("with_{}_display", field_name);
+    let setter_name = format_ident!("with_{}", field_name);
+
+    Some(quote! {
+        #docs
+        #deprecation
+        /// Set field value from a number (or anything `ToString`), e.g. a numeric code.
+        pub fn #method_name(self, value: impl ToString) -> Self {
+            self.#setter_name(value.to_string())
+        }
+
+        #docs
+        #deprecation
+        /// Set field value to the `Display` output of `value`.
+        pub fn #display_method_name(self, value: impl std::fmt::Display) -> Self {
+            self.#setter_name(value.to_string())
+        }
+    })
+}
+
+// =============================================================================
+// CODE GENERATION: with_<field>_epoch methods for timestamp fields
+// =============================================================================
+
+/// Generates `with_<field>_epoch(i64)` for timestamp fields (see `timestamp_from_epoch`),
+/// delegating to `with_<field>`.
+fn generate_epoch_with_method(field: &Field) -> Option<TokenStream2> {
+    let field_name = field.ident.as_ref().unwrap();
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+    let value_type = extract_option_inner_type(&field.ty).unwrap_or(&field.ty);
+    let timestamp = timestamp_from_epoch(value_type, quote! { secs })?;
+
+    let method_name = format_ident!("with_{}_epoch", field_name);
+    let setter_name = format_ident!("with_{}", field_name);
+    let error_msg = format!("{field_name}: {{}} is out of range for a timestamp");
+
+    Some(quote! {
+        #docs
+        #deprecation
+        /// Set the timestamp from seconds since the Unix epoch.
+        /// Panics if it's out of range for the timestamp type.
+        pub fn #method_name(self, secs: i64) -> Self {
+            let timestamp = #timestamp.unwrap_or_else(|| panic!(#error_msg, secs));
+            self.#setter_name(timestamp)
+        }
+    })
+}
+
+/// For timestamp types, an `Option` of the timestamp `secs` Unix seconds in,
+/// built through the type itself so re-exports (`sqlx::types::chrono`) work:
+/// - chrono `DateTime<Utc>`: `DateTime::<Utc>::from_timestamp`
+/// - time `OffsetDateTime`: `from_unix_timestamp`, in UTC
+///
+/// `DateTime`s in other time zones have no such constructor and are skipped.
+fn timestamp_from_epoch(ty: &Type, secs: TokenStream2) -> Option<TokenStream2> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident == "OffsetDateTime" {
+        return Some(quote! { <#ty>::from_unix_timestamp(#secs).ok() });
+    }
+    if segment.ident != "DateTime" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(Type::Path(tz)) if tz.path.segments.last()?.ident == "Utc" => {
+            Some(quote! { <#ty>::from_timestamp(#secs, 0) })
+        }
+        _ => None,
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: with_<field>_ok / with_<field>_err for Result fields
+// =============================================================================
+
+/// Generates `with_<field>_ok(T)` and `with_<field>_err(E)` for `Result<T, E>`
+/// fields, which set the matching variant. For `Option<Result<T, E>>` fields the
+/// variant is wrapped in `Some`.
+fn generate_result_with_methods(field: &Field) -> Option<TokenStream2> {
+    let field_name = field.ident.as_ref().unwrap();
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+    let value_type = extract_option_inner_type(&field.ty).unwrap_or(&field.ty);
+    let (ok_type, err_type) = extract_result_types(value_type)?;
+
+    let ok_method_name = format_ident!("with_{}_ok", field_name);
+    let err_method_name = format_ident!("with_{}_err", field_name);
+    let (ok, err) = if is_option_type(&field.ty) {
+        (quote! { Some(Ok(value)) }, quote! { Some(Err(error)) })
+    } else {
+        (quote! { Ok(value) }, quote! { Err(error) })
+    };
+
+    Some(quote! {
+        #docs
+        #deprecation
+        /// Set field to `Ok(value)`.
+        pub fn #ok_method_name(mut self, value: #ok_type) -> Self {
+            self.#field_name = #ok;
+            self
+        }
+
+        #docs
+        #deprecation
+        /// Set field to `Err(error)`.
+        pub fn #err_method_name(mut self, error: #err_type) -> Self {
+            self.#field_name = #err;
+            self
+        }
+    })
+}
+
+// =============================================================================
+// CODE GENERATION: with_<field>_parse methods for #[parse] fields
+// =============================================================================
+
+/// Generates `with_<field>_parse(&str)`, which parses the value via `FromStr`.
+/// For `Option<T>` fields the parsed `T` is wrapped in `Some`.
+fn generate_parse_with_method(field: &Field) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+    let method_name = format_ident!("with_{}_parse", field_name);
+
+    let (target_type, assigned) = match extract_option_inner_type(&field.ty) {
+        Some(inner_type) => (inner_type, quote! { Some(value) }),
+        None => (&field.ty, quote! { value }),
+    };
+
+    quote! {
+        #docs
+        #deprecation
+        /// Set field value by parsing a string via `FromStr`.
+        pub fn #method_name(
+            mut self,
+            value: &str,
+        ) -> Result<Self, <#target_type as std::str::FromStr>::Err> {
+            let value: #target_type = value.parse()?;
+            self.#field_name = #assigned;
+            Ok(self)
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: with_<field>_raw methods for #[enum_repr] fields
+// =============================================================================
+
+/// Generates `with_<field>_raw(repr)`, which converts a discriminant into the
+/// enum via `TryFrom`, panicking on an invalid value.
+/// For `Option<Enum>` fields the converted value is wrapped in `Some`.
+fn generate_enum_repr_with_method(field: &Field) -> Option<TokenStream2> {
+    let repr_type = parse_enum_repr_attr(field)?;
+    let field_name = field.ident.as_ref().unwrap();
+    let docs = field_docs(field);
+    let deprecation = field_deprecation(field);
+    let method_name = format_ident!("with_{}_raw", field_name);
+
+    let (enum_type, assigned) = match extract_option_inner_type(&field.ty) {
+        Some(inner_type) => (inner_type, quote! { Some(value) }),
+        None => (&field.ty, quote! { value }),
+    };
+    let error_msg = format!("invalid {field_name} discriminant: {{}}");
+
+    Some(quote! {
+        #docs
+        #deprecation
+        /// Set enum field value from its integer discriminant.
+        /// Panics if the discriminant doesn't map to a variant.
+        pub fn #method_name(mut self, raw: #repr_type) -> Self {
+            let value = <#enum_type as std::convert::TryFrom<#repr_type>>::try_from(raw)
+                .unwrap_or_else(|_| panic!(#error_msg, raw));
+            self.#field_name = #assigned;
+            self
+        }
+    })
+}
+
+// =============================================================================
+// CODE GENERATION: deprecated alias setters for #[alias("...")] fields
+// =============================================================================
+
+/// Generates a `#[deprecated]` `with_<alias>` setter per `#[alias("...")]`,
+/// delegating to the field's canonical `with_<field>` setter.
+fn generate_alias_with_methods(field: &Field, into_setters: bool) -> Vec<TokenStream2> {
+    let field_name = field.ident.as_ref().unwrap();
+    let canonical_name = format_ident!("with_{}", field_name);
+    let value_type = setter_value_type(field, into_setters);
+    let note = format!("use `{canonical_name}` instead");
+
+    parse_alias_attrs(field)
+        .iter()
+        .map(|alias| {
+            let alias_name = format_ident!("with_{}", alias.value(), span = alias.span());
+            quote! {
+                /// Deprecated alias kept for a renamed field.
+                #[deprecated(note = #note)]
+                pub fn #alias_name(self, value: #value_type) -> Self {
+                    self.#canonical_name(value)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Parameter type of a field's canonical `with_<field>` setter
+fn setter_value_type(field: &Field, into_setters: bool) -> TokenStream2 {
+    // FK id setters take the id type as-is, or `impl Into<Id>` with `into_setters`
+    if parse_fk_attr(field).is_some() {
+        let id_type = fk_id_type(field);
+        return if into_setters {
+            quote! { impl Into<#id_type> }
+        } else {
+            quote! { #id_type }
+        };
+    }
+
+    let value_type = extract_option_inner_type(&field.ty).unwrap_or(&field.ty);
+    if let Some(builder) = parse_nested_builder_attr(field) {
+        quote! { impl FnOnce(#builder) -> #value_type }
+    } else if is_string_type(value_type) {
+        quote! { impl Into<String> }
+    } else if is_option_type(&field.ty)
+        && let Some(item_type) = extract_vec_inner_type(value_type)
+    {
+        quote! { impl IntoIterator<Item = #item_type> }
+    } else if is_integer_type(value_type) && has_attr(field, "clamp") {
+        quote! { impl TryInto<i128> }
+    } else if needs_clone(value_type) {
+        quote! { impl Into<#value_type> }
+    } else {
+        quote! { #value_type }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: build() assignments
+// =============================================================================
+
+fn generate_build_assignment(field: &Field) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+    let field_name_str = field_name.to_string();
+
+    // pk: Default, or its `unset_value`
+    if has_attr(field, "pk") {
+        let unset_value = pk_unset_value(field);
+        return quote! {
+            #field_name: #unset_value
+        };
+    }
+
+    // PhantomData: a fresh marker (the field's type parameter is inferred)
+    if is_phantom_data_type(&field.ty) {
+        return quote! {
+            #field_name: std::marker::PhantomData
+        };
+    }
+
+    // FK field: behavior based on field type
+    if let Some(fk_info) = parse_fk_attr(field) {
+        let is_option_field = is_option_type(&field.ty);
+
+        if is_option_field && fk_info.into_vec {
+            // into_vec: the staged id, unless unset, as a Vec
+            let staged_id = if is_pointer_fk(field) {
+                quote! { &***id }
+            } else {
+                quote! { *id }
+            };
+            return quote! {
+                #field_name: self
+                    .#field_name
+                    .iter()
+                    .filter(|id| !factory_m8::Sentinel::is_sentinel(#staged_id))
+                    .cloned()
+                    .collect()
+            };
+        } else if is_option_field {
+            // Option<T> FK field: clone as-is for build() (entity field is Option<T>)
+            return quote! {
+                #field_name: self.#field_name.clone()
+            };
+        } else {
+            // Required FK with non-Option factory field: use directly
+            if needs_clone(&field.ty) {
+                return quote! {
+                    #field_name: self.#field_name.clone()
+                };
+            } else {
+                return quote! {
+                    #field_name: self.#field_name
+                };
+            }
+        }
+    }
+
+    // #[sequence] field: fall back to the next value of its counter
+    if let Some(assignment) = generate_sequence_assignment(field) {
+        return assignment;
+    }
+
+    // #[seq] field: number the value set with with_<field>
+    if let Some(assignment) = generate_seq_assignment(field) {
+        return assignment;
+    }
+
+    // #[env("VAR")] field: fall back to the environment variable
+    if let Some(assignment) = generate_env_assignment(field) {
+        return assignment;
+    }
+
+    // #[required] Option field: unwrap with error message (entity field is non-Option)
+    if has_attr(field, "required") && is_option_type(&field.ty) {
+        let error_msg = format!("{field_name_str} is required - use with_{field_name_str}()");
+        return quote! {
+            #field_name: self.#field_name.clone().expect(#error_msg)
+        };
+    }
+
+    // Option field: clone as-is
+    if is_option_type(&field.ty) {
+        return quote! {
+            #field_name: self.#field_name.clone()
+        };
+    }
+
+    // Regular non-Option field: clone or copy
+    if needs_clone(&field.ty) {
+        quote! {
+            #field_name: self.#field_name.clone()
+        }
+    } else {
+        quote! {
+            #field_name: self.#field_name
+        }
+    }
+}
+
+/// Generates the `try_build()` check for a `#[required]` Option field, which
+/// collects a `MissingField` error if it is unset (and its `#[env]` variable,
+/// if any, isn't set either)
+fn generate_required_check(field: &Field, error_name: &Ident) -> Option<TokenStream2> {
+    if !has_attr(field, "required") || !is_option_type(&field.ty) || parse_fk_attr(field).is_some()
+    {
+        return None;
+    }
+    let field_name = field.ident.as_ref().unwrap();
+    let field_name_str = field_name.to_string();
+
+    let (unset, message) = match parse_env_attr(field) {
+        Some(var_name) => (
+            quote! { self.#field_name.is_none() && std::env::var(#var_name).is_err() },
+            format!(
+                "{field_name_str} is required - use with_{field_name_str}() or set {}",
+                var_name.value()
+            ),
+        ),
+        None => (
+            quote! { self.#field_name.is_none() },
+            format!("{field_name_str} is required - use with_{field_name_str}()"),
+        ),
+    };
+
+    Some(quote! {
+        if #unset {
+            errors.push(#error_name::MissingField {
+                field: #field_name_str,
+                message: #message,
+            });
+        }
+    })
+}
+
+/// Generates the assignment for an `#[env("VAR")]` field, which reads `VAR`
+/// at build time unless a value was set through `with_<field>`:
+/// - `String`: an empty value counts as unset, and a missing variable gives `""`
+/// - `Option<String>`: `None` counts as unset, and a missing variable stays `None`
+///   (or panics for `#[required]` fields)
+fn generate_env_assignment(field: &Field) -> Option<TokenStream2> {
+    let var_name = parse_env_attr(field)?;
+    let field_name = field.ident.as_ref().unwrap();
+    let field_name_str = field_name.to_string();
+
+    if !is_option_type(&field.ty) {
+        return Some(quote! {
+            #field_name: if self.#field_name.is_empty() {
+                std::env::var(#var_name).unwrap_or_default()
+            } else {
+                self.#field_name.clone()
+            }
+        });
+    }
+
+    let value = quote! {
+        self.#field_name.clone().or_else(|| std::env::var(#var_name).ok())
+    };
+    if has_attr(field, "required") {
+        let error_msg = format!(
+            "{field_name_str} is required - use with_{field_name_str}() or set {}",
+            var_name.value()
+        );
+        Some(quote! { #field_name: #value.expect(#error_msg) })
+    } else {
+        Some(quote! { #field_name: #value })
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: #[sequence] counters
+// =============================================================================
+
+/// Generates `next_<field>()` for a `#[sequence]` field: the next value of a
+/// process-global `AtomicU64` counter (starting at 1) owned by this factory and
+/// field. Being process-global rather than thread-local, concurrently running
+/// tests never get the same value, even when they share a database.
+fn generate_sequence_fn(field: &Field) -> Option<TokenStream2> {
+    if !has_attr(field, "sequence") {
+        return None;
+    }
+    let field_name = field.ident.as_ref().unwrap();
+    let value_type = extract_option_inner_type(&field.ty)?;
+    let fn_name = format_ident!("next_{}", field_name);
+    let overflow_msg = format!("{field_name}: #[sequence] overflowed {}", quote!(#value_type));
+
+    Some(quote! {
+        /// The next value of this field's `#[sequence]` counter.
+        fn #fn_name() -> #value_type {
+            static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+            let next = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            <#value_type as std::convert::TryFrom<u64>>::try_from(next).expect(#overflow_msg)
+        }
+    })
+}
+
+/// Generates the entity assignment for a `#[sequence]` field: the value set with
+/// `with_<field>`, or else the next value of the field's counter
+fn generate_sequence_assignment(field: &Field) -> Option<TokenStream2> {
+    if !has_attr(field, "sequence") {
+        return None;
+    }
+    let field_name = field.ident.as_ref().unwrap();
+    let fn_name = format_ident!("next_{}", field_name);
+    Some(quote! {
+        #field_name: self.#field_name.unwrap_or_else(Self::#fn_name)
+    })
+}
+
+/// Generates the entity assignment for a `#[seq]` field: the value set with
+/// `with_<field>`, with its placeholder replaced by the next value of the
+/// factory's counter. Values without the placeholder are used as-is and don't
+/// advance the counter.
+fn generate_seq_assignment(field: &Field) -> Option<TokenStream2> {
+    let placeholder = parse_seq_attr(field)?;
+    let field_name = field.ident.as_ref().unwrap();
+    let field_name_str = field_name.to_string();
+
+    let numbered = quote! {
+        if value.contains(#placeholder) {
+            value.replace(#placeholder, &Self::next_seq().to_string())
+        } else {
+            value.clone()
+        }
+    };
+    if !is_option_type(&field.ty) {
+        return Some(quote! {
+            #field_name: {
+                let value = &self.#field_name;
+                #numbered
+            }
+        });
+    }
+
+    let value = quote! { self.#field_name.as_ref().map(|value| #numbered) };
+    if has_attr(field, "required") {
+        let error_msg = format!("{field_name_str} is required - use with_{field_name_str}()");
+        Some(quote! { #field_name: #value.expect(#error_msg) })
+    } else {
+        Some(quote! { #field_name: #value })
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: <Factory>Error for try_build()
+// =============================================================================
+
+/// Generates the `try_build()` error type: one variant per kind of problem,
+/// plus `Multiple` when more than one was found. Its `Display` is the message
+/// `build()` panics with.
+///
+/// Each factory gets its own type, named `<Factory>Error` (`UserFactory` ->
+/// `UserFactoryError`) with the factory's visibility.
+fn generate_build_error(
+    vis: &syn::Visibility,
+    factory_name: &Ident,
+    error_name: &Ident,
+) -> TokenStream2 {
+    let doc = format!(
+        "Why `{factory_name}::try_build()` couldn't build the entity. Generated by \
+         `#[derive(Factory)]` for `{factory_name}` only."
+    );
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #vis enum #error_name {
+            /// A `#[required]` field is unset
+            MissingField {
+                field: &'static str,
+                message: &'static str,
+            },
+            /// A non-Option FK is still the sentinel (debug builds only)
+            UnsetFk {
+                field: &'static str,
+                message: &'static str,
+            },
+            /// Several of the above, in field declaration order
+            Multiple(Vec<#error_name>),
+            /// The built entity failed the factory's `invariant` check
+            Invariant(String),
+        }
+
+        #[automatically_derived]
+        impl #error_name {
+            fn from_errors(mut errors: Vec<Self>) -> Self {
+                if errors.len() == 1 {
+                    errors.remove(0)
+                } else {
+                    Self::Multiple(errors)
+                }
+            }
+
+            /// Names of all fields with a problem
+            pub fn fields(&self) -> Vec<&'static str> {
+                match self {
+                    Self::MissingField { field, .. } | Self::UnsetFk { field, .. } => vec![*field],
+                    Self::Multiple(errors) => errors.iter().flat_map(Self::fields).collect(),
+                    Self::Invariant(_) => Vec::new(),
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl std::fmt::Display for #error_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Self::MissingField { message, .. } | Self::UnsetFk { message, .. } => {
+                        f.write_str(message)
+                    }
+                    Self::Invariant(message) => write!(f, "invariant violated: {message}"),
+                    Self::Multiple(errors) => {
+                        write!(f, "{} problems: ", errors.len())?;
+                        for (i, error) in errors.iter().enumerate() {
+                            if i > 0 {
+                                f.write_str("; ")?;
+                            }
+                            write!(f, "{error}")?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl std::error::Error for #error_name {}
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: #[template("...")] row values
+// =============================================================================
+
+/// Generates the assignment of a `#[template("user-{i}")]` field on row `i` of
+/// a batch: `factory.<field>` is set to the template with `{i}` replaced by `i`
+fn generate_template_assignment(field: &Field) -> Option<TokenStream2> {
+    let template = parse_template_attr(field)?;
+    let field_name = field.ident.as_ref().unwrap();
+    let value = quote! { #template.replace("{i}", &i.to_string()) };
+    Some(if is_option_type(&field.ty) {
+        quote! { factory.#field_name = Some(#value); }
+    } else {
+        quote! { factory.#field_name = #value; }
+    })
+}
+
+// =============================================================================
+// CODE GENERATION: Send assertion
+// =============================================================================
+
+/// Generates a `const _` that only compiles if the `build_with_fks()` future is
+/// `Send` for every pool it accepts (given a `Sync` factory), so a non-`Send`
+/// value held across an `.await` in FK resolution is caught where the factory
+/// is derived rather than at some test's `tokio::spawn`.
+fn generate_send_assertion(
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    fk_factory_bounds: &[TokenStream2],
+) -> TokenStream2 {
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let where_predicates = where_clause.map(|w| &w.predicates);
+
+    let mut assert_generics = generics.clone();
+    assert_generics.params.push(syn::parse_quote! { Pool });
+    let (assert_impl_generics, _, _) = assert_generics.split_for_impl();
+
+    quote! {
+        const _: () = {
+            fn assert_send<T: Send>(_: T) {}
+
+            #[allow(dead_code)]
+            fn build_with_fks_is_send #assert_impl_generics(
+                factory: &#factory_name #ty_generics,
+                pool: &Pool,
+            ) where
+                Pool: Sync,
+                #(#fk_factory_bounds,)*
+                // higher-ranked so non-Sync factories still compile without it
+                for<'a> #factory_name #ty_generics: Sync,
+                #where_predicates
+            {
+                assert_send(factory.build_with_fks(pool));
+            }
+        };
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: <Factory>Persist / persist()
+// =============================================================================
+
+/// Generates `<Factory>Persist`, a factory paired with its pool that can be
+/// `.await`ed directly (`IntoFuture`, running `create()`), and `persist(pool)`.
+fn generate_persist(
+    vis: &syn::Visibility,
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    entity_type: &syn::Path,
+) -> (TokenStream2, TokenStream2) {
+    let persist_name = format_ident!("{}Persist", factory_name);
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let where_predicates = where_clause.map(|w| &w.predicates);
+
+    let mut persist_generics = generics.clone();
+    persist_generics.params.insert(0, syn::parse_quote! { 'p });
+    persist_generics.params.push(syn::parse_quote! { Pool });
+    let (persist_impl_generics, persist_ty_generics, _) = persist_generics.split_for_impl();
+
+    let doc = format!(
+        "A `{factory_name}` paired with its pool by `persist()`; `.await` it to create the entity."
+    );
+    let persist_struct = quote! {
+        #[doc = #doc]
+        #vis struct #persist_name #persist_generics #where_clause {
+            pub factory: #factory_name #ty_generics,
+            pub pool: &'p Pool,
+        }
+
+        #[automatically_derived]
+        impl #persist_impl_generics std::future::IntoFuture for #persist_name #persist_ty_generics
+        where
+            Pool: Sync,
+            #factory_name #ty_generics: factory_m8::FactoryCreate<Pool, Entity = #entity_type> + 'p,
+            #where_predicates
+        {
+            type Output = Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>;
+            type IntoFuture = std::pin::Pin<
+                Box<dyn std::future::Future<Output = Self::Output> + Send + 'p>,
+            >;
+
+            fn into_future(self) -> Self::IntoFuture {
+                factory_m8::FactoryCreate::create(self.factory, self.pool)
+            }
+        }
+    };
+
+    let persist_fn = quote! {
+        /// Pair the factory with `pool`; awaiting the result creates the entity via
+        /// its `FactoryCreate` impl, like `create(pool)`.
+        pub fn persist<'p, Pool>(self, pool: &'p Pool) -> #persist_name #persist_ty_generics {
+            #persist_name { factory: self, pool }
+        }
+    };
+
+    (persist_struct, persist_fn)
+}
+
+// =============================================================================
+// CODE GENERATION: <Entity>Handle / as_fk()
+// =============================================================================
+
+/// Generates `<Entity>Handle`, a created entity that spawns child factories
+/// pre-wired to it (`as_fk::<ChildFactory>()`), and `create_handle()`.
+fn generate_handle(vis: &syn::Visibility, entity_type: &syn::Path) -> (TokenStream2, TokenStream2) {
+    let entity_name = &entity_type.segments.last().unwrap().ident;
+    let handle_name = format_ident!("{}Handle", entity_name);
+
+    let doc = format!("A created `{entity_name}`, for creating related rows with `as_fk()`.");
+    let handle_struct = quote! {
+        #[doc = #doc]
+        #vis struct #handle_name {
+            pub entity: #entity_type,
+        }
+
+        #[automatically_derived]
+        impl #handle_name {
+            /// A child factory with its FK to this entity already set
+            /// (the child factory needs `#[factory(fk_from)]`).
+            pub fn as_fk<ChildFactory>(&self) -> ChildFactory
+            where
+                ChildFactory: for<'a> From<&'a #entity_type>,
+            {
+                ChildFactory::from(&self.entity)
+            }
+
+            /// The created entity
+            pub fn into_entity(self) -> #entity_type {
+                self.entity
+            }
+        }
+
+        #[automatically_derived]
+        impl std::ops::Deref for #handle_name {
+            type Target = #entity_type;
+
+            fn deref(&self) -> &Self::Target {
+                &self.entity
+            }
+        }
+    };
+
+    let doc = format!("Create the entity via its `FactoryCreate` impl, as a `{handle_name}`.");
+    let create_handle_fn = quote! {
+        #[doc = #doc]
+        pub async fn create_handle<Pool>(
+            self,
+            pool: &Pool,
+        ) -> Result<#handle_name, Box<dyn std::error::Error + Send + Sync>>
+        where
+            Pool: Sync,
+            Self: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+        {
+            use factory_m8::FactoryCreate;
+
+            let entity = self.create(pool).await?;
+            Ok(#handle_name { entity })
+        }
+    };
+
+    (handle_struct, create_handle_fn)
+}
+
+// =============================================================================
+// CODE GENERATION: after_create hook
+// =============================================================================
+
+/// Generates the helpers running `#[factory(after_create = "hook")]`:
+/// - create_and_after(pool) - `FactoryCreate::create`, then the hook on the
+///   inserted entity. The one to use when `create` inserts
+/// - build_with_fks_and_after(pool) - `build_with_fks()`, then the hook, for
+///   `create` impls that are `build_with_fks()` alone (mocks, in-memory stores)
+///
+/// A `create` impl must not call either one itself: it would run the hook twice
+/// (or, via `build_with_fks_and_after`, before the row exists).
+///
+/// The hook takes a concrete pool type, like `create_method` parents: `pool` is
+/// downcast to it, and any other pool type is an error rather than a skipped hook.
+fn generate_after_create(
+    hook: &Ident,
+    entity_type: &syn::Path,
+    fk_factory_bounds: &[TokenStream2],
+) -> TokenStream2 {
+    let run_hook = quote! {
+        let Some(typed_pool) = (pool as &dyn std::any::Any).downcast_ref() else {
+            return Err(format!(
+                "after_create hook `{}` doesn't take a {}",
+                stringify!(#hook),
+                std::any::type_name::<Pool>(),
+            )
+            .into());
+        };
+        Self::#hook(&entity, typed_pool).await?;
+    };
+
+    quote! {
+        /// Create the entity via its `FactoryCreate` impl, then run the factory's
+        /// `after_create` hook on it (e.g. to grant permissions on the new row).
+        pub async fn create_and_after<Pool>(
+            self,
+            pool: &Pool,
+        ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
+        where
+            Pool: Sync + 'static,
+            Self: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+        {
+            use factory_m8::FactoryCreate;
+
+            let entity = self.create(pool).await?;
+            #run_hook
+            Ok(entity)
+        }
+
+        /// `build_with_fks()`, then the factory's `after_create` hook on the built
+        /// entity. Nothing is inserted in between, so prefer `create_and_after()`
+        /// unless `create` is `build_with_fks()` alone.
+        pub async fn build_with_fks_and_after<Pool>(
+            &self,
+            pool: &Pool,
+        ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
+        where
+            Pool: Sync + 'static,
+            #(#fk_factory_bounds,)*
+        {
+            let entity = self.build_with_fks(pool).await?;
+            #run_hook
+            Ok(entity)
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: #[has_many] child rows
+// =============================================================================
+
+/// Generates, for `#[has_many(ChildFactory)]` fields (`Vec<ChildFactory>`):
+/// - with_<child>(ChildFactory) / set_<child> - queue one child (notes -> with_note)
+/// - create_with_children(pool) - create the entity via `FactoryCreate`, then
+///   each queued child with its FK set to the entity's `#[pk]`
+///
+/// The children are separate rows, so `build()` and `build_with_fks()` ignore them.
+fn generate_has_many(
+    entity_type: &syn::Path,
+    fields: &[&Field],
+    has_many_fields: &[&Field],
+) -> TokenStream2 {
+    let pk_field = fields
+        .iter()
+        .find(|f| has_attr(f, "pk"))
+        .expect("validate_field_attrs checks for a #[pk] field");
+    let pk_name = pk_field.ident.as_ref().unwrap();
+    let pk_value = if needs_clone(&pk_field.ty) {
+        quote! { entity.#pk_name.clone() }
+    } else {
+        quote! { entity.#pk_name }
+    };
+    let entity_name = &entity_type.segments.last().unwrap().ident;
+    let default_fk = format_ident!("{}_id", to_snake_case(entity_name));
+
+    let mut pushers = Vec::new();
+    let mut bounds = Vec::new();
+    let mut creations = Vec::new();
+    for field in has_many_fields {
+        let field_name = field.ident.as_ref().unwrap();
+        let info = parse_has_many_attr(field).unwrap();
+        let child_factory = &info.factory_type;
+        let fk_setter = format_ident!("with_{}", info.fk.as_ref().unwrap_or(&default_fk));
+
+        let method_name = format_ident!("with_{}", singular_name(field_name));
+        let docs = field_docs(field);
+        pushers.push(quote! {
+            #docs
+            /// Queue a child row, created by `create_with_children()` after this entity.
+            pub fn #method_name(mut self, child: #child_factory) -> Self {
+                self.#field_name.push(child);
+                self
+            }
+        });
+        pushers.push(generate_set_method(
+            &method_name,
+            &docs,
+            quote! { child: #child_factory },
+            quote! { self.#field_name.push(child); },
+        ));
+
+        bounds.push(quote! { #child_factory: factory_m8::FactoryCreate<Pool> });
+        creations.push(quote! {
+            for child in #field_name {
+                child.#fk_setter(#pk_value).create(pool).await?;
+            }
+        });
+    }
+    let taken: Vec<&Ident> = has_many_fields
+        .iter()
+        .map(|f| f.ident.as_ref().unwrap())
+        .collect();
+
+    quote! {
+        #(#pushers)*
+
+        /// Create the entity via its `FactoryCreate` impl, then every queued
+        /// `#[has_many]` child with its FK set to the entity's primary key.
+        pub async fn create_with_children<Pool>(
+            mut self,
+            pool: &Pool,
+        ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
+        where
+            Pool: Sync,
+            Self: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+            #(#bounds,)*
+        {
+            use factory_m8::FactoryCreate;
+
+            #(let #taken = std::mem::take(&mut self.#taken);)*
+            let entity = self.create(pool).await?;
+            #(#creations)*
+            Ok(entity)
+        }
+    }
+}
+
+/// Generates `impl From<&Parent> for Factory` (`new()` with the FK set) for every
+/// parent entity type exactly one FK field points at, which `<Parent>Handle::as_fk()` uses.
+fn generate_fk_from_impls(
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    fk_fields: &[&Field],
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let fk_infos: Vec<(&Field, FkAttrInfo)> = fk_fields
+        .iter()
+        .map(|f| (*f, parse_fk_attr(f).unwrap()))
+        .collect();
+
+    let impls = fk_infos.iter().filter_map(|(field, fk_info)| {
+        let same_parent = fk_infos
+            .iter()
+            .filter(|(_, other)| other.entity_type == fk_info.entity_type)
+            .count();
+        if same_parent > 1 {
+            return None;
+        }
+
+        let entity_type = &fk_info.entity_type;
+        let entity_method_name = fk_method_name(field.ident.as_ref().unwrap(), &fk_info.entity_field);
+        Some(quote! {
+            #[automatically_derived]
+            impl #impl_generics From<&#entity_type> for #factory_name #ty_generics #where_clause {
+                fn from(entity: &#entity_type) -> Self {
+                    Self::new().#entity_method_name(entity)
+                }
+            }
+        })
+    });
+
+    quote! { #(#impls)* }
+}
+
+// =============================================================================
+// CODE GENERATION: <Factory>Parts / into_parts()
+// =============================================================================
+
+/// Generates `<Factory>Parts`, with one public field per factory field (same
+/// name and type), and `into_parts()`, which moves the factory's values into it
+/// for custom inserts or composing factories.
+fn generate_parts(
+    vis: &syn::Visibility,
+    factory_name: &Ident,
+    parts_name: &Ident,
+    generics: &syn::Generics,
+    fields: &[&Field],
+) -> (TokenStream2, TokenStream2) {
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let field_names: Vec<&Ident> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let field_types = fields.iter().map(|f| &f.ty);
+
+    let doc = format!("Every field value of a `{factory_name}`, from `into_parts()`.");
+    let parts_struct = quote! {
+        #[doc = #doc]
+        #vis struct #parts_name #generics #where_clause {
+            #(pub #field_names: #field_types,)*
+        }
+    };
+
+    let into_parts_fn = quote! {
+        /// Move every configured field value out of the factory, for custom
+        /// inserts or composing factories.
+        pub fn into_parts(self) -> #parts_name #ty_generics {
+            #parts_name {
+                #(#field_names: self.#field_names,)*
+            }
+        }
+    };
+
+    (parts_struct, into_parts_fn)
+}
+
+// =============================================================================
+// CODE GENERATION: build_with_fks() FK resolution
+// =============================================================================
+
+/// Groups the auto-creating FKs without flags by parent factory and, for each
+/// factory referenced by two or more of them, generates an iterator over the
+/// parents from one `create_many(n, pool)` call, `n` being how many of those FKs
+/// are unset. Returns the batches keyed by iterator variable, so each can be
+/// created at its first field, and which one each grouped field takes from.
+fn generate_fk_batches(
+    fk_fields: &[&Field],
+) -> (HashMap<Ident, TokenStream2>, HashMap<Ident, Ident>) {
+    let mut groups: Vec<(syn::Path, Vec<&Field>)> = Vec::new();
+    for field in fk_fields {
+        let fk_info = parse_fk_attr(field).unwrap();
+        if fk_info.no_default
+            || fk_info.null_on_error
+            || fk_info.optional_create
+            || fk_info.dedup
+            || fk_info.use_last
+            || fk_info.find.is_some()
+            || fk_info.require_existing
+            || fk_info.max_depth.is_some()
+            || fk_info.create_method.is_some()
+            || fk_info.shared
+        {
+            continue;
+        }
+        match groups
+            .iter_mut()
+            .find(|(factory_type, _)| *factory_type == fk_info.factory_type)
+        {
+            Some((_, fields)) => fields.push(field),
+            None => groups.push((fk_info.factory_type, vec![field])),
+        }
+    }
+
+    let mut batches = HashMap::new();
+    let mut batched_fields = HashMap::new();
+    for (factory_type, fields) in groups.into_iter().filter(|(_, fields)| fields.len() > 1) {
+        let batch_var = format_ident!("batched_{}", fields[0].ident.as_ref().unwrap());
+        let unset_checks = fields.iter().map(|f| {
+            let field_name = f.ident.as_ref().unwrap();
+            if is_option_type(&f.ty) {
+                fk_option_unset_check(f, quote! { self.#field_name })
+            } else {
+                fk_unset_check(f, &parse_fk_attr(f).unwrap(), quote! { self.#field_name })
+            }
+        });
+        let batch = quote! {
+            let mut #batch_var = {
+                use factory_m8::Sentinel;
+                let unset = [#(#unset_checks),*].into_iter().filter(|unset| *unset).count();
+                #factory_type::new().create_many(unset, pool).await?.into_iter()
+            };
+        };
+        batches.insert(batch_var.clone(), batch);
+        for f in fields {
+            batched_fields.insert(f.ident.clone().unwrap(), batch_var.clone());
+        }
+    }
+
+    (batches, batched_fields)
+}
+
+/// Generates the `resolved_<field>` binding for an FK. With `batch`, an unset
+/// FK takes its parent from that `generate_fk_batches` iterator instead of
+/// creating one.
+///
+/// `inheriting` lists the `#[from_fk]` fields copying from this FK's parent, as
+/// (child field, parent entity field): whenever a parent entity is created (or
+/// taken from a batch or the shared memo), `inherited_<child field>` is set.
+///
+/// With `tracked` (for `create_tracked()`), a created parent is also moved into
+/// `created.<relation>`; a shared one is moved there from the memo afterwards
+/// (see `generate_shared_handoff`), by the FK that created it.
+fn generate_fk_resolution(
+    field: &Field,
+    batch: Option<&Ident>,
+    inheriting: &[(Ident, Ident)],
+    tracked: bool,
+) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+    let fk_info = parse_fk_attr(field).unwrap();
+    let entity_type = &fk_info.entity_type;
+    let entity_field = &fk_info.entity_field;
+    // The id as the field stores it, moved out of an owned parent `entity`, or
+    // cloned out of a borrowed one
+    let raw_entity_read = fk_read_id(&fk_info, quote! { entity });
+    let entity_read = wrap_fk_id(field, raw_entity_read.clone());
+    let entity_id_clone = wrap_fk_id(field, quote! { Clone::clone(&#raw_entity_read) });
+    let factory_type = &fk_info.factory_type;
+    let is_option_field = is_option_type(&field.ty);
+
+    // Evaluates to the id of an owned parent `entity`, keeping the entity when tracked
+    let take_id = if tracked {
+        let relation = format_ident!("{}", fk_relation_name(field_name, entity_field));
+        quote! {
+            {
+                let id = #entity_id_clone;
+                created.#relation = Some(entity);
+                id
+            }
+        }
+    } else {
+        entity_read.clone()
+    };
+
+    // Variable name for resolved ID
+    // Option FKs are matched by reference and cloned, so non-Copy id types
+    // (e.g. String ids) work without moving out of `&self`
+    let resolved_var = format_ident!("resolved_{}", field_name);
+
+    if fk_info.require_existing {
+        return generate_existing_fk_resolution(field, &fk_info);
+    }
+
+    let captures: Vec<TokenStream2> = inheriting
+        .iter()
+        .map(|(child_field, parent_field)| {
+            let inherited_var = format_ident!("inherited_{}", child_field);
+            quote! { #inherited_var = Some(Clone::clone(&entity.#parent_field)); }
+        })
+        .collect();
+
+    // Creates the parent, evaluating to the Result: via create() or `create_method`
+    let create_entity = fk_create_call(&fk_info);
+    let create_entity = if captures.is_empty() {
+        create_entity
+    } else {
+        quote! {
+            {
+                let parent: Result<#entity_type, _> = #create_entity;
+                if let Ok(entity) = &parent {
+                    #(#captures)*
+                }
+                parent
+            }
+        }
+    };
+
+    // Auto-creates the parent and evaluates to its id
+    let create_parent = if let Some(batch) = batch {
+        quote! {
+            let entity: #entity_type = #batch
+                .next()
+                .expect("create_many() returned fewer entities than requested");
+            #(#captures)*
+            #take_id
+        }
+    } else if fk_info.use_last {
+        // Reuse the parent last created on this thread, or create one and remember it
+        quote! {
+            let last = #factory_type::__last_created().with(|last| {
+                last.borrow()
+                    .as_ref()
+                    .and_then(|entity| entity.downcast_ref::<#entity_type>())
+                    .map(|entity| #entity_id_clone)
+            });
+            match last {
+                Some(id) => id,
+                None => {
+                    let entity: #entity_type = #create_entity?;
+                    let id = #entity_id_clone;
+                    #factory_type::__last_created()
+                        .with(|last| *last.borrow_mut() = Some(Box::new(entity)));
+                    id
+                }
+            }
+        }
+    } else if fk_info.shared {
+        // Create the parent once per call, then reuse it for every shared FK
+        // to the same parent factory
+        let field_name_str = field_name.to_string();
+        let record_creator = tracked.then(|| {
+            quote! { shared_creators.insert(key, #field_name_str); }
+        });
+        quote! {
+            let key = std::any::TypeId::of::<#factory_type>();
+            if !shared_parents.contains_key(&key) {
+                let entity: #entity_type = #create_entity?;
+                shared_parents.insert(key, Box::new(entity));
+                #record_creator
+            }
+            let entity = shared_parents[&key]
+                .downcast_ref::<#entity_type>()
+                .expect("shared FKs to one parent factory share its entity type");
+            #(#captures)*
+            #entity_id_clone
+        }
+    } else {
+        quote! {
+            // Auto-create dependency via factory
+            let entity: #entity_type = #create_entity?;
+            #take_id
+        }
+    };
+
+    // find = path: look the parent up first. The finder takes a concrete pool
+    // type, so it only runs when `Pool` is that type (inferred from its signature)
+    let create_parent = match &fk_info.find {
+        Some(find) => {
+            let found_id = wrap_fk_id(field, quote! { id });
+            quote! {
+                let typed_pool = (pool as &dyn std::any::Any).downcast_ref();
+                let found = match typed_pool {
+                    Some(typed_pool) => #find(typed_pool).await,
+                    None => None,
+                };
+                match found {
+                    Some(id) => #found_id,
+                    None => {
+                        #create_parent
+                    }
+                }
+            }
+        }
+        None => create_parent,
+    };
+
+    // max_depth = N: count the parents this FK is creating (nested through their
+    // own build_with_fks) and stop at N. The count is a task-local scoped to the
+    // parent's creation, so it follows the future across worker threads and
+    // builds interleaved on one thread don't see each other's depth
+    let create_parent = match fk_info.max_depth {
+        Some(max_depth) => {
+            let entity_name = &entity_type.segments.last().unwrap().ident;
+            let exceeded_msg = format!(
+                "{field_name} reached max_depth = {max_depth} nested {entity_name} parents - set it explicitly or make it an Option"
+            );
+            let (exceeded, created) = if is_option_field {
+                (quote! { None }, quote! { Some({ #create_parent }) })
+            } else {
+                (
+                    quote! { return Err(#exceeded_msg.into()) },
+                    quote! { { #create_parent } },
+                )
+            };
+            quote! {
+                tokio::task_local! {
+                    static DEPTH: usize;
+                }
+                let depth = DEPTH.try_with(|depth| *depth).unwrap_or(0);
+                if depth >= #max_depth {
+                    #exceeded
+                } else {
+                    DEPTH
+                        .scope(depth + 1, async {
+                            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(#created)
+                        })
+                        .await?
+                }
+            }
+        }
+        None => create_parent,
+    };
+
+    if is_option_field {
+        if fk_info.max_depth.is_some() {
+            // Option<T> with max_depth: auto-create if None/sentinel, None past the depth
+            quote! {
+                let #resolved_var = {
+                    use factory_m8::Sentinel;
+                    match &self.#field_name {
+                        Some(id) if !id.is_sentinel() => Some(id.clone()),
+                        _ => {
+                            #create_parent
+                        }
+                    }
+                };
+            }
+        } else if fk_info.optional_create {
+            // Option<T> with optional_create: auto-create only when the caller asks for it
+            quote! {
+                let #resolved_var = {
+                    use factory_m8::Sentinel;
+                    match &self.#field_name {
+                        Some(id) if !id.is_sentinel() => Some(id.clone()),
+                        _ if options.create_optionals => {
+                            let entity: #entity_type = #create_entity?;
+                            Some(#entity_read)
+                        }
+                        _ => None,
+                    }
+                };
+            }
+        } else if fk_info.no_default && fk_info.not_null {
+            // Option<T> with no_default + not_null: unset is an error, not a NULL insert
+            let error_msg = format!(
+                "{field_name} is NOT NULL but unset - use {}() or {}()",
+                fk_method_name(field_name, entity_field),
+                format_ident!("with_{}", field_name)
+            );
+            quote! {
+                let #resolved_var = {
+                    use factory_m8::Sentinel;
+                    match &self.#field_name {
+                        Some(id) if !id.is_sentinel() => Some(id.clone()),
+                        _ => return Err(#error_msg.into()),
+                    }
+                };
+            }
+        } else if fk_info.no_default {
+            // Option<T> with no_default: don't auto-create, None/sentinel stays None
+            // Returns Option<T> - for truly optional entity fields
+            quote! {
+                let #resolved_var = {
+                    use factory_m8::Sentinel;
+                    match &self.#field_name {
+                        Some(id) if !id.is_sentinel() => Some(id.clone()),
+                        _ => None,  // None or Some(sentinel) stays None
+                    }
+                };
+            }
+        } else if fk_info.null_on_error {
+            // Option<T> with null_on_error: best-effort auto-create, None if it fails
+            quote! {
+                let #resolved_var = {
+                    use factory_m8::Sentinel;
+                    match &self.#field_name {
+                        Some(id) if !id.is_sentinel() => Some(id.clone()),
+                        _ => {
+                            // Auto-create dependency via factory, swallowing the error
+                            let parent: Result<#entity_type, _> = #create_entity;
+                            parent.ok().map(|entity| #take_id)
+                        }
+                    }
+                };
+            }
+        } else {
+            // Option<T> without no_default: auto-create if None/sentinel
+            // Returns Option<T> (Some(id)) - for Option entity fields
+            quote! {
+                let #resolved_var = {
+                    use factory_m8::Sentinel;
+                    Some(match &self.#field_name {
+                        Some(id) if !id.is_sentinel() => id.clone(),
+                        _ => {
+                            #create_parent
+                        }
+                    })
+                };
+            }
+        }
+    } else {
+        // Non-Option field: auto-create if sentinel (no_default doesn't apply)
+        // Returns T
+        let current_value = if needs_clone(&field.ty) {
+            quote! { self.#field_name.clone() }
+        } else {
+            quote! { self.#field_name }
+        };
+        let is_unset = fk_unset_check(field, &fk_info, quote! { self.#field_name });
+        quote! {
+            let #resolved_var = {
+                use factory_m8::Sentinel;
+                if #is_unset {
+                    #create_parent
+                } else {
+                    #current_value
+                }
+            };
+        }
+    }
+}
+
+/// Generates the `resolved_<field>` binding for a `require_existing` FK, which
+/// is never auto-created: an unset non-Option FK is an error, an unset Option FK
+/// `None`. With the `sqlx` feature, a set FK is looked up (see
+/// `fk_fetch_existing`) when the pool is a `sqlx::PgPool`.
+fn generate_existing_fk_resolution(field: &Field, fk_info: &FkAttrInfo) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+    let resolved_var = format_ident!("resolved_{}", field_name);
+
+    if is_option_type(&field.ty) {
+        let check = fk_fetch_existing(field, fk_info, quote! { id }, quote! { _parent });
+        return quote! {
+            let #resolved_var = {
+                use factory_m8::Sentinel;
+                match &self.#field_name {
+                    Some(id) if !id.is_sentinel() => {
+                        #check
+                        Some(id.clone())
+                    }
+                    _ => None,
+                }
+            };
+        };
+    }
+
+    let entity_name = &fk_info.entity_type.segments.last().unwrap().ident;
+    let unset_msg = format!(
+        "{field_name} must reference an existing {entity_name} - use {}() or {}()",
+        fk_method_name(field_name, &fk_info.entity_field),
+        format_ident!("with_{}", field_name)
+    );
+    let is_unset = fk_unset_check(field, fk_info, quote! { self.#field_name });
+    let check = fk_fetch_existing(field, fk_info, quote! { self.#field_name }, quote! { _parent });
+    let current_value = if needs_clone(&field.ty) {
+        quote! { self.#field_name.clone() }
+    } else {
+        quote! { self.#field_name }
+    };
+    quote! {
+        let #resolved_var = {
+            use factory_m8::Sentinel;
+            if #is_unset {
+                return Err(#unset_msg.into());
+            }
+            #check
+            #current_value
+        };
+    }
+}
+
+/// `sqlx` feature: statements binding `binding: Option<Entity>` to the parent a
+/// `require_existing` FK references, fetched by
+/// `SELECT * FROM <ParentFactory::TABLE> WHERE <column> = $1` when `pool` is a
+/// `sqlx::PgPool` (`None` for other pools), returning an error if there's no
+/// such row. `None` without the feature, when nothing is checked.
+fn fk_fetch_existing(
+    field: &Field,
+    fk_info: &FkAttrInfo,
+    id: TokenStream2,
+    binding: TokenStream2,
+) -> Option<TokenStream2> {
+    if !cfg!(feature = "sqlx") {
+        return None;
+    }
+    let field_name = field.ident.as_ref().unwrap();
+    let entity_type = &fk_info.entity_type;
+    let factory_type = &fk_info.factory_type;
+    let column = fk_info.entity_field.to_string();
+    let entity_name = &entity_type.segments.last().unwrap().ident;
+    let missing_msg = format!("{field_name} references a {entity_name} that doesn't exist");
+
+    Some(quote! {
+        let pg_pool = (pool as &dyn std::any::Any).downcast_ref::<sqlx::PgPool>();
+        let #binding: Option<#entity_type> = match pg_pool {
+            Some(pg_pool) => {
+                let sql = format!(
+                    "SELECT * FROM {} WHERE {} = $1",
+                    #factory_type::TABLE,
+                    #column
+                );
+                let parent = sqlx::query_as::<_, #entity_type>(&sql)
+                    .bind(Clone::clone(&#id))
+                    .fetch_optional(pg_pool)
+                    .await?;
+                match parent {
+                    Some(parent) => Some(parent),
+                    None => return Err(#missing_msg.into()),
+                }
+            }
+            None => None,
+        };
+    })
+}
+
+/// Generates the concurrent resolution of `#[factory(parallel_fks)]` FKs: each
+/// field's resolution runs in its own future, and all of them are polled
+/// together, stopping at the first error. Dependency-free, so callers don't
+/// need `futures` or a particular runtime.
+fn generate_parallel_fk_resolutions(fields: &[&Field]) -> Option<TokenStream2> {
+    if fields.is_empty() {
+        return None;
+    }
+    let resolved_vars: Vec<Ident> = fields
+        .iter()
+        .map(|f| format_ident!("resolved_{}", f.ident.as_ref().unwrap()))
+        .collect();
+    let future_vars: Vec<Ident> = fields
+        .iter()
+        .map(|f| format_ident!("resolve_{}", f.ident.as_ref().unwrap()))
+        .collect();
+    let resolutions = fields
+        .iter()
+        .map(|f| generate_fk_resolution(f, None, &[], false));
+
+    Some(quote! {
+        let (#(#resolved_vars,)*) = {
+            #(
+                let mut #future_vars = std::pin::pin!(async {
+                    #resolutions
+                    Ok::<_, Box<dyn std::error::Error + Send + Sync>>(#resolved_vars)
+                });
+            )*
+            #(let mut #resolved_vars = None;)*
+            std::future::poll_fn(|cx| {
+                use std::future::Future;
+
+                let mut pending = false;
+                #(
+                    if #resolved_vars.is_none() {
+                        match #future_vars.as_mut().poll(cx) {
+                            std::task::Poll::Ready(resolved) => #resolved_vars = Some(resolved?),
+                            std::task::Poll::Pending => pending = true,
+                        }
+                    }
+                )*
+                if pending {
+                    std::task::Poll::Pending
+                } else {
+                    std::task::Poll::Ready(Ok::<(), Box<dyn std::error::Error + Send + Sync>>(()))
+                }
+            })
+            .await?;
+            (#(#resolved_vars.unwrap(),)*)
+        };
+    })
+}
+
+/// Generates the debug-build checks for a required (non-Option) FK:
+/// - in `try_build()`, which never auto-creates, the FK must not be the sentinel
+///   (collected as an `UnsetFk` error)
+/// - in `build_with_fks()`, the resolved id must not be the sentinel either,
+///   which catches parent `FactoryCreate` impls that return an unsaved entity
+fn generate_sentinel_fk_checks(field: &Field, error_name: &Ident) -> (TokenStream2, TokenStream2) {
+    let field_name = field.ident.as_ref().unwrap();
+    let resolved_var = format_ident!("resolved_{}", field_name);
+    let fk_info = parse_fk_attr(field).unwrap();
+    let factory_type = &fk_info.factory_type.segments.last().unwrap().ident;
+
+    let build_msg = format!(
+        "{field_name} is unset (sentinel) - build() doesn't create FKs, use {}() or build_with_fks()",
+        fk_method_name(field_name, &fk_info.entity_field)
+    );
+    let resolved_msg = format!(
+        "{field_name} resolved to a sentinel id - check {factory_type}'s FactoryCreate impl"
+    );
+
+    let is_unset = fk_unset_check(field, &fk_info, quote! { self.#field_name });
+    let resolved_is_unset = fk_unset_check(field, &fk_info, quote! { #resolved_var });
+    let field_name_str = field_name.to_string();
+    (
+        quote! {
+            if cfg!(debug_assertions) && #is_unset {
+                errors.push(#error_name::UnsetFk {
+                    field: #field_name_str,
+                    message: #build_msg,
+                });
+            }
+        },
+        quote! {
+            debug_assert!(
+                !#resolved_is_unset,
+                #resolved_msg
+            );
+        },
+    )
+}
+
+fn generate_build_with_fks_assignment(field: &Field) -> TokenStream2 {
+    let field_name = field.ident.as_ref().unwrap();
+
+    // pk: Default, or its `unset_value`
+    if has_attr(field, "pk") {
+        let unset_value = pk_unset_value(field);
+        return quote! {
+            #field_name: #unset_value
+        };
+    }
+
+    // PhantomData: a fresh marker
+    if is_phantom_data_type(&field.ty) {
+        return quote! {
+            #field_name: std::marker::PhantomData
+        };
+    }
+
+    // #[from_fk]: the auto-created parent's value, else the factory's
+    if parse_from_fk_attr(field).is_some() {
+        let inherited_var = format_ident!("inherited_{}", field_name);
+        return quote! {
+            #field_name: #inherited_var.unwrap_or_else(|| Clone::clone(&self.#field_name))
+        };
+    }
+
+    // Vec<Id> FK field: the staged ids as-is
+    if is_vec_fk(field) {
+        return quote! {
+            #field_name: self.#field_name.clone()
+        };
+    }
+
+    // FK field: use resolved variable
+    // The resolved variable type matches the field type (Option<T> or T)
+    if let Some(fk_info) = parse_fk_attr(field) {
+        let resolved_var = format_ident!("resolved_{}", field_name);
+        if fk_info.into_vec {
+            return quote! {
+                #field_name: #resolved_var.into_iter().collect()
+            };
+        }
+        return quote! {
+            #field_name: #resolved_var
+        };
+    }
+
+    // #[sequence] field: fall back to the next value of its counter
+    if let Some(assignment) = generate_sequence_assignment(field) {
+        return assignment;
+    }
+
+    // #[seq] field: number the value set with with_<field>
+    if let Some(assignment) = generate_seq_assignment(field) {
+        return assignment;
+    }
+
+    // #[env("VAR")] field: fall back to the environment variable
+    if let Some(assignment) = generate_env_assignment(field) {
+        return assignment;
+    }
+
+    // #[required] Option field: unwrap (entity field is non-Option)
+    let field_name_str = field_name.to_string();
+    if has_attr(field, "required") && is_option_type(&field.ty) {
+        let error_msg = format!("{field_name_str} is required - use with_{field_name_str}()");
+        return quote! {
+            #field_name: self.#field_name.clone().expect(#error_msg)
+        };
+    }
+
+    // Option field: clone as-is
+    if is_option_type(&field.ty) {
+        return quote! {
+            #field_name: self.#field_name.clone()
+        };
+    }
+
+    // Regular non-Option field: clone or copy
+    if needs_clone(&field.ty) {
+        quote! {
+            #field_name: self.#field_name.clone()
+        }
+    } else {
+        quote! {
+            #field_name: self.#field_name
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: create_tracked()
+// =============================================================================
+
+/// Generates `<Factory>CreatedEntities` (one `Option<ParentEntity>` per
+/// auto-creating FK) and `create_tracked()`.
+///
+/// `create_tracked()` resolves sentinel FKs itself, keeping each created
+/// parent, and writes the parent ids back into the factory before calling the
+/// user's `FactoryCreate::create`. Its `build_with_fks()` then sees the ids as
+/// set and doesn't create the parents a second time.
+fn generate_create_tracked(
+    vis: &syn::Visibility,
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    entity_type: &syn::Path,
+    fields: &[&Field],
+    fk_factory_bounds: &[TokenStream2],
+    batch_fks: bool,
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let created_name = format_ident!("{}CreatedEntities", factory_name);
+    let fk_fields: Vec<&Field> = fields
+        .iter()
+        .filter(|f| parse_fk_attr(f).is_some() && !is_vec_fk(f))
+        .copied()
+        .collect();
+    let from_fk_fields: Vec<(&Field, FromFkAttrInfo)> = fields
+        .iter()
+        .filter_map(|f| Some((*f, parse_from_fk_attr(f)?)))
+        .collect();
+
+    let auto_create_fields: Vec<(&Field, FkAttrInfo)> = fk_fields
+        .iter()
+        .filter_map(|f| Some((*f, parse_fk_attr(f)?)))
+        .filter(|(_, fk_info)| {
+            !fk_info.no_default
+                && !fk_info.optional_create
+                && !fk_info.use_last
+                && fk_info.find.is_none()
+                && !fk_info.require_existing
+                && fk_info.max_depth.is_none()
+        })
+        .collect();
+
+    let created_fields: Vec<TokenStream2> = auto_create_fields
+        .iter()
+        .map(|(f, fk_info)| {
+            let relation = format_ident!(
+                "{}",
+                fk_relation_name(f.ident.as_ref().unwrap(), &fk_info.entity_field)
+            );
+            let parent_type = &fk_info.entity_type;
+            quote! {
+                pub #relation: Option<#parent_type>
+            }
+        })
+        .collect();
+
+    // require_existing FKs (sqlx feature): the parent fetched to check it exists
+    let existing_fields: Vec<(&Field, FkAttrInfo)> = fk_fields
+        .iter()
+        .filter_map(|f| Some((*f, parse_fk_attr(f)?)))
+        .filter(|(_, fk_info)| fk_info.require_existing && cfg!(feature = "sqlx"))
+        .collect();
+
+    let created_fields: Vec<TokenStream2> = created_fields
+        .into_iter()
+        .chain(existing_fields.iter().map(|(f, fk_info)| {
+            let relation = format_ident!(
+                "{}",
+                fk_relation_name(f.ident.as_ref().unwrap(), &fk_info.entity_field)
+            );
+            let parent_type = &fk_info.entity_type;
+            quote! {
+                pub #relation: Option<#parent_type>
+            }
+        }))
+        .collect();
+
+    let existing_resolutions = existing_fields.iter().map(|(f, fk_info)| {
+        let field_name = f.ident.as_ref().unwrap();
+        let relation = format_ident!("{}", fk_relation_name(field_name, &fk_info.entity_field));
+        if is_option_type(&f.ty) {
+            let fetch = fk_fetch_existing(f, fk_info, quote! { id }, quote! { parent });
+            quote! {
+                if let Some(id) = &self.#field_name
+                    && !id.is_sentinel()
+                {
+                    #fetch
+                    created.#relation = parent;
+                }
+            }
+        } else {
+            let is_unset = fk_unset_check(f, fk_info, quote! { self.#field_name });
+            let fetch = fk_fetch_existing(f, fk_info, quote! { self.#field_name }, quote! { parent });
+            quote! {
+                if !(#is_unset) {
+                    #fetch
+                    created.#relation = parent;
+                }
+            }
+        }
+    });
+
+    // The auto-created FKs resolve exactly like in build_with_fks(), batches and
+    // shared memo included, keeping the parents they create
+    let (mut batches, batched_fields) = if batch_fks {
+        let fields: Vec<&Field> = auto_create_fields.iter().map(|(f, _)| *f).collect();
+        generate_fk_batches(&fields)
+    } else {
+        (HashMap::new(), HashMap::new())
+    };
+    let shared_fields: Vec<&Field> = auto_create_fields
+        .iter()
+        .filter(|(_, fk_info)| fk_info.shared)
+        .map(|(f, _)| *f)
+        .collect();
+    let shared_memo = (!shared_fields.is_empty()).then(|| {
+        quote! {
+            let mut shared_parents: std::collections::HashMap<
+                std::any::TypeId,
+                Box<dyn std::any::Any + Send>,
+            > = std::collections::HashMap::new();
+            let mut shared_creators: std::collections::HashMap<std::any::TypeId, &'static str> =
+                std::collections::HashMap::new();
+        }
+    });
+
+    // #[from_fk] fields take the value off a parent created here, as
+    // build_with_fks() won't see that parent
+    let inherited_declarations = from_fk_fields.iter().map(|(f, _)| {
+        let inherited_var = format_ident!("inherited_{}", f.ident.as_ref().unwrap());
+        let field_type = &f.ty;
+        quote! {
+            #[allow(unused_mut)]
+            let mut #inherited_var: Option<#field_type> = None;
+        }
+    });
+    let inherited_assignments = from_fk_fields.iter().map(|(f, _)| {
+        let field_name = f.ident.as_ref().unwrap();
+        let inherited_var = format_ident!("inherited_{}", field_name);
+        quote! {
+            if let Some(value) = #inherited_var {
+                self.#field_name = value;
+            }
+        }
+    });
+
+    let tracked_resolutions: Vec<TokenStream2> = shared_memo
+        .into_iter()
+        .chain(inherited_declarations)
+        .chain(auto_create_fields.iter().map(|(f, _)| {
+            let field_name = f.ident.as_ref().unwrap();
+            let resolved_var = format_ident!("resolved_{}", field_name);
+            let batch = batched_fields.get(field_name);
+            let batch_creation = batch.and_then(|batch_var| batches.remove(batch_var));
+            let inheriting: Vec<(Ident, Ident)> = from_fk_fields
+                .iter()
+                .filter(|(_, info)| &info.parent == field_name)
+                .map(|(child, info)| (child.ident.clone().unwrap(), info.field.clone()))
+                .collect();
+            let resolution = generate_fk_resolution(f, batch, &inheriting, true);
+            quote! {
+                #batch_creation
+                #resolution
+                self.#field_name = #resolved_var;
+            }
+        }))
+        .chain(generate_shared_handoff(&shared_fields))
+        .chain(inherited_assignments)
+        .chain(existing_resolutions)
+        .collect();
+
+    quote! {
+        /// FK parents auto-created by `create_tracked()`; `None` if the FK was already set.
+        /// `require_existing` FKs (`sqlx` feature) hold the existing parent instead.
+        #[derive(Default)]
+        #vis struct #created_name {
+            #(#created_fields),*
+        }
+
+        #[automatically_derived]
+        impl #impl_generics #factory_name #ty_generics #where_clause {
+            /// Create the entity via its `FactoryCreate` impl, also returning the
+            /// FK parents that were auto-created for it.
+            pub async fn create_tracked<Pool>(
+                mut self,
+                pool: &Pool,
+            ) -> Result<(#entity_type, #created_name), Box<dyn std::error::Error + Send + Sync>>
+            where
+                Pool: Sync,
+                Self: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+                #(#fk_factory_bounds,)*
+            {
+                use factory_m8::{FactoryCreate, Sentinel};
+
+                let mut created = #created_name::default();
+                #(#tracked_resolutions)*
+
+                let entity = self.create(pool).await?;
+                Ok((entity, created))
+            }
+        }
+    }
+}
+
+/// Moves each shared parent `create_tracked()` created out of the memo into
+/// `created.<relation>` of the shared FK that created it; the FKs reusing it
+/// report `None`, like FKs that were set explicitly.
+fn generate_shared_handoff(shared_fields: &[&Field]) -> Option<TokenStream2> {
+    if shared_fields.is_empty() {
+        return None;
+    }
+    let handoffs = shared_fields.iter().map(|f| {
+        let field_name = f.ident.as_ref().unwrap();
+        let field_name_str = field_name.to_string();
+        let fk_info = parse_fk_attr(f).unwrap();
+        let relation = format_ident!("{}", fk_relation_name(field_name, &fk_info.entity_field));
+        let factory_type = &fk_info.factory_type;
+        let parent_type = &fk_info.entity_type;
+        quote! {
+            let key = std::any::TypeId::of::<#factory_type>();
+            if shared_creators.get(&key) == Some(&#field_name_str) {
+                created.#relation = shared_parents
+                    .remove(&key)
+                    .and_then(|entity| entity.downcast::<#parent_type>().ok())
+                    .map(|entity| *entity);
+            }
+        }
+    });
+    Some(quote! { #(#handoffs)* })
+}
+
+// =============================================================================
+// CODE GENERATION: create_cached()
+// =============================================================================
+
+/// Generates `create_cached()`, which threads a cache of created entities
+/// (`Vec<Box<dyn Any + Send>>`) through the whole FK chain.
+///
+/// A sentinel FK first looks for an entity of the parent type in the cache and
+/// reuses its id. Otherwise the parent is created through *its* `create_cached()`
+/// (so parent factories must also use `#[factory(cache)]`), which lets
+/// grandparents be reused across different parents. Every entity created this
+/// way, including the returned one, is pushed onto the cache.
+fn generate_create_cached(
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    entity_type: &syn::Path,
+    fk_fields: &[&Field],
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let auto_create_fields: Vec<(&Field, FkAttrInfo)> = fk_fields
+        .iter()
+        .filter_map(|f| Some((*f, parse_fk_attr(f)?)))
+        .filter(|(_, fk_info)| {
+            !fk_info.no_default
+                && !fk_info.optional_create
+                && !fk_info.use_last
+                && fk_info.find.is_none()
+                && !fk_info.require_existing
+                && fk_info.max_depth.is_none()
+        })
+        .collect();
+
+    let parent_bounds: Vec<TokenStream2> = auto_create_fields
+        .iter()
+        .map(|(_, fk_info)| {
+            let parent_type = &fk_info.entity_type;
+            let parent_factory = &fk_info.factory_type;
+            quote! {
+                #parent_factory: factory_m8::FactoryCreate<Pool, Entity = #parent_type>,
+                #parent_type: Clone + Send + 'static
+            }
+        })
+        .collect();
+
+    let cached_resolutions: Vec<TokenStream2> = auto_create_fields
+        .iter()
+        .map(|(f, fk_info)| {
+            let field_name = f.ident.as_ref().unwrap();
+            let parent_type = &fk_info.entity_type;
+            let parent_read = fk_read_id(fk_info, quote! { parent });
+            let parent_factory = &fk_info.factory_type;
+            let id = wrap_fk_id(f, quote! { id });
+            let assigned = if is_option_type(&f.ty) {
+                quote! { Some(#id) }
+            } else {
+                id
+            };
+
+            // null_on_error: a failed create leaves the FK for build_with_fks() to null out
+            let create_parent = if fk_info.null_on_error {
+                quote! {
+                    #parent_factory::new()
+                        .create_cached(pool, cache)
+                        .await
+                        .ok()
+                        .map(|parent| #parent_read)
+                }
+            } else {
+                quote! {
+                    {
+                        let parent = #parent_factory::new().create_cached(pool, cache).await?;
+                        Some(#parent_read)
+                    }
+                }
+            };
+
+            let is_unset = fk_unset_check(f, fk_info, quote! { self.#field_name });
+            quote! {
+                if #is_unset {
+                    let cached = cache
+                        .iter()
+                        .rev()
+                        .find_map(|entity| entity.downcast_ref::<#parent_type>())
+                        .map(|parent| #parent_read.clone());
+                    let id = match cached {
+                        Some(id) => Some(id),
+                        None => #create_parent,
+                    };
+                    if let Some(id) = id {
+                        self.#field_name = #assigned;
+                    }
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics #factory_name #ty_generics #where_clause {
+            /// Create the entity via its `FactoryCreate` impl, reusing FK parents
+            /// found in `cache` instead of creating new ones.
+            ///
+            /// Parents that do need creating go through their own `create_cached()`,
+            /// so the cache is shared across the whole FK chain. The created entity
+            /// is pushed onto the cache as well.
+            pub async fn create_cached<Pool>(
+                mut self,
+                pool: &Pool,
+                cache: &mut Vec<Box<dyn std::any::Any + Send>>,
+            ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
+            where
+                Pool: Sync,
+                Self: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+                #entity_type: Clone + Send + 'static,
+                #(#parent_bounds,)*
+            {
+                #[allow(unused_imports)]
+                use factory_m8::{FactoryCreate, Sentinel};
+
+                #(#cached_resolutions)*
+
+                let entity = self.create(pool).await?;
+                cache.push(Box::new(entity.clone()));
+                Ok(entity)
+            }
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: create_deduped()
+// =============================================================================
+
+/// Generates `create_deduped()` for factories with `#[fk(..., dedup)]` FKs
+/// (nothing otherwise).
+///
+/// For each unset dedup FK, the parent factory (`ParentFactory::new()`) is hashed
+/// and looked up in `dedup`, a map from config hash to parent id shared across a
+/// batch of creates. A hit reuses that parent's id, a miss creates the parent and
+/// records its id. Parent factories must implement `Hash`, so parents whose
+/// defaults differ (e.g. unique names) are still created separately.
+fn generate_create_deduped(
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    entity_type: &syn::Path,
+    fk_fields: &[&Field],
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let dedup_fields: Vec<(&Field, FkAttrInfo)> = fk_fields
+        .iter()
+        .filter_map(|f| Some((*f, parse_fk_attr(f)?)))
+        .filter(|(_, fk_info)| fk_info.dedup)
+        .collect();
+
+    if dedup_fields.is_empty() {
+        return quote! {};
+    }
+
+    let mut parent_bounds = Vec::new();
+    let mut dedup_resolutions = Vec::new();
+    for (f, fk_info) in &dedup_fields {
+        let field_name = f.ident.as_ref().unwrap();
+        let parent_type = &fk_info.entity_type;
+        let parent_read = fk_read_id(fk_info, quote! { parent });
+        let parent_factory = &fk_info.factory_type;
+        let id_type = fk_id_type(f);
+        let id = wrap_fk_id(f, quote! { id });
+        let assigned = if is_option_type(&f.ty) {
+            quote! { Some(#id) }
+        } else {
+            id
+        };
+
+        parent_bounds.push(quote! {
+            #parent_factory: factory_m8::FactoryCreate<Pool, Entity = #parent_type>
+                + std::hash::Hash
+                + 'static,
+            #id_type: Clone + Send + 'static
+        });
+
+        // null_on_error: a failed create leaves the FK for build_with_fks() to null out
+        let create_parent = if fk_info.null_on_error {
+            quote! { parent_factory.create(pool).await.ok() }
+        } else {
+            quote! { Some(parent_factory.create(pool).await?) }
+        };
+
+        let is_unset = fk_unset_check(f, fk_info, quote! { self.#field_name });
+        dedup_resolutions.push(quote! {
+            if #is_unset {
+                let parent_factory = #parent_factory::new();
+                let key = {
+                    use std::hash::{Hash, Hasher};
+                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                    std::any::TypeId::of::<#parent_factory>().hash(&mut hasher);
+                    parent_factory.hash(&mut hasher);
+                    hasher.finish()
+                };
+                let existing = dedup
+                    .get(&key)
+                    .and_then(|id| id.downcast_ref::<#id_type>())
+                    .cloned();
+                let id = match existing {
+                    Some(id) => Some(id),
+                    None => {
+                        let parent: Option<#parent_type> = #create_parent;
+                        parent.map(|parent| {
+                            let id = #parent_read;
+                            dedup.insert(key, Box::new(id.clone()));
+                            id
+                        })
+                    }
+                };
+                if let Some(id) = id {
+                    self.#field_name = #assigned;
+                }
+            }
+        });
+    }
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics #factory_name #ty_generics #where_clause {
+            /// Create the entity via its `FactoryCreate` impl, sharing auto-created
+            /// `dedup` FK parents with earlier creates whose parent factory config
+            /// was identical.
+            ///
+            /// `dedup` maps a hash of each parent factory config to the created
+            /// parent's id; reuse the same map for a whole batch.
+            pub async fn create_deduped<Pool>(
+                mut self,
+                pool: &Pool,
+                dedup: &mut std::collections::HashMap<u64, Box<dyn std::any::Any + Send>>,
+            ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
+            where
+                Pool: Sync,
+                Self: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+                #(#parent_bounds,)*
+            {
+                use factory_m8::{FactoryCreate, Sentinel};
+
+                #(#dedup_resolutions)*
+
+                self.create(pool).await
+            }
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: mock FactoryCreate impl
+// =============================================================================
+
+/// Generates `FactoryCreate<MockPool>`, an in-memory backend that returns
+/// `build_with_fks(pool)` without touching a database. The `#[pk]` field gets a
+/// fake id from a per-factory counter starting at 1, so the pk type must
+/// implement `From<i64>`.
+///
+/// Sentinel FKs are resolved through the parent factories' `FactoryCreate<MockPool>`,
+/// so parents need `mock` as well (or a hand-written impl), and whole FK chains
+/// get fake ids without any database.
+///
+/// The impl is written out by hand (instead of via `#[async_trait]`) so the
+/// user's crate doesn't need `async-trait` for it.
+fn generate_mock_create(
+    factory_name: &Ident,
+    entity_type: &syn::Path,
+    mock_pool: &syn::Path,
+    fields: &[&Field],
+) -> TokenStream2 {
+    let assign_pk = fields.iter().find(|f| has_attr(f, "pk")).map(|f| {
+        let pk_name = f.ident.as_ref().unwrap();
+        let pk_type = &f.ty;
+        quote! {
+            static NEXT_ID: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(1);
+            let next_id = NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            entity.#pk_name = <#pk_type as From<i64>>::from(next_id);
+        }
+    });
+
+    quote! {
+        #[automatically_derived]
+        impl factory_m8::FactoryCreate<#mock_pool> for #factory_name {
+            type Entity = #entity_type;
+
+            fn create<'life0, 'async_trait>(
+                self,
+                pool: &'life0 #mock_pool,
+            ) -> std::pin::Pin<
+                Box<
+                    dyn std::future::Future<
+                            Output = Result<
+                                #entity_type,
+                                Box<dyn std::error::Error + Send + Sync>,
+                            >,
+                        > + Send
+                        + 'async_trait,
+                >,
+            >
+            where
+                'life0: 'async_trait,
+                Self: 'async_trait,
+            {
+                Box::pin(async move {
+                    #[allow(unused_mut)]
+                    let mut entity = self.build_with_fks(pool).await?;
+                    #assign_pk
+                    Ok(entity)
+                })
+            }
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: proptest arb()
+// =============================================================================
+
+/// Generates `arb()`, a proptest strategy producing factories from `new()`
+/// with random values for string, integer and bool fields (`Option` fields may
+/// be `None` unless `#[required]`) and every FK reset to its sentinel, so
+/// `build_with_fks()` creates the parents. Other fields keep their `new()` value.
+fn generate_arb_fn(fields: &[&Field]) -> TokenStream2 {
+    let mut fk_resets = Vec::new();
+    let mut randomized = Vec::new();
+    for f in fields.iter().filter(|f| !has_attr(f, "pk")) {
+        let field_name = f.ident.as_ref().unwrap();
+        if is_vec_fk(f) {
+            continue;
+        }
+        if parse_fk_attr(f).is_some() {
+            let sentinel = fk_sentinel_value(f);
+            fk_resets.push(quote! {
+                factory.#field_name = #sentinel;
+            });
+            continue;
+        }
+
+        let value_type = extract_option_inner_type(&f.ty).unwrap_or(&f.ty);
+        let value_strategy = if is_string_type(value_type) {
+            quote! { "[a-z]{1,16}" }
+        } else if is_integer_type(value_type)
+            || matches!(value_type, Type::Path(tp) if tp.path.is_ident("bool"))
+        {
+            quote! { proptest::prelude::any::<#value_type>() }
+        } else {
+            continue;
+        };
+        let strategy = if !is_option_type(&f.ty) {
+            value_strategy
+        } else if has_attr(f, "required") {
+            quote! { #value_strategy.prop_map(Some) }
+        } else {
+            quote! { proptest::option::of(#value_strategy) }
+        };
+        randomized.push((field_name, strategy));
+    }
+
+    // Nest as (a, (b, (c, Just(())))) - proptest tuples stop at 12 elements
+    let strategies = randomized.iter().rev().fold(
+        quote! { proptest::strategy::Just(()) },
+        |rest, (_, strategy)| quote! { (#strategy, #rest) },
+    );
+    let pattern = randomized
+        .iter()
+        .rev()
+        .fold(quote! { () }, |rest, (name, _)| quote! { (#name, #rest) });
+    let assignments = randomized.iter().map(|(name, _)| {
+        quote! { factory.#name = #name; }
+    });
+
+    quote! {
+        /// A proptest strategy over factories: `new()` with random string,
+        /// integer and bool fields, and FKs left unset for auto-creation.
+        pub fn arb() -> impl proptest::strategy::Strategy<Value = Self> {
+            use proptest::strategy::Strategy;
+
+            #strategies.prop_map(|#pattern| {
+                let mut factory = Self::new();
+                #(#fk_resets)*
+                #(#assignments)*
+                factory
+            })
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: sqlx upsert create
+// =============================================================================
+
+/// The columns of a generated `INSERT`: the non-pk, non-`PhantomData` fields,
+/// in declaration order or in `column_order(...)` order. It's an error unless
+/// `column_order` lists every column exactly once.
+fn insert_columns<'a>(
+    fields: &[&'a Field],
+    column_order: Option<&[Ident]>,
+) -> syn::Result<Vec<&'a Ident>> {
+    let columns: Vec<&Ident> = fields
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| !is_phantom_data_type(&f.ty))
+        .map(|f| f.ident.as_ref().unwrap())
+        .collect();
+
+    let Some(order) = column_order else {
+        return Ok(columns);
+    };
+    let mut ordered: Vec<&Ident> = Vec::new();
+    for name in order {
+        let Some(column) = columns.iter().find(|c| **c == name) else {
+            return Err(syn::Error::new_spanned(
+                name,
+                format!("column_order: `{name}` is not a non-pk field"),
+            ));
+        };
+        if ordered.contains(column) {
+            return Err(syn::Error::new_spanned(
+                name,
+                format!("column_order: `{name}` is listed twice"),
+            ));
+        }
+        ordered.push(column);
+    }
+    if let Some(missing) = columns.iter().find(|c| !ordered.contains(c)) {
+        return Err(syn::Error::new_spanned(
+            quote! { #(#order),* },
+            format!("column_order: `{missing}` is missing"),
+        ));
+    }
+    Ok(ordered)
+}
+
+/// Generates `FactoryCreate<sqlx::PgPool>` whose `create` runs
+/// `INSERT INTO table (...) VALUES ($1, ...) RETURNING *`, binding the columns
+/// in order. The SQL is also exposed as `INSERT_SQL`.
+///
+/// With `upsert`, it runs `INSERT ... ON CONFLICT (col) DO UPDATE SET ... RETURNING *`
+/// instead, so creating the same logical row twice keeps a single row (the second
+/// create updates it). Every column is overwritten with the new value on conflict,
+/// which also makes `RETURNING *` return the existing row. That SQL is exposed as
+/// `UPSERT_SQL`.
+///
+/// Either way, FKs are resolved with `build_with_fks(pool)` first.
+fn generate_sqlx_create(
+    factory_name: &Ident,
+    entity_type: &syn::Path,
+    table: &LitStr,
+    upsert: Option<&LitStr>,
+    columns: &[&Ident],
+) -> TokenStream2 {
+    let column_list = columns
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let placeholders = (1..=columns.len())
+        .map(|i| format!("${i}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let insert = format!(
+        "INSERT INTO {} ({column_list}) VALUES ({placeholders})",
+        table.value()
+    );
+
+    let (sql_const, sql, doc) = match upsert {
+        Some(conflict) => {
+            let updates = columns
+                .iter()
+                .map(|c| format!("{c} = EXCLUDED.{c}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            (
+                format_ident!("UPSERT_SQL"),
+                format!(
+                    "{insert} ON CONFLICT ({}) DO UPDATE SET {updates} RETURNING *",
+                    conflict.value()
+                ),
+                "The `INSERT ... ON CONFLICT` statement run by `create()`",
+            )
+        }
+        None => (
+            format_ident!("INSERT_SQL"),
+            format!("{insert} RETURNING *"),
+            "The `INSERT` statement run by `create()`",
+        ),
+    };
+
+    quote! {
+        #[automatically_derived]
+        impl #factory_name {
+            #[doc = #doc]
+            pub const #sql_const: &'static str = #sql;
+        }
+
+        #[automatically_derived]
+        impl factory_m8::FactoryCreate<sqlx::PgPool> for #factory_name {
+            type Entity = #entity_type;
+
+            fn create<'life0, 'async_trait>(
+                self,
+                pool: &'life0 sqlx::PgPool,
+            ) -> std::pin::Pin<
+                Box<
+                    dyn std::future::Future<
+                            Output = Result<
+                                #entity_type,
+                                Box<dyn std::error::Error + Send + Sync>,
+                            >,
+                        > + Send
+                        + 'async_trait,
+                >,
+            >
+            where
+                'life0: 'async_trait,
+                Self: 'async_trait,
+            {
+                Box::pin(async move {
+                    let entity = self.build_with_fks(pool).await?;
+
+                    let created = sqlx::query_as::<_, #entity_type>(Self::#sql_const)
+                        #(.bind(entity.#columns))*
+                        .fetch_one(pool)
+                        .await?;
+
+                    Ok(created)
+                })
+            }
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: per-row generators (#[each])
+// =============================================================================
+
+/// Generates `<Factory>Each`, the type of the `#[each]` storage field, holding
+/// an optional `Fn(usize) -> T` generator per non-pk, non-FK field, plus:
+/// - `with_<field>_each(f)` - store a generator for the field
+/// - `apply_each(index)` - set every field that has a generator from `f(index)`
+///
+/// Generators are kept in `Arc`s so the factory stays `Clone`; `Debug` only
+/// lists which fields have one.
+fn generate_each(
+    vis: &syn::Visibility,
+    factory_name: &Ident,
+    storage: &Field,
+    fields: &[&Field],
+) -> (TokenStream2, TokenStream2) {
+    let each_name = format_ident!("{}Each", factory_name);
+    let storage_name = storage.ident.as_ref().unwrap();
+
+    let each_fields: Vec<(&Ident, &Type, bool)> = fields
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| parse_fk_attr(f).is_none())
+        .map(|f| {
+            let value_type = extract_option_inner_type(&f.ty).unwrap_or(&f.ty);
+            (f.ident.as_ref().unwrap(), value_type, is_option_type(&f.ty))
+        })
+        .collect();
+
+    let declarations = each_fields.iter().map(|(field_name, value_type, _)| {
+        quote! {
+            pub #field_name: Option<std::sync::Arc<dyn Fn(usize) -> #value_type + Send + Sync>>
+        }
+    });
+    let field_names: Vec<&Ident> = each_fields.iter().map(|(name, _, _)| *name).collect();
+
+    let setters = each_fields.iter().map(|(field_name, value_type, _)| {
+        let method_name = format_ident!("with_{}_each", field_name);
+        quote! {
+            /// Set field value per row: `generate` is called with the row index.
+            pub fn #method_name(
+                mut self,
+                generate: impl Fn(usize) -> #value_type + Send + Sync + 'static,
+            ) -> Self {
+                self.#storage_name.#field_name = Some(std::sync::Arc::new(generate));
+                self
+            }
+        }
+    });
+
+    let applications = each_fields.iter().map(|(field_name, _, is_option)| {
+        let value = if *is_option {
+            quote! { Some(generate(index)) }
+        } else {
+            quote! { generate(index) }
+        };
+        quote! {
+            if let Some(generate) = &self.#storage_name.#field_name {
+                self.#field_name = #value;
+            }
+        }
+    });
+
+    let doc = format!("Per-row generators for `{factory_name}`, set via `with_<field>_each`.");
+
+    let each_struct = quote! {
+        #[doc = #doc]
+        #vis struct #each_name {
+            #(#declarations),*
+        }
+
+        #[automatically_derived]
+        impl Default for #each_name {
+            fn default() -> Self {
+                Self {
+                    #(#field_names: None),*
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl Clone for #each_name {
+            fn clone(&self) -> Self {
+                Self {
+                    #(#field_names: self.#field_names.clone()),*
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl std::fmt::Debug for #each_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(stringify!(#each_name))
+                    #(.field(stringify!(#field_names), &self.#field_names.is_some()))*
+                    .finish()
+            }
+        }
+    };
+
+    let each_methods = quote! {
+        #(#setters)*
+
+        /// Set every field that has a `with_<field>_each` generator to its value
+        /// for row `index`.
+        pub fn apply_each(&mut self, index: usize) {
+            #(#applications)*
+        }
+    };
+
+    (each_struct, each_methods)
+}
+
+// =============================================================================
+// CODE GENERATION: grouped setters (#[group])
+// =============================================================================
+
+/// Generates a scoped builder per `#[group("name")]`: a `<Factory><Name>Setters`
+/// struct wrapping the factory with the `with_<field>` setters of the group's
+/// fields, and a `name(|g| ...)` method on the factory that applies them.
+///
+/// The grouped fields keep their regular setters on the factory as well.
+fn generate_groups(
+    vis: &syn::Visibility,
+    factory_name: &Ident,
+    fields: &[&Field],
+    into_setters: bool,
+) -> (TokenStream2, TokenStream2) {
+    // Groups in order of first appearance
+    let mut groups: Vec<(LitStr, Vec<&Field>)> = Vec::new();
+    for f in fields {
+        let Some(group) = parse_group_attr(f) else {
+            continue;
+        };
+        match groups
+            .iter_mut()
+            .find(|(name, _)| name.value() == group.value())
+        {
+            Some((_, members)) => members.push(f),
+            None => groups.push((group, vec![f])),
+        }
+    }
+
+    let mut structs = Vec::new();
+    let mut methods = Vec::new();
+    for (group, members) in &groups {
+        let group_ident = Ident::new(&group.value(), group.span());
+        let setters_name = format_ident!(
+            "{}{}Setters",
+            factory_name,
+            to_upper_camel_case(&group_ident)
+        );
+
+        let setters = members.iter().map(|f| {
+            let field_name = f.ident.as_ref().unwrap();
+            let method_name = format_ident!("with_{}", field_name);
+            let value_type = setter_value_type(f, into_setters);
+            let doc = format!("Set `{field_name}`.");
+            let deprecation = field_deprecation(f);
+            quote! {
+                #[doc = #doc]
+                #deprecation
+                #[allow(deprecated)]
+                pub fn #method_name(self, value: #value_type) -> Self {
+                    Self(self.0.#method_name(value))
+                }
+            }
+        });
+
+        let doc = format!(
+            "Setters for the `{}` fields of `{factory_name}`, used via `{factory_name}::{}`.",
+            group.value(),
+            group.value()
+        );
+        structs.push(quote! {
+            #[doc = #doc]
+            #vis struct #setters_name(#factory_name);
+
+            #[automatically_derived]
+            impl #setters_name {
+                #(#setters)*
+            }
+        });
+
+        let method_doc = format!("Set the `{}` fields in one scoped builder.", group.value());
+        methods.push(quote! {
+            #[doc = #method_doc]
+            pub fn #group_ident(self, set: impl FnOnce(#setters_name) -> #setters_name) -> Self {
+                set(#setters_name(self)).0
+            }
+        });
+    }
+
+    (quote! { #(#structs)* }, quote! { #(#methods)* })
+}
+
+// =============================================================================
+// CODE GENERATION: defaults()
+// =============================================================================
+
+/// Generates `defaults()`, which builds the factory without its `Default` impl:
+/// FK fields start at their sentinel (so they auto-create), non-FK `Option`
+/// fields at `None`, and every other field at its type's `Default`.
+fn generate_defaults_fn(fields: &[&Field]) -> TokenStream2 {
+    let initializers = fields.iter().map(|f| {
+        let field_name = f.ident.as_ref().unwrap();
+        let field_type = &f.ty;
+        if parse_fk_attr(f).is_some() && !is_vec_fk(f) {
+            let sentinel = fk_sentinel_value(f);
+            quote! { #field_name: #sentinel }
+        } else if let Some(unset_value) = parse_pk_unset_value(f) {
+            quote! { #field_name: #unset_value }
+        } else if is_option_type(field_type) {
+            quote! { #field_name: None }
+        } else {
+            quote! { #field_name: Default::default() }
+        }
+    });
+
+    quote! {
+        /// Factory defaults, independent of any `Default` impl: FK fields are
+        /// sentinel, optional fields `None`, the rest their type's default.
+        pub fn defaults() -> Self {
+            Self {
+                #(#initializers,)*
+            }
+        }
+    }
+}
+
+/// Generates the factory's `impl Default` for `#[factory(derive_default)]`: the
+/// `#[pk]` and non-Option FK fields start at their sentinel, `Option` fields at
+/// `None`, the rest at their type's default.
+fn generate_default_impl(
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    fields: &[&Field],
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let initializers = fields.iter().map(|f| {
+        let field_name = f.ident.as_ref().unwrap();
+        let field_type = &f.ty;
+        let is_sentinel_fk = parse_fk_attr(f).is_some() && !is_vec_fk(f);
+        if let Some(unset_value) = parse_pk_unset_value(f) {
+            quote! { #field_name: #unset_value }
+        } else if is_option_type(field_type) {
+            quote! { #field_name: None }
+        } else if is_sentinel_fk {
+            let sentinel = fk_sentinel_value(f);
+            quote! { #field_name: #sentinel }
+        } else if has_attr(f, "pk") {
+            quote! { #field_name: <#field_type as factory_m8::Sentinel>::sentinel() }
+        } else {
+            quote! { #field_name: Default::default() }
+        }
+    });
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics Default for #factory_name #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #(#initializers,)*
+                }
+            }
+        }
+    }
+}
+
+/// Generates `impl TryFrom<HashMap<String, String>>`, starting from `new()` and
+/// setting each field whose name is a key in the map:
+/// - `String` fields take the value as is
+/// - other fields parse it via `FromStr`, failing with `"<field>: <parse error>"`
+/// - `Option` fields are `None` for an empty value, otherwise set like their inner type
+///
+/// Keys that don't name a field are ignored, as are the pk, `PhantomData` and
+/// many-to-many FK fields, which never come from a row.
+fn generate_from_map_impl(
+    factory_name: &Ident,
+    generics: &syn::Generics,
+    fields: &[&Field],
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let arms = fields
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| !is_phantom_data_type(&f.ty) && !is_vec_fk(f))
+        .map(|f| {
+            let field_name = f.ident.as_ref().unwrap();
+            let key = field_name.to_string();
+            let value_type = extract_option_inner_type(&f.ty).unwrap_or(&f.ty);
+            let value = if is_string_type(value_type) {
+                quote! { value.clone() }
+            } else {
+                quote! {
+                    value
+                        .parse::<#value_type>()
+                        .map_err(|error| format!("{}: {error}", #key))?
+                }
+            };
+            if is_option_type(&f.ty) {
+                quote! {
+                    #key => factory.#field_name = if value.is_empty() { None } else { Some(#value) },
+                }
+            } else {
+                quote! { #key => factory.#field_name = #value, }
+            }
+        });
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics TryFrom<std::collections::HashMap<String, String>>
+            for #factory_name #ty_generics #where_clause
+        {
+            type Error = Box<dyn std::error::Error + Send + Sync>;
+
+            /// Factory from a string map (e.g. a CSV row), parsing each value into
+            /// the field named by its key. Unknown keys are ignored.
+            fn try_from(
+                map: std::collections::HashMap<String, String>,
+            ) -> Result<Self, Self::Error> {
+                let mut factory = Self::new();
+                for (key, value) in &map {
+                    match key.as_str() {
+                        #(#arms)*
+                        _ => {}
+                    }
+                }
+                Ok(factory)
+            }
+        }
+    }
+}
+
+/// Generates `normalize(&mut self)`, which puts the factory in canonical form
+/// before a build:
+/// - a non-Option FK that counts as unset (sentinel, or `is_unset`) is reset to
+///   the sentinel, so it's auto-created
+/// - an `Option` FK holding `Some(sentinel)` becomes `None`
+/// - an unset `#[required]` field gets its type's default (unless its `#[env]`
+///   variable is set, which the build reads instead)
+fn generate_normalize_fn(fields: &[&Field]) -> TokenStream2 {
+    let mut bounds = Vec::new();
+    let steps: Vec<TokenStream2> = fields
+        .iter()
+        .filter_map(|f| {
+            let field_name = f.ident.as_ref().unwrap();
+            if let Some(fk_info) = parse_fk_attr(f) {
+                if is_vec_fk(f) {
+                    return None;
+                }
+                if is_option_type(&f.ty) {
+                    let is_unset = fk_option_unset_check(f, quote! { self.#field_name });
+                    return Some(quote! {
+                        if #is_unset {
+                            self.#field_name = None;
+                        }
+                    });
+                }
+                // unset_when ids have no sentinel to reset to, and already count as unset
+                if fk_info.unset_when.is_some() {
+                    return None;
+                }
+                let is_unset = fk_unset_check(f, &fk_info, quote! { self.#field_name });
+                let sentinel = fk_sentinel_value(f);
+                return Some(quote! {
+                    if #is_unset {
+                        self.#field_name = #sentinel;
+                    }
+                });
+            }
+            if !has_attr(f, "required") {
+                return None;
+            }
+            let inner_type = extract_option_inner_type(&f.ty)?;
+            bounds.push(quote! { #inner_type: Default });
+            let unset = match parse_env_attr(f) {
+                Some(var_name) => {
+                    quote! { self.#field_name.is_none() && std::env::var(#var_name).is_err() }
+                }
+                None => quote! { self.#field_name.is_none() },
+            };
+            Some(quote! {
+                if #unset {
+                    self.#field_name = Some(Default::default());
+                }
+            })
+        })
+        .collect();
+
+    quote! {
+        /// Put the factory in canonical form: unset non-Option FKs back at their
+        /// sentinel (to be auto-created), `Some(sentinel)` Option FKs `None`, and
+        /// unset `#[required]` fields at their type's default.
+        pub fn normalize(&mut self)
+        where
+            // higher-ranked so required fields without Default still compile without it
+            #(for<'a> #bounds,)*
+        {
+            #(#steps)*
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: partial update struct
+// =============================================================================
+
+/// Generates `<Entity>Update`, holding an `Option` of every non-pk entity field,
+/// and `apply(&self, &mut Entity)`, which overwrites only the `Some` fields.
+///
+/// Field types follow the entity: a `#[required]` `Option<T>` factory field is
+/// `T` on the entity, so its update field is `Option<T>`, not `Option<Option<T>>`.
+fn generate_update_struct(
+    vis: &syn::Visibility,
+    entity_type: &syn::Path,
+    fields: &[&Field],
+) -> TokenStream2 {
+    let entity_name = &entity_type.segments.last().unwrap().ident;
+    let update_name = format_ident!("{}Update", entity_name);
+
+    let update_fields: Vec<(&Ident, Type)> = fields
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .map(|f| {
+            let into_vec = parse_fk_attr(f).is_some_and(|fk_info| fk_info.into_vec);
+            let entity_field_type = if has_attr(f, "required") {
+                extract_option_inner_type(&f.ty).unwrap_or(&f.ty).clone()
+            } else if let Some(id_type) = extract_option_inner_type(&f.ty).filter(|_| into_vec) {
+                syn::parse_quote! { Vec<#id_type> }
+            } else {
+                f.ty.clone()
+            };
+            (f.ident.as_ref().unwrap(), entity_field_type)
+        })
+        .collect();
+
+    let declarations = update_fields.iter().map(|(field_name, field_type)| {
+        quote! { pub #field_name: Option<#field_type> }
+    });
+
+    let applications = update_fields.iter().map(|(field_name, _)| {
+        quote! {
+            if let Some(value) = &self.#field_name {
+                entity.#field_name = value.clone();
+            }
+        }
+    });
+
+    let doc = format!("Partial update of a `{entity_name}`: only `Some` fields are applied.");
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Default)]
+        #vis struct #update_name {
+            #(#declarations),*
+        }
+
+        #[automatically_derived]
+        impl #update_name {
+            /// Overwrite the entity's fields that are `Some` in this update.
+            pub fn apply(&self, entity: &mut #entity_type) {
+                #(#applications)*
+            }
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: factory inheritance
+// =============================================================================
+
+/// Name of the `macro_rules!` a `base` factory emits to generate its
+/// delegating setters on a child factory
+fn delegate_macro_name(base_factory: &Ident) -> Ident {
+    format_ident!("__{}_delegate_setters", base_factory)
+}
+
+/// Generates the pieces a `base` factory exposes to child factories:
+/// - `FACTORY_SCHEMA`, the names of the base's settable fields
+/// - `__<Factory>_delegate_setters!(Child, field)`, which implements each of the
+///   base's canonical `with_*` setters on `Child` by forwarding to `Child.field`
+///
+/// A proc macro can't look at another struct's fields, so the base generates the
+/// delegating setters itself. The child's derive only invokes the macro, which
+/// means the base must be declared before the child (textual `macro_rules!` scope).
+fn generate_base_delegation(
+    factory_name: &Ident,
+    fields: &[&Field],
+    into_setters: bool,
+) -> TokenStream2 {
+    let settable: Vec<&Field> = fields
+        .iter()
+        .filter(|f| !has_attr(f, "pk"))
+        .filter(|f| !is_phantom_data_type(&f.ty))
+        .copied()
+        .collect();
+
+    let schema: Vec<String> = settable
+        .iter()
+        .map(|f| f.ident.as_ref().unwrap().to_string())
+        .collect();
+
+    let delegating_setters: Vec<TokenStream2> = settable
+        .iter()
+        .flat_map(|f| {
+            let field_name = f.ident.as_ref().unwrap();
+            let method_name = format_ident!("with_{}", field_name);
+            let value_type = setter_value_type(f, into_setters);
+            let doc = format!("Set `{field_name}` on the base `{factory_name}`.");
+            let deprecation = field_deprecation(f);
+            let mut setters = vec![quote! {
+                #[doc = #doc]
+                #deprecation
+                #[allow(deprecated)]
+                pub fn #method_name(mut self, value: #value_type) -> Self {
+                    self.$base = self.$base.#method_name(value);
+                    self
+                }
+            }];
+
+            if let Some(fk_info) = parse_fk_attr(f).filter(|_| !is_vec_fk(f)) {
+                let relation_method = fk_method_name(field_name, &fk_info.entity_field);
+                let fk_entity_type = fk_info.entity_type;
+                setters.push(quote! {
+                    #[doc = #doc]
+                    #deprecation
+                    #[allow(deprecated)]
+                    pub fn #relation_method(
+                        mut self,
+                        entity: impl std::borrow::Borrow<#fk_entity_type>,
+                    ) -> Self {
+                        self.$base = self.$base.#relation_method(entity);
+                        self
+                    }
+                });
+            }
+
+            setters
+        })
+        .collect();
+
+    let delegate_macro = delegate_macro_name(factory_name);
+
+    quote! {
+        #[automatically_derived]
+        impl #factory_name {
+            /// Names of the fields a child factory can set through this base.
+            pub const FACTORY_SCHEMA: &'static [&'static str] = &[#(#schema),*];
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #delegate_macro {
+            ($child:ident, $base:ident) => {
+                impl $child {
+                    #(#delegating_setters)*
+                }
+            };
+        }
+    }
+}
+
+// =============================================================================
+// CODE GENERATION: typestate builder
+// =============================================================================
+
+/// Generates `<Factory>Builder`, a wrapper that tracks in its type parameters
+/// which `#[required]` fields have been set. Each required setter moves its
+/// state parameter from `<Factory>Unset` to `<Factory>Set`, and `build()` and
+/// `create()` only exist once every state is `Set`.
+///
+/// Non-required fields are configured through `configure()`, which hands the
+/// wrapped factory to a closure so all regular `with_*` setters stay available.
+fn generate_typestate_builder(
+    vis: &syn::Visibility,
+    factory_name: &Ident,
+    entity_type: &syn::Path,
+    fields: &[&Field],
+    into_setters: bool,
+) -> TokenStream2 {
+    let builder_name = format_ident!("{}Builder", factory_name);
+    let set_marker = format_ident!("{}Set", factory_name);
+    let unset_marker = format_ident!("{}Unset", factory_name);
+
+    let required_fields: Vec<&Field> = fields
+        .iter()
+        .filter(|f| has_attr(f, "required") && is_option_type(&f.ty))
+        .copied()
+        .collect();
+
+    // One state parameter per required field: first_name -> FirstNameState
+    let state_params: Vec<Ident> = required_fields
+        .iter()
+        .map(|f| format_ident!("{}State", to_upper_camel_case(f.ident.as_ref().unwrap())))
+        .collect();
+
+    let all_unset: Vec<&Ident> = state_params.iter().map(|_| &unset_marker).collect();
+    let all_set: Vec<&Ident> = state_params.iter().map(|_| &set_marker).collect();
+
+    // Setters for required fields: only callable while the field is still unset
+    let required_setters: Vec<TokenStream2> = required_fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let field_name = f.ident.as_ref().unwrap();
+            let method_name = format_ident!("with_{}", field_name);
+            let value_type = setter_value_type(f, into_setters);
+            let deprecation = field_deprecation(f);
+
+            let other_params: Vec<&Ident> = state_params
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, p)| p)
+                .collect();
+            let before: Vec<&Ident> = state_params
+                .iter()
+                .enumerate()
+                .map(|(j, p)| if j == i { &unset_marker } else { p })
+                .collect();
+            let after: Vec<&Ident> = state_params
+                .iter()
+                .enumerate()
+                .map(|(j, p)| if j == i { &set_marker } else { p })
+                .collect();
+
+            quote! {
+                #[automatically_derived]
+                impl<#(#other_params),*> #builder_name<#(#before),*> {
+                    /// Set required field value.
+                    #deprecation
+                    #[allow(deprecated)]
+                    pub fn #method_name(self, value: #value_type) -> #builder_name<#(#after),*> {
+                        #builder_name {
+                            factory: self.factory.#method_name(value),
+                            _state: std::marker::PhantomData,
+                        }
+                    }
+                }
+            }
+        })
+        .collect();
 
-    let inner_type = extract_option_inner_type(field_type).expect("Option field must be Option<T>");
+    quote! {
+        /// Marks a required field as set in the typestate builder.
+        #vis struct #set_marker;
 
-    if is_string_type(inner_type) {
-        quote! {
-            /// Set optional field value.
-            pub fn #method_name(mut self, value: impl Into<String>) -> Self {
-                self.#field_name = Some(value.into());
-                self
+        /// Marks a required field as not yet set in the typestate builder.
+        #vis struct #unset_marker;
+
+        /// Typestate builder: `build()` is only available once all required fields are set.
+        #[must_use]
+        #vis struct #builder_name<#(#state_params),*> {
+            factory: #factory_name,
+            _state: std::marker::PhantomData<(#(#state_params,)*)>,
+        }
+
+        #[automatically_derived]
+        impl #factory_name {
+            /// Start a typestate builder with default values.
+            pub fn builder() -> #builder_name<#(#all_unset),*> {
+                #builder_name {
+                    factory: Self::new(),
+                    _state: std::marker::PhantomData,
+                }
             }
         }
-    } else {
-        quote! {
-            /// Set optional field value.
-            pub fn #method_name(mut self, value: #inner_type) -> Self {
-                self.#field_name = Some(value);
-                self
+
+        #[automatically_derived]
+        impl<#(#state_params),*> #builder_name<#(#state_params),*> {
+            /// Configure non-required fields on the wrapped factory.
+            pub fn configure(self, f: impl FnOnce(#factory_name) -> #factory_name) -> Self {
+                #builder_name {
+                    factory: f(self.factory),
+                    _state: std::marker::PhantomData,
+                }
             }
         }
-    }
-}
 
-// =============================================================================
-// CODE GENERATION: with_* methods for regular (non-Option) non-FK fields
-// =============================================================================
+        #(#required_setters)*
 
-fn generate_regular_with_method(field: &Field) -> TokenStream2 {
-    let field_name = field.ident.as_ref().unwrap();
-    let field_type = &field.ty;
-    let method_name = format_ident!("with_{}", field_name);
+        #[automatically_derived]
+        impl #builder_name<#(#all_set),*> {
+            /// Unwrap the fully configured factory.
+            pub fn into_factory(self) -> #factory_name {
+                self.factory
+            }
 
-    if is_string_type(field_type) {
-        quote! {
-            /// Set field value.
-            pub fn #method_name(mut self, value: impl Into<String>) -> Self {
-                self.#field_name = value.into();
-                self
+            /// Build an in-memory entity without DB insert.
+            pub fn build(&self) -> #entity_type {
+                self.factory.build()
             }
-        }
-    } else {
-        quote! {
-            /// Set field value.
-            pub fn #method_name(mut self, value: #field_type) -> Self {
-                self.#field_name = value;
-                self
+
+            /// Create the entity via the factory's `FactoryCreate` impl.
+            pub async fn create<Pool>(
+                self,
+                pool: &Pool,
+            ) -> Result<#entity_type, Box<dyn std::error::Error + Send + Sync>>
+            where
+                Pool: Sync,
+                #factory_name: factory_m8::FactoryCreate<Pool, Entity = #entity_type>,
+            {
+                factory_m8::FactoryCreate::create(self.factory, pool).await
             }
         }
     }
 }
 
 // =============================================================================
-// CODE GENERATION: build() assignments
+// TYPE HELPERS
 // =============================================================================
 
-fn generate_build_assignment(field: &Field) -> TokenStream2 {
-    let field_name = field.ident.as_ref().unwrap();
-    let field_name_str = field_name.to_string();
-
-    // pk: use Default
-    if has_attr(field, "pk") {
-        return quote! {
-            #field_name: Default::default()
-        };
+/// Singular form of a collection field name for `add_<item>`: tags -> tag,
+/// allergies -> allergy. Names that don't look plural are kept as-is.
+fn singular_name(field_name: &Ident) -> String {
+    let name = field_name.to_string();
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if let Some(stem) = name.strip_suffix('s')
+        && !name.ends_with("ss")
+    {
+        stem.to_string()
+    } else {
+        name
     }
+}
 
-    // FK field: behavior based on field type
-    if let Some(_fk_info) = parse_fk_attr(field) {
-        let is_option_field = is_option_type(&field.ty);
-
-        if is_option_field {
-            // Option<T> FK field: clone as-is for build() (entity field is Option<T>)
-            return quote! {
-                #field_name: self.#field_name.clone()
-            };
-        } else {
-            // Required FK with non-Option factory field: use directly
-            if needs_clone(&field.ty) {
-                return quote! {
-                    #field_name: self.#field_name.clone()
-                };
-            } else {
-                return quote! {
-                    #field_name: self.#field_name
-                };
+/// Converts an UpperCamelCase type name to snake_case: MedicalRecord -> medical_record
+fn to_snake_case(ident: &Ident) -> String {
+    let mut snake = String::new();
+    for (i, c) in ident.to_string().chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
             }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
         }
     }
+    snake
+}
 
-    // #[required] Option field: unwrap with error message (entity field is non-Option)
-    if has_attr(field, "required") && is_option_type(&field.ty) {
-        let error_msg = format!("{field_name_str} is required - use with_{field_name_str}()");
-        return quote! {
-            #field_name: self.#field_name.clone().expect(#error_msg)
-        };
-    }
-
-    // Option field: clone as-is
-    if is_option_type(&field.ty) {
-        return quote! {
-            #field_name: self.#field_name.clone()
-        };
-    }
+/// Converts a snake_case field name to UpperCamelCase: first_name -> FirstName
+fn to_upper_camel_case(ident: &Ident) -> String {
+    ident
+        .to_string()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
 
-    // Regular non-Option field: clone or copy
-    if needs_clone(&field.ty) {
-        quote! {
-            #field_name: self.#field_name.clone()
-        }
-    } else {
-        quote! {
-            #field_name: self.#field_name
+/// Turns a type path into one usable in expressions by adding turbofishes:
+/// `Event<String>` -> `Event::<String>`
+fn expr_path(path: &syn::Path) -> syn::Path {
+    let mut path = path.clone();
+    for segment in &mut path.segments {
+        if let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments {
+            args.colon2_token = Some(Default::default());
         }
     }
+    path
 }
 
-// =============================================================================
-// CODE GENERATION: build_with_fks() FK resolution
-// =============================================================================
+/// Check if type is the plain path `name`
+fn is_type_named(ty: &Type, name: &Ident) -> bool {
+    matches!(ty, Type::Path(type_path) if type_path.qself.is_none() && type_path.path.is_ident(name))
+}
 
-fn generate_fk_resolution(field: &Field) -> TokenStream2 {
-    let field_name = field.ident.as_ref().unwrap();
-    let fk_info = parse_fk_attr(field).unwrap();
-    let entity_type = &fk_info.entity_type;
-    let entity_field = &fk_info.entity_field;
-    let factory_type = &fk_info.factory_type;
-    let is_option_field = is_option_type(&field.ty);
+/// `#[fk]` field of type `Vec<Id>` (many-to-many ids)
+fn is_vec_fk(field: &Field) -> bool {
+    parse_fk_attr(field).is_some() && extract_vec_inner_type(&field.ty).is_some()
+}
 
-    // Variable name for resolved ID
-    let resolved_var = format_ident!("resolved_{}", field_name);
+fn is_option_type(ty: &Type) -> bool {
+    extract_option_inner_type(ty).is_some()
+}
 
-    if is_option_field {
-        if fk_info.no_default {
-            // Option<T> with no_default: don't auto-create, None/sentinel stays None
-            // Returns Option<T> - for truly optional entity fields
-            quote! {
-                let #resolved_var = {
-                    use factory_m8::Sentinel;
-                    match self.#field_name {
-                        Some(id) if !id.is_sentinel() => Some(id),
-                        _ => None,  // None or Some(sentinel) stays None
-                    }
-                };
-            }
-        } else {
-            // Option<T> without no_default: auto-create if None/sentinel
-            // Returns Option<T> (Some(id)) - for Option entity fields
-            quote! {
-                let #resolved_var = {
-                    use factory_m8::Sentinel;
-                    Some(match self.#field_name {
-                        Some(id) if !id.is_sentinel() => id,
-                        _ => {
-                            // Auto-create dependency via factory
-                            use factory_m8::FactoryCreate;
-                            let entity: #entity_type = #factory_type::new().create(pool).await?;
-                            entity.#entity_field
-                        }
-                    })
-                };
-            }
-        }
-    } else {
-        // Non-Option field: auto-create if sentinel (no_default doesn't apply)
-        // Returns T
-        quote! {
-            let #resolved_var = {
-                use factory_m8::Sentinel;
-                if self.#field_name.is_sentinel() {
-                    // Auto-create dependency via factory
-                    use factory_m8::FactoryCreate;
-                    let entity: #entity_type = #factory_type::new().create(pool).await?;
-                    entity.#entity_field
-                } else {
-                    self.#field_name
-                }
-            };
-        }
-    }
+fn extract_option_inner_type(ty: &Type) -> Option<&Type> {
+    extract_wrapper_inner_type(ty, "Option")
 }
 
-fn generate_build_with_fks_assignment(field: &Field) -> TokenStream2 {
-    let field_name = field.ident.as_ref().unwrap();
+fn extract_vec_inner_type(ty: &Type) -> Option<&Type> {
+    extract_wrapper_inner_type(ty, "Vec")
+}
 
-    // pk: use Default
-    if has_attr(field, "pk") {
-        return quote! {
-            #field_name: Default::default()
-        };
-    }
+/// Smart pointers an FK id can be stored behind (`owner_id: Arc<UserId>`)
+const ID_POINTER_TYPES: &[&str] = &["Box", "Arc", "Rc"];
 
-    // FK field: use resolved variable
-    // The resolved variable type matches the field type (Option<T> or T)
-    if parse_fk_attr(field).is_some() {
-        let resolved_var = format_ident!("resolved_{}", field_name);
-        return quote! {
-            #field_name: #resolved_var
-        };
-    }
+/// Returns `T` for `Box<T>`, `Arc<T>` or `Rc<T>`
+fn extract_pointer_inner_type(ty: &Type) -> Option<&Type> {
+    ID_POINTER_TYPES
+        .iter()
+        .find_map(|pointer| extract_wrapper_inner_type(ty, pointer))
+}
 
-    // #[required] Option field: unwrap (entity field is non-Option)
-    let field_name_str = field_name.to_string();
-    if has_attr(field, "required") && is_option_type(&field.ty) {
-        let error_msg = format!("{field_name_str} is required - use with_{field_name_str}()");
-        return quote! {
-            #field_name: self.#field_name.clone().expect(#error_msg)
-        };
-    }
+/// What a non-Vec FK field stores inside its `Option` (if any): `Id` or `Arc<Id>`
+fn fk_slot_type(field: &Field) -> &Type {
+    extract_option_inner_type(&field.ty).unwrap_or(&field.ty)
+}
 
-    // Option field: clone as-is
-    if is_option_type(&field.ty) {
-        return quote! {
-            #field_name: self.#field_name.clone()
-        };
-    }
+/// Id type of a non-Vec FK field, looking through its `Option` and through a
+/// pointer around the id (`Option<Arc<UserId>>` -> `UserId`)
+fn fk_id_type(field: &Field) -> &Type {
+    let slot_type = fk_slot_type(field);
+    extract_pointer_inner_type(slot_type).unwrap_or(slot_type)
+}
 
-    // Regular non-Option field: clone or copy
-    if needs_clone(&field.ty) {
-        quote! {
-            #field_name: self.#field_name.clone()
-        }
+fn is_pointer_fk(field: &Field) -> bool {
+    extract_pointer_inner_type(fk_slot_type(field)).is_some()
+}
+
+/// Converts an id into what a non-Vec FK field stores: the id itself, or for
+/// an id behind a pointer `<Arc<Id>>::from(id)`, so setters and auto-creation
+/// take and read plain ids
+fn wrap_fk_id(field: &Field, id: TokenStream2) -> TokenStream2 {
+    if is_pointer_fk(field) {
+        let slot_type = fk_slot_type(field);
+        quote! { <#slot_type>::from(#id) }
     } else {
-        quote! {
-            #field_name: self.#field_name
-        }
+        id
     }
 }
 
-// =============================================================================
-// TYPE HELPERS
-// =============================================================================
+/// The sentinel a non-Vec FK field starts at (an `Option` FK behind a pointer
+/// starts at `None`, which counts as unset just like `Some(sentinel)`)
+fn fk_sentinel_value(field: &Field) -> TokenStream2 {
+    let field_type = &field.ty;
+    if !is_pointer_fk(field) {
+        quote! { <#field_type as factory_m8::Sentinel>::sentinel() }
+    } else if is_option_type(field_type) {
+        quote! { None }
+    } else {
+        let id_type = fk_id_type(field);
+        let sentinel = quote! { <#id_type as factory_m8::Sentinel>::sentinel() };
+        wrap_fk_id(field, sentinel)
+    }
+}
 
-fn is_option_type(ty: &Type) -> bool {
-    extract_option_inner_type(ty).is_some()
+/// Whether an `Option` FK field's `value` is `None` or `Some(sentinel)`
+fn fk_option_unset_check(field: &Field, value: TokenStream2) -> TokenStream2 {
+    if is_pointer_fk(field) {
+        quote! { #value.as_ref().is_none_or(|id| factory_m8::Sentinel::is_sentinel(&**id)) }
+    } else {
+        quote! { factory_m8::Sentinel::is_sentinel(&#value) }
+    }
 }
 
-fn extract_option_inner_type(ty: &Type) -> Option<&Type> {
+/// Returns `T` for `Wrapper<T>`, matching on the last path segment so
+/// qualified paths like `std::vec::Vec<T>` are recognized too.
+fn extract_wrapper_inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
     if let Type::Path(type_path) = ty {
         let segment = type_path.path.segments.last()?;
-        if segment.ident == "Option" {
-            if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
-                    return Some(inner);
-                }
-            }
+        if segment.ident == wrapper
+            && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+            && let Some(syn::GenericArgument::Type(inner)) = args.args.first()
+        {
+            return Some(inner);
         }
     }
     None
 }
 
-fn is_string_type(ty: &Type) -> bool {
+/// Returns `(T, E)` for `Result<T, E>`, matching on the last path segment
+fn extract_result_types(ty: &Type) -> Option<(&Type, &Type)> {
     if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            return segment.ident == "String";
+        let segment = type_path.path.segments.last()?;
+        if segment.ident == "Result"
+            && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+            && let [
+                syn::GenericArgument::Type(ok_type),
+                syn::GenericArgument::Type(err_type),
+            ] = args.args.iter().collect::<Vec<_>>()[..]
+        {
+            return Some((ok_type, err_type));
         }
     }
+    None
+}
+
+fn is_string_type(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        return segment.ident == "String";
+    }
+    false
+}
+
+fn is_phantom_data_type(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        return segment.ident == "PhantomData";
+    }
+    false
+}
+
+/// String and collection types that get a `with_<field>_empty()` setter
+const EMPTYABLE_TYPES: &[&str] = &[
+    "String", "Vec", "HashMap", "BTreeMap", "HashSet", "BTreeSet",
+];
+
+fn is_emptyable_type(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        return EMPTYABLE_TYPES.iter().any(|name| segment.ident == name);
+    }
+    false
+}
+
+/// Primitive integer types, which also get `TryInto`-based `_from` / `try_with_` setters
+const INTEGER_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64", "u128",
+];
+
+fn is_integer_type(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty
+        && let Some(ident) = type_path.path.get_ident()
+    {
+        return INTEGER_TYPES.iter().any(|int| ident == int);
+    }
     false
 }
 
 fn needs_clone(ty: &Type) -> bool {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            let name = segment.ident.to_string();
-            return !matches!(
-                name.as_str(),
-                "bool"
-                    | "i8"
-                    | "i16"
-                    | "i32"
-                    | "i64"
-                    | "i128"
-                    | "u8"
-                    | "u16"
-                    | "u32"
-                    | "u64"
-                    | "u128"
-                    | "f32"
-                    | "f64"
-                    | "char"
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        let name = segment.ident.to_string();
+        return !(INTEGER_TYPES.contains(&name.as_str())
+            || matches!(name.as_str(), "bool" | "f32" | "f64" | "char"));
+    }
+    true
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_impl_is_automatically_derived() {
+        let without_fks: DeriveInput = parse_quote! {
+            #[factory(entity = Tag)]
+            struct TagFactory {
+                #[pk]
+                id: TagId,
+                name: String,
+            }
+        };
+        let with_fks: DeriveInput = parse_quote! {
+            #[factory(entity = Note)]
+            struct NoteFactory {
+                #[pk]
+                id: NoteId,
+                #[fk(Person, "id", PersonFactory)]
+                person_id: PersonId,
+            }
+        };
+
+        for input in [without_fks, with_fks] {
+            let expanded = expand_factory(&input).to_string();
+            assert!(
+                expanded.starts_with("# [automatically_derived] impl"),
+                "missing #[automatically_derived]: {expanded}"
             );
         }
     }
-    true
+
+    #[test]
+    fn test_setter_docs_include_field_docs() {
+        let input: DeriveInput = parse_quote! {
+            #[factory(entity = Patient)]
+            struct PatientFactory {
+                #[pk]
+                id: PatientId,
+                /// Name shown on the patient's chart
+                name: String,
+                /// Practice the patient is registered at
+                #[fk(Practice, "id", PracticeFactory)]
+                practice_id: PracticeId,
+                age: i32,
+            }
+        };
+
+        let expanded = expand_factory(&input).to_string();
+
+        // The attributes between the previous method and a setter are its docs
+        let setter_docs = |setter: &str| {
+            let setter_pos = expanded.find(setter).unwrap();
+            let docs_start = expanded[..setter_pos].rfind("pub fn").unwrap_or(0);
+            expanded[docs_start..setter_pos].to_string()
+        };
+
+        assert!(setter_docs("pub fn with_name").contains("Name shown on the patient's chart"));
+        assert!(
+            setter_docs("pub fn with_practice (").contains("Practice the patient is registered at")
+        );
+        assert!(
+            setter_docs("pub fn with_practice_id")
+                .contains("Practice the patient is registered at")
+        );
+
+        // Fields without docs keep just the generated doc
+        let age_docs = setter_docs("pub fn with_age");
+        assert!(
+            !age_docs.contains("patient"),
+            "unexpected field docs: {age_docs}"
+        );
+    }
+
+    #[test]
+    fn test_qualified_option_paths_are_options() {
+        let std_option: Type = parse_quote! { std::option::Option<String> };
+        let core_option: Type = parse_quote! { core::option::Option<i32> };
+        let string: Type = parse_quote! { String };
+        let integer: Type = parse_quote! { i32 };
+
+        assert!(is_option_type(&std_option));
+        assert!(is_option_type(&core_option));
+        assert_eq!(extract_option_inner_type(&std_option), Some(&string));
+        assert_eq!(extract_option_inner_type(&core_option), Some(&integer));
+    }
+
+    #[test]
+    fn test_fk_id_type_looks_through_pointers() {
+        let arc_fk: Field =
+            parse_quote! { #[fk(User, "id", UserFactory)] owner_id: std::sync::Arc<UserId> };
+        let boxed_option_fk: Field =
+            parse_quote! { #[fk(User, "id", UserFactory)] editor_id: Option<Box<UserId>> };
+        let plain_fk: Field = parse_quote! { #[fk(User, "id", UserFactory)] author_id: UserId };
+        let user_id: Type = parse_quote! { UserId };
+
+        assert_eq!(fk_id_type(&arc_fk), &user_id);
+        assert_eq!(fk_id_type(&boxed_option_fk), &user_id);
+        assert_eq!(fk_id_type(&plain_fk), &user_id);
+        assert!(is_pointer_fk(&arc_fk) && is_pointer_fk(&boxed_option_fk));
+        assert!(!is_pointer_fk(&plain_fk));
+    }
 }
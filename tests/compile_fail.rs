@@ -0,0 +1,9 @@
+//! Compile-fail tests for the Factory derive macro.
+//!
+//! Each file in `tests/ui` must fail to compile with the error in its `.stderr` file.
+
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -6,6 +6,7 @@ use async_trait::async_trait;
 use factory_m8::{FactoryCreate, Sentinel};
 use factory_derive::Factory;
 use sqlx::PgPool;
+use std::collections::HashMap;
 use std::error::Error;
 
 // =============================================================================
@@ -14,7 +15,7 @@ use std::error::Error;
 
 macro_rules! define_simple_id {
     ($name:ident) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, sqlx::Type)]
         #[sqlx(transparent)]
         pub struct $name(pub i64);
 
@@ -33,6 +34,14 @@ macro_rules! define_simple_id {
 define_simple_id!(PersonId);
 define_simple_id!(NoteId);
 define_simple_id!(TestId);
+define_simple_id!(SpecialtyId);
+define_simple_id!(DepartmentId);
+define_simple_id!(EmployeeId);
+define_simple_id!(ReviewId);
+define_simple_id!(TicketId);
+define_simple_id!(ClinicianId);
+define_simple_id!(WardId);
+define_simple_id!(BedId);
 
 // =============================================================================
 // ENTITIES
@@ -65,8 +74,8 @@ pub struct MaybePersonNoteMappingEntity {
 // FACTORIES
 // =============================================================================
 
-#[derive(Debug, Factory)]
-#[factory(entity = Person)]
+#[derive(Debug, Clone, Hash, Factory)]
+#[factory(entity = Person, cache, handle)]
 pub struct PersonFactory {
     #[pk]
     pub id: PersonId,
@@ -107,8 +116,8 @@ impl FactoryCreate<PgPool> for PersonFactory {
     }
 }
 
-#[derive(Debug, Factory)]
-#[factory(entity = Note)]
+#[derive(Debug, Clone, Factory)]
+#[factory(entity = Note, cache, fk_from, persist)]
 pub struct NoteFactory {
     #[pk]
     pub id: i64,
@@ -151,7 +160,7 @@ impl FactoryCreate<PgPool> for NoteFactory {
 }
 
 #[derive(Debug, Factory)]
-#[factory(entity = MaybePersonNoteMappingEntity)]
+#[factory(entity = MaybePersonNoteMappingEntity, derive_default)]
 pub struct MaybePersonNoteMappingEntityFactory {
     #[pk]
     pub id: TestId,
@@ -163,16 +172,6 @@ pub struct MaybePersonNoteMappingEntityFactory {
     pub note_id: Option<NoteId>,
 }
 
-impl Default for MaybePersonNoteMappingEntityFactory {
-    fn default() -> Self {
-        Self {
-            id: TestId::sentinel(),
-            person_id: None,
-            note_id: None,
-        }
-    }
-}
-
 #[async_trait]
 impl FactoryCreate<PgPool> for MaybePersonNoteMappingEntityFactory {
     type Entity = MaybePersonNoteMappingEntity;
@@ -195,264 +194,1536 @@ impl FactoryCreate<PgPool> for MaybePersonNoteMappingEntityFactory {
     }
 }
 
-// =============================================================================
-// HELPER: Create tables for tests
-// =============================================================================
+/// Parent factory whose create always fails - used to test null_on_error
+#[derive(Debug, Default)]
+pub struct FailingPersonFactory;
 
-async fn setup_tables(pool: &PgPool) -> Result<(), sqlx::Error> {
-    let statements = vec![
-        r#"
-        CREATE TABLE IF NOT EXISTS person (
-            id BIGSERIAL PRIMARY KEY,
-            first_name TEXT NOT NULL,
-            last_name TEXT
-        )
-        "#,
-        r#"
-        CREATE TABLE IF NOT EXISTS note (
-            id BIGSERIAL PRIMARY KEY,
-            person_id BIGINT NOT NULL REFERENCES person(id),
-            content TEXT NOT NULL
-        )
-        "#,
-        r#"
-        CREATE TABLE IF NOT EXISTS person_note_mapping (
-            id BIGSERIAL PRIMARY KEY,
-            person_id BIGINT NULL REFERENCES person(id),
-            note_id BIGINT NULL REFERENCES note(id)
+impl FailingPersonFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for FailingPersonFactory {
+    type Entity = Person;
+
+    async fn create(self, pool: &PgPool) -> Result<Person, Box<dyn Error + Send + Sync>> {
+        // NULL first_name violates the NOT NULL constraint
+        let person = sqlx::query_as::<_, Person>(
+            "INSERT INTO person (first_name) VALUES (NULL) RETURNING *",
         )
-        "#,
-        "truncate person_note_mapping cascade",
-        "truncate person cascade",
-        "truncate note cascade",
-    ];
+        .fetch_one(pool)
+        .await?;
 
-    for s in statements {
-        sqlx::query(s).execute(pool).await?;
+        Ok(person)
     }
-
-    Ok(())
 }
 
-// =============================================================================
-// TESTS
-// =============================================================================
-
-/// Test that NoteFactory auto-creates Person when person_id is not set.
-///
-/// This is the key test - we call NoteFactory::new().create() WITHOUT setting
-/// person_id, and verify that:
-/// 1. A Person record was auto-created
-/// 2. A Note record was created with the correct person_id FK
-#[sqlx::test]
-async fn test_factory_auto_creates_fk_dependency(
-    pool: PgPool,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    setup_tables(&pool).await?;
+#[derive(Debug, Default, Factory)]
+#[factory(entity = MaybePersonNoteMappingEntity)]
+pub struct BestEffortMappingFactory {
+    #[pk]
+    pub id: TestId,
 
-    // Verify tables are empty
-    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
-        .fetch_one(&pool)
-        .await?;
-    assert_eq!(person_count.0, 0, "person table should be empty initially");
+    #[fk(Person, "id", FailingPersonFactory, null_on_error)]
+    pub person_id: Option<PersonId>,
 
-    let note_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM note")
-        .fetch_one(&pool)
-        .await?;
-    assert_eq!(note_count.0, 0, "note table should be empty initially");
+    #[fk(Note, "id", NoteFactory, no_default)]
+    pub note_id: Option<NoteId>,
+}
 
-    // Create note WITHOUT setting person_id - should auto-create person!
-    let note = NoteFactory::new()
-        .with_content("This is a test note")
-        .create(&pool)
-        .await?;
+#[async_trait]
+impl FactoryCreate<PgPool> for BestEffortMappingFactory {
+    type Entity = MaybePersonNoteMappingEntity;
 
-    // Verify note was created
-    assert!(note.id.0 > 0, "Note should have a valid ID");
-    assert_eq!(note.content, "This is a test note");
-    assert!(note.person_id.0 > 0, "Note should have a person_id");
+    async fn create(
+        self,
+        pool: &PgPool,
+    ) -> Result<MaybePersonNoteMappingEntity, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
 
-    // Verify person was auto-created
-    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
-        .fetch_one(&pool)
+        let model = sqlx::query_as::<_, MaybePersonNoteMappingEntity>(
+            "INSERT INTO person_note_mapping (person_id, note_id) values ($1, $2) RETURNING *",
+        )
+        .bind(entity.person_id)
+        .bind(entity.note_id)
+        .fetch_one(pool)
         .await?;
-    assert_eq!(person_count.0, 1, "Person should have been auto-created");
 
-    // Verify the person exists and note references it
-    let person: Person = sqlx::query_as("SELECT * FROM person WHERE id = $1")
-        .bind(note.person_id)
-        .fetch_one(&pool)
-        .await?;
+        Ok(model)
+    }
+}
 
-    assert_eq!(
-        person.id, note.person_id,
-        "Note should reference the created person"
-    );
+/// Mapping where both FKs auto-create - Note itself auto-creates a Person
+#[derive(Debug, Default, Factory)]
+#[factory(entity = MaybePersonNoteMappingEntity, cache)]
+pub struct CachedMappingFactory {
+    #[pk]
+    pub id: TestId,
 
-    println!(
-        "SUCCESS: Auto-created Person (id={:?}) for Note (id={:?})",
-        person.id, note.id
-    );
+    #[fk(Person, "id", PersonFactory)]
+    pub person_id: Option<PersonId>,
 
-    Ok(())
+    #[fk(Note, "id", NoteFactory)]
+    pub note_id: Option<NoteId>,
 }
 
-/// Test that we can explicitly set the FK to use an existing entity.
-#[sqlx::test]
-async fn test_factory_uses_explicit_fk(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
-    setup_tables(&pool).await?;
-
-    // First create a person explicitly
-    let person = PersonFactory::new()
-        .with_first_name("John")
-        .with_last_name("Doe")
-        .create(&pool)
-        .await?;
+#[async_trait]
+impl FactoryCreate<PgPool> for CachedMappingFactory {
+    type Entity = MaybePersonNoteMappingEntity;
 
-    assert_eq!(person.first_name, "John");
-    assert_eq!(person.last_name, Some("Doe".to_string()));
+    async fn create(
+        self,
+        pool: &PgPool,
+    ) -> Result<MaybePersonNoteMappingEntity, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
 
-    // Create note WITH explicit person reference
-    let note = NoteFactory::new()
-        .with_person(&person) // Use the existing person
-        .with_content("Note for John")
-        .create(&pool)
+        let model = sqlx::query_as::<_, MaybePersonNoteMappingEntity>(
+            "INSERT INTO person_note_mapping (person_id, note_id) values ($1, $2) RETURNING *",
+        )
+        .bind(entity.person_id)
+        .bind(entity.note_id)
+        .fetch_one(pool)
         .await?;
 
-    // Verify note references the person we created
-    assert_eq!(note.person_id, person.id);
+        Ok(model)
+    }
+}
 
-    // Verify only 1 person exists (no auto-creation)
-    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
-        .fetch_one(&pool)
-        .await?;
-    assert_eq!(
-        person_count.0, 1,
-        "Should only have 1 person (no auto-creation)"
-    );
+/// Note factory whose person is shared between notes by create_deduped()
+#[derive(Debug, Factory)]
+#[factory(entity = Note)]
+pub struct DedupNoteFactory {
+    #[pk]
+    pub id: i64,
 
-    println!(
-        "SUCCESS: Note (id={:?}) correctly references existing Person (id={:?})",
-        note.id, person.id
-    );
+    #[fk(Person, "id", PersonFactory, dedup)]
+    pub person_id: PersonId,
 
-    Ok(())
+    #[required]
+    pub content: Option<String>,
 }
 
-/// Test that we can set FK by ID directly.
-#[sqlx::test]
-async fn test_factory_uses_explicit_fk_id(
-    pool: PgPool,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    setup_tables(&pool).await?;
-
-    // First create a person
-    let person = PersonFactory::new()
-        .with_first_name("Jane")
-        .create(&pool)
-        .await?;
+impl Default for DedupNoteFactory {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            person_id: PersonId::sentinel(),
+            content: Some("Deduped note content".to_string()),
+        }
+    }
+}
 
-    // Create note with explicit person_id
-    let note = NoteFactory::new()
-        .with_person_id(person.id) // Use ID directly
-        .with_content("Note for Jane")
-        .create(&pool)
-        .await?;
+#[async_trait]
+impl FactoryCreate<PgPool> for DedupNoteFactory {
+    type Entity = Note;
 
-    assert_eq!(note.person_id, person.id);
+    async fn create(self, pool: &PgPool) -> Result<Note, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
 
-    // Verify only 1 person
-    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
-        .fetch_one(&pool)
+        let note = sqlx::query_as::<_, Note>(
+            "INSERT INTO note (person_id, content) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(entity.person_id)
+        .bind(&entity.content)
+        .fetch_one(pool)
         .await?;
-    assert_eq!(person_count.0, 1);
 
-    Ok(())
+        Ok(note)
+    }
 }
 
-/// Test creating multiple notes for the same person.
-#[sqlx::test]
-async fn test_multiple_notes_same_person(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
-    setup_tables(&pool).await?;
+/// Mapping whose note must be set explicitly - used to test not_null
+#[derive(Debug, Default, Factory)]
+#[factory(entity = MaybePersonNoteMappingEntity)]
+pub struct StrictMappingFactory {
+    #[pk]
+    pub id: TestId,
 
-    let person = PersonFactory::new()
-        .with_first_name("Bob")
-        .create(&pool)
-        .await?;
+    #[fk(Person, "id", PersonFactory, no_default)]
+    pub person_id: Option<PersonId>,
 
-    let note1 = NoteFactory::new()
-        .with_person(&person)
-        .with_content("First note")
-        .create(&pool)
-        .await?;
+    #[fk(Note, "id", NoteFactory, no_default, not_null)]
+    pub note_id: Option<NoteId>,
+}
 
-    let note2 = NoteFactory::new()
-        .with_person(&person)
-        .with_content("Second note")
-        .create(&pool)
-        .await?;
+#[async_trait]
+impl FactoryCreate<PgPool> for StrictMappingFactory {
+    type Entity = MaybePersonNoteMappingEntity;
+
+    async fn create(
+        self,
+        pool: &PgPool,
+    ) -> Result<MaybePersonNoteMappingEntity, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let model = sqlx::query_as::<_, MaybePersonNoteMappingEntity>(
+            "INSERT INTO person_note_mapping (person_id, note_id) values ($1, $2) RETURNING *",
+        )
+        .bind(entity.person_id)
+        .bind(entity.note_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(model)
+    }
+}
+
+/// Mapping whose person is only created on request - used to test optional_create
+#[derive(Debug, Default, Factory)]
+#[factory(entity = MaybePersonNoteMappingEntity)]
+pub struct OptionalPersonMappingFactory {
+    #[pk]
+    pub id: TestId,
+
+    #[fk(Person, "id", PersonFactory, optional_create)]
+    pub person_id: Option<PersonId>,
+
+    #[fk(Note, "id", NoteFactory, no_default)]
+    pub note_id: Option<NoteId>,
+}
+
+/// Note factory whose unset person reuses the person last created on this thread
+#[derive(Debug, Factory)]
+#[factory(entity = Note)]
+pub struct CurrentPersonNoteFactory {
+    #[pk]
+    pub id: NoteId,
+
+    #[fk(Person, "id", PersonFactory, use_last)]
+    pub person_id: PersonId,
+
+    pub content: String,
+}
+
+impl Default for CurrentPersonNoteFactory {
+    fn default() -> Self {
+        Self {
+            id: NoteId::sentinel(),
+            person_id: PersonId::sentinel(),
+            content: "Note about the current person".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for CurrentPersonNoteFactory {
+    type Entity = Note;
+
+    async fn create(self, pool: &PgPool) -> Result<Note, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let note = sqlx::query_as::<_, Note>(
+            "INSERT INTO note (person_id, content) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(entity.person_id)
+        .bind(&entity.content)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(note)
+    }
+}
+
+/// Entity with two FKs to Person
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Review {
+    pub id: ReviewId,
+    pub author_id: PersonId,
+    pub reviewer_id: PersonId,
+}
+
+/// Both Person FKs are created with one `PersonFactory::create_many` call
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Review, batch_fks)]
+pub struct ReviewFactory {
+    #[pk]
+    pub id: ReviewId,
+
+    #[fk(Person, "id", PersonFactory)]
+    pub author_id: PersonId,
+
+    #[fk(Person, "id", PersonFactory)]
+    pub reviewer_id: PersonId,
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for ReviewFactory {
+    type Entity = Review;
+
+    async fn create(self, pool: &PgPool) -> Result<Review, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let review = sqlx::query_as::<_, Review>(
+            "INSERT INTO review (author_id, reviewer_id) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(entity.author_id)
+        .bind(entity.reviewer_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(review)
+    }
+}
+
+/// Finds an existing person named "Existing" for `find = ...`
+async fn find_existing_person(pool: &PgPool) -> Option<PersonId> {
+    sqlx::query_scalar("SELECT id FROM person WHERE first_name = 'Existing' LIMIT 1")
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Note factory that looks up an existing person before creating one
+#[derive(Debug, Factory)]
+#[factory(entity = Note)]
+pub struct FoundPersonNoteFactory {
+    #[pk]
+    pub id: NoteId,
+
+    #[fk(Person, "id", PersonFactory, find = find_existing_person)]
+    pub person_id: PersonId,
+
+    pub content: String,
+}
+
+impl Default for FoundPersonNoteFactory {
+    fn default() -> Self {
+        Self {
+            id: NoteId::sentinel(),
+            person_id: PersonId::sentinel(),
+            content: "Note about an existing person".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for FoundPersonNoteFactory {
+    type Entity = Note;
+
+    async fn create(self, pool: &PgPool) -> Result<Note, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let note = sqlx::query_as::<_, Note>(
+            "INSERT INTO note (person_id, content) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(entity.person_id)
+        .bind(&entity.content)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(note)
+    }
+}
+
+/// Entity with a unique, factory-numbered column
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Ticket {
+    pub id: TicketId,
+    pub number: i64,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Ticket)]
+pub struct TicketFactory {
+    #[pk]
+    pub id: TicketId,
+
+    #[sequence]
+    pub number: Option<i64>,
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for TicketFactory {
+    type Entity = Ticket;
+
+    async fn create(self, pool: &PgPool) -> Result<Ticket, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let ticket =
+            sqlx::query_as::<_, Ticket>("INSERT INTO ticket (number) VALUES ($1) RETURNING *")
+                .bind(entity.number)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(ticket)
+    }
+}
+
+/// Parent referenced by its unique `code` rather than its pk
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Department {
+    pub id: DepartmentId,
+    pub code: String,
+}
+
+/// Child whose FK column references `department(code)`
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Employee {
+    pub id: EmployeeId,
+    pub department_code: String,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Department)]
+pub struct DepartmentFactory {
+    #[pk]
+    pub id: DepartmentId,
+
+    pub code: String,
+}
+
+impl Default for DepartmentFactory {
+    fn default() -> Self {
+        Self {
+            id: DepartmentId::sentinel(),
+            code: "CARDIOLOGY".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for DepartmentFactory {
+    type Entity = Department;
+
+    async fn create(self, pool: &PgPool) -> Result<Department, Box<dyn Error + Send + Sync>> {
+        let entity = self.build();
+
+        let department = sqlx::query_as::<_, Department>(
+            "INSERT INTO department (code) VALUES ($1) RETURNING *",
+        )
+        .bind(&entity.code)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(department)
+    }
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Employee, derive_default)]
+pub struct EmployeeFactory {
+    #[pk]
+    pub id: EmployeeId,
+
+    #[fk(Department, "code", DepartmentFactory)]
+    pub department_code: String,
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for EmployeeFactory {
+    type Entity = Employee;
+
+    async fn create(self, pool: &PgPool) -> Result<Employee, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let employee = sqlx::query_as::<_, Employee>(
+            "INSERT INTO employee (department_code) VALUES ($1) RETURNING *",
+        )
+        .bind(&entity.department_code)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(employee)
+    }
+}
+
+impl DepartmentFactory {
+    /// Reuses the department with this factory's code, creating it only if missing
+    pub async fn find_or_create(
+        self,
+        pool: &PgPool,
+    ) -> Result<Department, Box<dyn Error + Send + Sync>> {
+        let existing = sqlx::query_as::<_, Department>("SELECT * FROM department WHERE code = $1")
+            .bind(&self.code)
+            .fetch_optional(pool)
+            .await?;
+
+        match existing {
+            Some(department) => Ok(department),
+            None => self.create(pool).await,
+        }
+    }
+}
+
+/// Employee factory whose department is found by code before being created
+#[derive(Debug, Clone, Factory)]
+#[factory(entity = Employee, derive_default)]
+pub struct DepartmentMemberFactory {
+    #[pk]
+    pub id: EmployeeId,
+
+    #[fk(Department, "code", DepartmentFactory, create_method = "find_or_create")]
+    pub department_code: String,
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for DepartmentMemberFactory {
+    type Entity = Employee;
+
+    async fn create(self, pool: &PgPool) -> Result<Employee, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let employee = sqlx::query_as::<_, Employee>(
+            "INSERT INTO employee (department_code) VALUES ($1) RETURNING *",
+        )
+        .bind(&entity.department_code)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(employee)
+    }
+}
+
+/// Lookup row keyed by a unique `code`
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Specialty {
+    pub id: SpecialtyId,
+    pub code: String,
+    pub name: String,
+}
+
+/// Uses the generated sqlx upsert instead of a hand-written FactoryCreate,
+/// inserting `name` before `code`
+#[cfg(feature = "sqlx")]
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Specialty, table = "specialty", upsert = "code", column_order(name, code))]
+pub struct SpecialtyFactory {
+    #[pk]
+    pub id: SpecialtyId,
+
+    #[required]
+    pub code: Option<String>,
+
+    #[required]
+    pub name: Option<String>,
+}
+
+/// Child of seeded reference data: its specialty must already exist
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Clinician {
+    pub id: ClinicianId,
+    pub specialty_id: SpecialtyId,
+}
+
+/// Uses the generated sqlx INSERT instead of a hand-written FactoryCreate
+#[cfg(feature = "sqlx")]
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Clinician, table = "clinician")]
+pub struct ClinicianFactory {
+    #[pk]
+    pub id: ClinicianId,
+
+    #[fk(Specialty, "id", SpecialtyFactory, require_existing)]
+    pub specialty_id: SpecialtyId,
+}
+
+/// Tenant-scoped parent whose beds share its tenant
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Ward {
+    pub id: WardId,
+    pub tenant_id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Bed {
+    pub id: BedId,
+    pub ward_id: WardId,
+    pub tenant_id: i64,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Ward)]
+pub struct WardFactory {
+    #[pk]
+    pub id: WardId,
+
+    pub tenant_id: i64,
+}
+
+impl Default for WardFactory {
+    fn default() -> Self {
+        Self {
+            id: WardId::sentinel(),
+            tenant_id: 77,
+        }
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for WardFactory {
+    type Entity = Ward;
+
+    async fn create(self, pool: &PgPool) -> Result<Ward, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let ward =
+            sqlx::query_as::<_, Ward>("INSERT INTO ward (tenant_id) VALUES ($1) RETURNING *")
+                .bind(entity.tenant_id)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(ward)
+    }
+}
+
+/// A bed copies its tenant from an auto-created ward
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Bed)]
+pub struct BedFactory {
+    #[pk]
+    pub id: BedId,
+
+    #[fk(Ward, "id", WardFactory)]
+    pub ward_id: WardId,
+
+    #[from_fk(parent = "ward_id", field = "tenant_id")]
+    pub tenant_id: i64,
+}
+
+#[async_trait]
+impl FactoryCreate<PgPool> for BedFactory {
+    type Entity = Bed;
+
+    async fn create(self, pool: &PgPool) -> Result<Bed, Box<dyn Error + Send + Sync>> {
+        let entity = self.build_with_fks(pool).await?;
+
+        let bed = sqlx::query_as::<_, Bed>(
+            "INSERT INTO bed (ward_id, tenant_id) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(entity.ward_id)
+        .bind(entity.tenant_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(bed)
+    }
+}
+
+// =============================================================================
+// HELPER: Create tables for tests
+// =============================================================================
+
+async fn setup_tables(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let statements = vec![
+        r#"
+        CREATE TABLE IF NOT EXISTS person (
+            id BIGSERIAL PRIMARY KEY,
+            first_name TEXT NOT NULL,
+            last_name TEXT
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS note (
+            id BIGSERIAL PRIMARY KEY,
+            person_id BIGINT NOT NULL REFERENCES person(id),
+            content TEXT NOT NULL
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS person_note_mapping (
+            id BIGSERIAL PRIMARY KEY,
+            person_id BIGINT NULL REFERENCES person(id),
+            note_id BIGINT NULL REFERENCES note(id)
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS specialty (
+            id BIGSERIAL PRIMARY KEY,
+            code TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS department (
+            id BIGSERIAL PRIMARY KEY,
+            code TEXT NOT NULL UNIQUE
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS employee (
+            id BIGSERIAL PRIMARY KEY,
+            department_code TEXT NOT NULL REFERENCES department(code)
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS review (
+            id BIGSERIAL PRIMARY KEY,
+            author_id BIGINT NOT NULL REFERENCES person(id),
+            reviewer_id BIGINT NOT NULL REFERENCES person(id)
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS ticket (
+            id BIGSERIAL PRIMARY KEY,
+            number BIGINT NOT NULL UNIQUE
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS clinician (
+            id BIGSERIAL PRIMARY KEY,
+            specialty_id BIGINT NOT NULL REFERENCES specialty(id)
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS ward (
+            id BIGSERIAL PRIMARY KEY,
+            tenant_id BIGINT NOT NULL
+        )
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS bed (
+            id BIGSERIAL PRIMARY KEY,
+            ward_id BIGINT NOT NULL REFERENCES ward(id),
+            tenant_id BIGINT NOT NULL
+        )
+        "#,
+        "truncate bed cascade",
+        "truncate ward cascade",
+        "truncate clinician cascade",
+        "truncate review cascade",
+        "truncate ticket cascade",
+        "truncate person_note_mapping cascade",
+        "truncate person cascade",
+        "truncate note cascade",
+        "truncate specialty cascade",
+        "truncate employee cascade",
+        "truncate department cascade",
+    ];
+
+    for s in statements {
+        sqlx::query(s).execute(pool).await?;
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+/// Test that NoteFactory auto-creates Person when person_id is not set.
+///
+/// This is the key test - we call NoteFactory::new().create() WITHOUT setting
+/// person_id, and verify that:
+/// 1. A Person record was auto-created
+/// 2. A Note record was created with the correct person_id FK
+#[sqlx::test]
+async fn test_factory_auto_creates_fk_dependency(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    // Verify tables are empty
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 0, "person table should be empty initially");
+
+    let note_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM note")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(note_count.0, 0, "note table should be empty initially");
+
+    // Create note WITHOUT setting person_id - should auto-create person!
+    let note = NoteFactory::new()
+        .with_content("This is a test note")
+        .create(&pool)
+        .await?;
+
+    // Verify note was created
+    assert!(note.id.0 > 0, "Note should have a valid ID");
+    assert_eq!(note.content, "This is a test note");
+    assert!(note.person_id.0 > 0, "Note should have a person_id");
+
+    // Verify person was auto-created
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1, "Person should have been auto-created");
+
+    // Verify the person exists and note references it
+    let person: Person = sqlx::query_as("SELECT * FROM person WHERE id = $1")
+        .bind(note.person_id)
+        .fetch_one(&pool)
+        .await?;
+
+    assert_eq!(
+        person.id, note.person_id,
+        "Note should reference the created person"
+    );
+
+    println!(
+        "SUCCESS: Auto-created Person (id={:?}) for Note (id={:?})",
+        person.id, note.id
+    );
+
+    Ok(())
+}
+
+/// Test that we can explicitly set the FK to use an existing entity.
+#[sqlx::test]
+async fn test_factory_uses_explicit_fk(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    // First create a person explicitly
+    let person = PersonFactory::new()
+        .with_first_name("John")
+        .with_last_name("Doe")
+        .create(&pool)
+        .await?;
+
+    assert_eq!(person.first_name, "John");
+    assert_eq!(person.last_name, Some("Doe".to_string()));
+
+    // Create note WITH explicit person reference
+    let note = NoteFactory::new()
+        .with_person(&person) // Use the existing person
+        .with_content("Note for John")
+        .create(&pool)
+        .await?;
+
+    // Verify note references the person we created
+    assert_eq!(note.person_id, person.id);
+
+    // Verify only 1 person exists (no auto-creation)
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(
+        person_count.0, 1,
+        "Should only have 1 person (no auto-creation)"
+    );
+
+    println!(
+        "SUCCESS: Note (id={:?}) correctly references existing Person (id={:?})",
+        note.id, person.id
+    );
+
+    Ok(())
+}
+
+/// Test that we can set FK by ID directly.
+#[sqlx::test]
+async fn test_factory_uses_explicit_fk_id(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    // First create a person
+    let person = PersonFactory::new()
+        .with_first_name("Jane")
+        .create(&pool)
+        .await?;
+
+    // Create note with explicit person_id
+    let note = NoteFactory::new()
+        .with_person_id(person.id) // Use ID directly
+        .with_content("Note for Jane")
+        .create(&pool)
+        .await?;
+
+    assert_eq!(note.person_id, person.id);
+
+    // Verify only 1 person
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1);
+
+    Ok(())
+}
+
+/// Test that an injected `Option<Person>` is used when present, and that
+/// `None` falls back to auto-creating the person.
+#[sqlx::test]
+async fn test_optional_parent_entity_injection(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let person = PersonFactory::new()
+        .with_first_name("Injected")
+        .create(&pool)
+        .await?;
+
+    let injected = NoteFactory::new()
+        .with_person_entity(Some(person.clone()))
+        .create(&pool)
+        .await?;
+    assert_eq!(injected.person_id, person.id);
+
+    let auto_created = NoteFactory::new()
+        .with_person_entity(None)
+        .create(&pool)
+        .await?;
+    assert_ne!(auto_created.person_id, person.id);
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 2);
+
+    Ok(())
+}
+
+/// Test creating multiple notes for the same person.
+#[sqlx::test]
+async fn test_multiple_notes_same_person(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let person = PersonFactory::new()
+        .with_first_name("Bob")
+        .create(&pool)
+        .await?;
+
+    let note1 = NoteFactory::new()
+        .with_person(&person)
+        .with_content("First note")
+        .create(&pool)
+        .await?;
+
+    let note2 = NoteFactory::new()
+        .with_person(&person)
+        .with_content("Second note")
+        .create(&pool)
+        .await?;
+
+    assert_eq!(note1.person_id, person.id);
+    assert_eq!(note2.person_id, person.id);
+    assert_ne!(note1.id, note2.id);
+
+    let note_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM note WHERE person_id = $1")
+        .bind(person.id)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(note_count.0, 2);
+
+    Ok(())
+}
+
+/// Test that a person handle spawns note factories already pointing at the person.
+#[sqlx::test]
+async fn test_note_from_person_handle(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let person = PersonFactory::new()
+        .with_first_name("Handle")
+        .create_handle(&pool)
+        .await?;
+
+    let note = person
+        .as_fk::<NoteFactory>()
+        .with_content("From handle")
+        .create(&pool)
+        .await?;
+
+    assert_eq!(note.person_id, person.id);
+    assert_eq!(person.first_name, "Handle");
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1);
+
+    Ok(())
+}
+
+/// Test creating multiple notes without specifying the person at all
+#[sqlx::test]
+async fn test_multiple_notes_no_person(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let _ = NoteFactory::new()
+        .with_content("First Note")
+        .create(&pool)
+        .await?;
+
+    let _ = NoteFactory::new()
+        .with_content("Second Note")
+        .create(&pool)
+        .await?;
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 2);
+
+    Ok(())
+}
+
+/// Test that auto-created parents get their ids in creation order: note by note,
+/// row by row in create_many(), and field by field within one entity.
+#[sqlx::test]
+async fn test_auto_created_parent_ids_follow_creation_order(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let first = NoteFactory::new().create(&pool).await?;
+    let second = NoteFactory::new().create(&pool).await?;
+    let batch = NoteFactory::new().create_many(3, &pool).await?;
+
+    let person_ids: Vec<i64> = [first, second]
+        .iter()
+        .chain(&batch)
+        .map(|note| note.person_id.0)
+        .collect();
+    assert!(
+        person_ids.windows(2).all(|pair| pair[0] < pair[1]),
+        "{person_ids:?}"
+    );
+
+    let review = ReviewFactory::new().create(&pool).await?;
+    assert!(review.author_id.0 < review.reviewer_id.0);
+    assert!(person_ids[4] < review.author_id.0);
+
+    Ok(())
+}
+
+/// Test that create_many() resolves FKs per row, auto-creating a person for each note.
+#[sqlx::test]
+async fn test_create_many_notes_no_person(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let notes = NoteFactory::new()
+        .with_content("Batch note")
+        .create_many(3, &pool)
+        .await?;
+
+    assert_eq!(notes.len(), 3);
+    assert!(notes.iter().all(|note| note.content == "Batch note"));
+    assert_ne!(notes[0].person_id, notes[1].person_id);
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 3);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_persist_is_awaitable(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let note = NoteFactory::new().with_content("x").persist(&pool).await?;
+
+    assert_eq!(note.content, "x");
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn test_no_default_flag(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let mapping = MaybePersonNoteMappingEntityFactory::new()
+        .create(&pool)
+        .await?;
+
+    assert!(mapping.person_id.is_some());
+    assert!(mapping.note_id.is_none());
+
+    dbg!(mapping);
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) from person")
+        .fetch_one(&pool)
+        .await?;
+    let notes_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM  note")
+        .fetch_one(&pool)
+        .await?;
+
+    assert_eq!(
+        notes_count.0, 0,
+        "Note should not be created. [NOTE]: PLEASE BE CAREFUL AS NOTE CREATES PERSON AS WELL"
+    );
+    assert_eq!(person_count.0, 1, "Person should be created");
+
+    Ok(())
+}
+
+/// Test that create_tracked() hands back the auto-created parent entity.
+#[sqlx::test]
+async fn test_create_tracked_returns_auto_created_parent(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
 
-    assert_eq!(note1.person_id, person.id);
-    assert_eq!(note2.person_id, person.id);
-    assert_ne!(note1.id, note2.id);
+    let (note, created) = NoteFactory::new()
+        .with_content("Tracked note")
+        .create_tracked(&pool)
+        .await?;
 
-    let note_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM note WHERE person_id = $1")
-        .bind(person.id)
+    let person = created
+        .person
+        .expect("Person should have been auto-created");
+    assert_eq!(person.first_name, "Auto-Generated");
+    assert_eq!(note.person_id, person.id);
+
+    // The parent was created once, not again by NoteFactory::create
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
         .fetch_one(&pool)
         .await?;
-    assert_eq!(note_count.0, 2);
+    assert_eq!(person_count.0, 1);
 
     Ok(())
 }
 
-/// Test creating multiple notes without specifying the person at all
+/// Test that create_tracked() reports no parent when the FK was set explicitly.
 #[sqlx::test]
-async fn test_multiple_notes_no_person(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+async fn test_create_tracked_skips_explicit_parent(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
     setup_tables(&pool).await?;
 
-    let _ = NoteFactory::new()
-        .with_content("First Note")
+    let person = PersonFactory::new()
+        .with_first_name("Explicit")
         .create(&pool)
         .await?;
 
-    let _ = NoteFactory::new()
-        .with_content("Second Note")
+    let (note, created) = NoteFactory::new()
+        .with_person(&person)
+        .create_tracked(&pool)
+        .await?;
+
+    assert!(created.person.is_none());
+    assert_eq!(note.person_id, person.id);
+
+    Ok(())
+}
+
+/// Test that a failing parent create leaves a null_on_error FK as NULL.
+#[sqlx::test]
+async fn test_null_on_error_flag(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let mapping = BestEffortMappingFactory::new().create(&pool).await?;
+
+    assert!(mapping.id.0 > 0, "Mapping should still be created");
+    assert!(mapping.person_id.is_none());
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 0);
+
+    Ok(())
+}
+
+/// Test that create_cached() reuses a grandparent across parents.
+///
+/// Mapping -> Person and Mapping -> Note -> Person: without the cache this
+/// creates two persons, with it the note reuses the mapping's person.
+#[sqlx::test]
+async fn test_create_cached_reuses_grandparent(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let mut cache = Vec::new();
+    let mapping = CachedMappingFactory::new()
+        .create_cached(&pool, &mut cache)
+        .await?;
+
+    let note: Note = sqlx::query_as("SELECT * FROM note WHERE id = $1")
+        .bind(mapping.note_id)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(Some(note.person_id), mapping.person_id);
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(
+        person_count.0, 1,
+        "Person should be created once and reused"
+    );
+
+    // Later creates with the same cache reuse the cached note and person too
+    let second = CachedMappingFactory::new()
+        .create_cached(&pool, &mut cache)
+        .await?;
+    assert_eq!(second.note_id, mapping.note_id);
+
+    let note_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM note")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(note_count.0, 1);
+
+    Ok(())
+}
+
+/// Test that create_deduped() shares a parent between identically-configured children.
+#[sqlx::test]
+async fn test_create_deduped_shares_identical_parent(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let mut dedup = HashMap::new();
+    let first = DedupNoteFactory::new()
+        .create_deduped(&pool, &mut dedup)
+        .await?;
+    let second = DedupNoteFactory::new()
+        .with_content("Second note")
+        .create_deduped(&pool, &mut dedup)
+        .await?;
+
+    assert_eq!(first.person_id, second.person_id);
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1, "Identical parents should share one row");
+
+    Ok(())
+}
+
+/// Test that an unset not_null FK is a clean error and nothing is inserted.
+#[sqlx::test]
+async fn test_not_null_flag_errors_when_unset(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let err = StrictMappingFactory::new()
+        .create(&pool)
+        .await
+        .expect_err("unset not_null FK should fail");
+    assert_eq!(
+        err.to_string(),
+        "note_id is NOT NULL but unset - use with_note() or with_note_id()"
+    );
+
+    let mapping_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person_note_mapping")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(mapping_count.0, 0);
+
+    // Setting it explicitly works
+    let note = NoteFactory::new().create(&pool).await?;
+    let mapping = StrictMappingFactory::new()
+        .with_note(&note)
         .create(&pool)
         .await?;
+    assert_eq!(mapping.note_id, Some(note.id));
+
+    Ok(())
+}
+
+/// Test that optional_create FKs are only auto-created when the options ask for it.
+#[sqlx::test]
+async fn test_optional_create_flag(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let skipped = OptionalPersonMappingFactory::new()
+        .build_with_fks(&pool)
+        .await?;
+    assert!(skipped.person_id.is_none());
+
+    let options = OptionalPersonMappingFactoryBuildOptions {
+        create_optionals: true,
+    };
+    let created = OptionalPersonMappingFactory::new()
+        .build_with_fks_opts(&pool, options)
+        .await?;
+    assert!(created.person_id.is_some());
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1);
+
+    Ok(())
+}
+
+/// Test that a `use_last` FK reuses the person created with `create_last()`.
+#[sqlx::test]
+async fn test_use_last_reuses_last_created_parent(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+    PersonFactory::clear_last();
+
+    let person = PersonFactory::new().create_last(&pool).await?;
+    let first = CurrentPersonNoteFactory::new().create(&pool).await?;
+    let second = CurrentPersonNoteFactory::new().create(&pool).await?;
+
+    assert_eq!(first.person_id, person.id);
+    assert_eq!(second.person_id, person.id);
+
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1);
+
+    Ok(())
+}
+
+/// Test that `batch_fks` creates one distinct person per unset Person FK.
+#[sqlx::test]
+async fn test_batch_fks_creates_distinct_parents(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let review = ReviewFactory::new().create(&pool).await?;
+
+    assert!(!review.author_id.is_sentinel());
+    assert!(!review.reviewer_id.is_sentinel());
+    assert_ne!(review.author_id, review.reviewer_id);
 
     let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
         .fetch_one(&pool)
         .await?;
     assert_eq!(person_count.0, 2);
 
+    // An explicit FK is left alone; only the unset one is batched
+    let author = PersonFactory::new().create(&pool).await?;
+    let review = ReviewFactory::new()
+        .with_author(&author)
+        .create(&pool)
+        .await?;
+    assert_eq!(review.author_id, author.id);
+    assert_ne!(review.reviewer_id, author.id);
+
     Ok(())
 }
 
+/// Test that `find = ...` reuses the parent it locates instead of creating one.
 #[sqlx::test]
-async fn test_no_default_flag(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+async fn test_find_locates_existing_parent(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
     setup_tables(&pool).await?;
 
-    let mapping = MaybePersonNoteMappingEntityFactory::new()
+    let existing = PersonFactory::new()
+        .with_first_name("Existing")
         .create(&pool)
         .await?;
+    let note = FoundPersonNoteFactory::new().create(&pool).await?;
 
-    assert!(mapping.person_id.is_some());
-    assert!(mapping.note_id.is_none());
+    assert_eq!(note.person_id, existing.id);
 
-    dbg!(mapping);
+    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM person")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(person_count.0, 1);
 
-    let person_count: (i64,) = sqlx::query_as("SELECT COUNT(*) from person")
+    Ok(())
+}
+
+/// Test that an FK to a parent's non-pk column resolves to that column, read
+/// from the entity the parent's `INSERT ... RETURNING *` returned.
+#[sqlx::test]
+async fn test_fk_to_non_pk_column(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let employee = EmployeeFactory::new().create(&pool).await?;
+
+    let department: Department = sqlx::query_as("SELECT * FROM department")
         .fetch_one(&pool)
         .await?;
-    let notes_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM  note")
+    assert_eq!(employee.department_code, department.code);
+    assert_ne!(employee.department_code, department.id.0.to_string());
+
+    Ok(())
+}
+
+/// Test that `create_method = "find_or_create"` resolves every row of a batch
+/// to the one department with the default code, instead of inserting it again.
+#[sqlx::test]
+async fn test_create_method_reuses_parent_by_natural_key(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let employees = DepartmentMemberFactory::new().create_many(3, &pool).await?;
+
+    let departments: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM department")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(departments.0, 1);
+    assert!(employees.iter().all(|e| e.department_code == "CARDIOLOGY"));
+
+    Ok(())
+}
+
+/// Test that `upsert = "code"` keeps a single row when the same code is created twice.
+#[cfg(feature = "sqlx")]
+#[sqlx::test]
+async fn test_upsert_creates_single_row(pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let first = SpecialtyFactory::new()
+        .with_code("CARD")
+        .with_name("Cardiology")
+        .create(&pool)
+        .await?;
+    let second = SpecialtyFactory::new()
+        .with_code("CARD")
+        .with_name("Cardiology (Adult)")
+        .create(&pool)
+        .await?;
+
+    assert_eq!(first.id, second.id);
+    assert_eq!(second.name, "Cardiology (Adult)");
+
+    let specialty_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM specialty")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(specialty_count.0, 1);
+
+    Ok(())
+}
+
+/// Test that `table = "..."` alone generates a plain INSERT of the non-pk fields.
+#[cfg(feature = "sqlx")]
+#[sqlx::test]
+async fn test_table_generates_insert_create(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    assert_eq!(
+        ClinicianFactory::INSERT_SQL,
+        "INSERT INTO clinician (specialty_id) VALUES ($1) RETURNING *"
+    );
+
+    let specialty = SpecialtyFactory::new()
+        .with_code("DERM")
+        .with_name("Dermatology")
+        .create(&pool)
+        .await?;
+    let first = ClinicianFactory::new()
+        .with_specialty(&specialty)
+        .create(&pool)
+        .await?;
+    let second = ClinicianFactory::new()
+        .with_specialty(&specialty)
+        .create(&pool)
+        .await?;
+
+    assert_ne!(first.id, second.id);
+    assert_eq!(second.specialty_id, specialty.id);
+
+    Ok(())
+}
+
+/// Test that concurrent creates take distinct `#[sequence]` values, so the
+/// unique `number` column never collides.
+#[sqlx::test]
+async fn test_sequence_is_unique_across_concurrent_creates(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let (first, second) = tokio::try_join!(
+        TicketFactory::new().create(&pool),
+        TicketFactory::new().create(&pool),
+    )?;
+    assert_ne!(first.number, second.number);
+
+    let explicit = TicketFactory::new().with_number(-1).create(&pool).await?;
+    assert_eq!(explicit.number, -1);
+
+    Ok(())
+}
+
+/// Test that the pk type is exposed through `FactoryPk`.
+#[cfg(feature = "pk-trait")]
+#[test]
+fn test_factory_pk_names_pk_type() {
+    use factory_m8::FactoryPk;
+
+    let id: <PersonFactory as FactoryPk>::Pk = PersonId(7);
+    assert_eq!(id, PersonId(7));
+}
+
+/// Test that `column_order(...)` controls the generated INSERT column order.
+/// Test that a `require_existing` FK never creates its parent: unset or
+/// dangling ids are errors, and `create_tracked()` returns the existing parent.
+#[cfg(feature = "sqlx")]
+#[sqlx::test]
+async fn test_require_existing_fetches_parent_or_errors(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
+
+    let err = ClinicianFactory::new().create(&pool).await.unwrap_err();
+    assert!(err.to_string().contains("must reference an existing Specialty"), "{err}");
+
+    let err = ClinicianFactory::new()
+        .with_specialty_id(SpecialtyId(4242))
+        .create(&pool)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "specialty_id references a Specialty that doesn't exist");
+
+    let specialty = SpecialtyFactory::new()
+        .with_code("NEURO")
+        .with_name("Neurology")
+        .create(&pool)
+        .await?;
+    let (clinician, created) = ClinicianFactory::new()
+        .with_specialty(&specialty)
+        .create_tracked(&pool)
+        .await?;
+
+    assert_eq!(clinician.specialty_id, specialty.id);
+    assert_eq!(created.specialty, Some(specialty));
+    let specialty_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM specialty")
         .fetch_one(&pool)
         .await?;
+    assert_eq!(specialty_count.0, 1);
+
+    Ok(())
+}
+
+/// Test that a `#[from_fk]` field copies the auto-created parent's value, and
+/// keeps the factory's own value when the parent is set explicitly.
+#[sqlx::test]
+async fn test_from_fk_inherits_parent_tenant(
+    pool: PgPool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    setup_tables(&pool).await?;
 
+    let bed = BedFactory::new().create(&pool).await?;
+    let ward: Ward = sqlx::query_as("SELECT * FROM ward WHERE id = $1")
+        .bind(bed.ward_id)
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(ward.tenant_id, 77);
     assert_eq!(
-        notes_count.0, 0,
-        "Note should not be created. [NOTE]: PLEASE BE CAREFUL AS NOTE CREATES PERSON AS WELL"
+        bed.tenant_id, ward.tenant_id,
+        "Bed should inherit its ward's tenant"
     );
-    assert_eq!(person_count.0, 1, "Person should be created");
+
+    let bed = BedFactory::new()
+        .with_ward(&ward)
+        .with_tenant_id(5)
+        .create(&pool)
+        .await?;
+    assert_eq!(bed.tenant_id, 5);
 
     Ok(())
 }
+
+#[cfg(feature = "sqlx")]
+#[test]
+fn test_column_order_sets_insert_column_order() {
+    assert_eq!(
+        SpecialtyFactory::UPSERT_SQL,
+        "INSERT INTO specialty (name, code) VALUES ($1, $2) \
+         ON CONFLICT (code) DO UPDATE SET name = EXCLUDED.name, code = EXCLUDED.code RETURNING *"
+    );
+}
@@ -5,6 +5,7 @@
 use async_trait::async_trait;
 use factory_m8::{FactoryCreate, Sentinel};
 use factory_derive::Factory;
+use std::collections::HashMap;
 use std::error::Error;
 
 // =============================================================================
@@ -25,6 +26,12 @@ macro_rules! define_simple_id {
         #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
         pub struct $name(pub i64);
 
+        impl From<i64> for $name {
+            fn from(id: i64) -> Self {
+                $name(id)
+            }
+        }
+
         impl Sentinel for $name {
             fn sentinel() -> Self {
                 $name(0)
@@ -132,7 +139,7 @@ pub struct Patient {
 
 /// Factory with FK - all FK fields are Option<Id>
 #[derive(Debug, Default, Factory)]
-#[factory(entity = Patient)]
+#[factory(entity = Patient, parts)]
 pub struct PatientFactory {
     #[pk]
     pub id: PatientId,
@@ -168,6 +175,18 @@ fn test_with_practice_sets_id_from_entity() {
     assert_eq!(factory.practice_id, PracticeId(123));
 }
 
+#[test]
+fn test_with_practice_accepts_boxed_entity() {
+    let practice = Box::new(Practice {
+        id: PracticeId(321),
+        name: "Boxed Practice".to_string(),
+    });
+
+    let factory = PatientFactory::new().with_practice(practice);
+
+    assert_eq!(factory.practice_id, PracticeId(321));
+}
+
 #[test]
 fn test_with_practice_id_sets_id_directly() {
     let factory = PatientFactory::new().with_practice_id(PracticeId(456));
@@ -175,6 +194,70 @@ fn test_with_practice_id_sets_id_directly() {
     assert_eq!(factory.practice_id, PracticeId(456));
 }
 
+#[test]
+fn test_set_methods_configure_in_place() {
+    let practice = Practice {
+        id: PracticeId(77),
+        name: "Conditional".to_string(),
+    };
+    let with_tenant = true;
+
+    let mut factory = PatientFactory::new();
+    factory.set_practice(&practice).set_first_name("Ann");
+    if with_tenant {
+        factory.set_tenant_id(TenantId(5));
+    }
+
+    assert_eq!(factory.practice_id, PracticeId(77));
+    assert_eq!(factory.tenant_id, Some(TenantId(5)));
+    assert_eq!(factory.first_name.as_deref(), Some("Ann"));
+}
+
+#[test]
+fn test_with_tenant_id_opt_assigns_option_as_is() {
+    let factory = PatientFactory::new().with_tenant_id_opt(Some(TenantId(7)));
+    assert_eq!(factory.tenant_id, Some(TenantId(7)));
+
+    let factory = factory.with_tenant_id_opt(None);
+    assert!(factory.tenant_id.is_none());
+}
+
+#[test]
+fn test_clear_non_fk_keeps_fks_and_resets_fields() {
+    let mut factory = PatientFactory::new()
+        .with_practice_id(PracticeId(9))
+        .with_first_name("Alice");
+
+    factory.clear_non_fk();
+
+    assert_eq!(factory.practice_id, PracticeId(9));
+    assert!(factory.first_name.is_none());
+}
+
+#[test]
+fn test_into_parts_reconstructs_entity() {
+    let PatientFactoryParts {
+        id,
+        practice_id,
+        tenant_id,
+        first_name,
+    } = PatientFactory::new()
+        .with_practice_id(PracticeId(3))
+        .with_first_name("Ada")
+        .into_parts();
+
+    let patient = Patient {
+        id,
+        practice_id,
+        tenant_id,
+        first_name,
+    };
+
+    assert_eq!(patient.practice_id, PracticeId(3));
+    assert_eq!(patient.first_name.as_deref(), Some("Ada"));
+    assert!(patient.tenant_id.is_none());
+}
+
 #[test]
 fn test_with_optional_fields() {
     let practice = Practice {
@@ -271,6 +354,121 @@ fn test_required_field_panics_when_missing() {
         .build();
 }
 
+#[tokio::test]
+async fn test_build_with_fks_returns_missing_required_field_as_error() {
+    let err = PatientWithRequiredNameFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap_err();
+
+    let err = err
+        .downcast::<PatientWithRequiredNameFactoryError>()
+        .unwrap();
+    assert_eq!(err.fields(), vec!["name"]);
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Guardian {
+    pub id: PatientId,
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Guardian)]
+pub struct GuardianFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[required]
+    pub name: Option<String>,
+
+    #[required]
+    pub email: Option<String>,
+}
+
+#[test]
+fn test_try_build_reports_all_missing_required_fields() {
+    let err = GuardianFactory::new().try_build().unwrap_err();
+
+    assert_eq!(err.fields(), vec!["name", "email"]);
+    assert_eq!(
+        err.to_string(),
+        "2 problems: name is required - use with_name(); email is required - use with_email()"
+    );
+
+    let guardian = GuardianFactory::new()
+        .with_name("Ann")
+        .with_email("ann@example.com")
+        .try_build()
+        .unwrap();
+    assert_eq!(guardian.email, "ann@example.com");
+}
+
+#[test]
+#[should_panic(expected = "name is required - use with_name(); email is required")]
+fn test_build_panics_naming_all_missing_required_fields() {
+    GuardianFactory::new().build();
+}
+
+#[test]
+fn test_merge_keeps_value_on_none() {
+    let entity = PatientWithRequiredNameFactory::new()
+        .with_practice_id(PracticeId(1))
+        .with_name("Required Name")
+        .with_nickname("Bobby")
+        .merge_nickname(None)
+        .merge_name(Some("Merged Name".to_string()))
+        .build();
+
+    assert_eq!(entity.nickname, Some("Bobby".to_string()));
+    assert_eq!(entity.name, "Merged Name");
+}
+
+#[test]
+fn test_build_many_builds_n_entities() {
+    let patients = PatientWithRequiredNameFactory::new()
+        .with_practice_id(PracticeId(1))
+        .with_name("Twin")
+        .build_many(2);
+
+    assert_eq!(patients.len(), 2);
+    assert!(patients.iter().all(|p| p.name == "Twin"));
+
+    // Zero rows never reach the required-field checks
+    assert!(PatientWithRequiredNameFactory::new().build_many(0).is_empty());
+}
+
+#[test]
+fn test_with_opt_assigns_option_as_is() {
+    let upstream_nickname: Option<String> = None;
+
+    let entity = PatientWithRequiredNameFactory::new()
+        .with_practice_id(PracticeId(1))
+        .with_name_opt(Some("Opt Name".to_string()))
+        .with_nickname("Bobby")
+        .with_nickname_opt(upstream_nickname)
+        .build();
+
+    assert_eq!(entity.name, "Opt Name");
+    assert_eq!(entity.nickname, None);
+}
+
+#[test]
+fn test_normalize_fills_missing_required_field_from_default() {
+    let mut factory = PatientWithRequiredNameFactory::new()
+        .with_practice_id(PracticeId(3))
+        .with_nickname("Kept");
+    assert!(factory.try_build().is_err());
+
+    factory.normalize();
+    let entity = factory.build();
+
+    assert_eq!(entity.name, "");
+    assert_eq!(entity.practice_id, PracticeId(3));
+    assert_eq!(entity.nickname, Some("Kept".to_string()));
+}
+
 // =============================================================================
 // TEST 3: Factory with ALL OPTIONAL fields (no FK, no required)
 // =============================================================================
@@ -370,14 +568,3424 @@ fn test_non_option_fk_build_uses_value_directly() {
 }
 
 #[test]
+#[cfg_attr(
+    debug_assertions,
+    should_panic(expected = "practice_id is unset (sentinel) - build() doesn't create FKs")
+)]
 fn test_non_option_fk_build_with_default() {
-    // Non-Option FK uses Default value if not explicitly set
+    // Non-Option FK uses Default value if not explicitly set.
+    // Debug builds catch the unset FK with a debug_assert instead.
     let entity = EntityWithRequiredFkFactory::new().build();
 
     // Default for PracticeId is PracticeId(0)
     assert_eq!(entity.practice_id, PracticeId(0));
 }
 
+// =============================================================================
+// TEST 5: Option FK with a non-Copy (String) id type
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Country {
+    pub code: String,
+}
+
+#[derive(Debug, Default)]
+pub struct CountryFactory;
+
+#[async_trait]
+impl FactoryCreate<MockPool> for CountryFactory {
+    type Entity = Country;
+
+    async fn create(self, _pool: &MockPool) -> Result<Country, Box<dyn Error + Send + Sync>> {
+        Ok(Country {
+            code: "auto".to_string(),
+        })
+    }
+}
+
+impl CountryFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    pub id: PatientId,
+    pub country_id: Option<String>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Address)]
+pub struct AddressFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Country, "code", CountryFactory)]
+    pub country_id: Option<String>,
+}
+
+#[tokio::test]
+async fn test_string_option_fk_keeps_explicit_id() {
+    let factory = AddressFactory::new().with_country_id("nl".to_string());
+
+    let entity = factory.build_with_fks(&MockPool).await.unwrap();
+
+    assert_eq!(entity.country_id, Some("nl".to_string()));
+    // Factory is still usable - the id was cloned, not moved
+    assert_eq!(factory.country_id, Some("nl".to_string()));
+}
+
+#[tokio::test]
+async fn test_string_option_fk_auto_creates_when_empty() {
+    let entity = AddressFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(entity.country_id, Some("auto".to_string()));
+}
+
+#[tokio::test]
+async fn test_build_with_fks_future_can_be_spawned() {
+    // tokio::spawn requires a Send future; the derive asserts build_with_fks() is one
+    let factory = AddressFactory::new();
+    let entity = tokio::spawn(async move { factory.build_with_fks(&MockPool).await.unwrap() })
+        .await
+        .unwrap();
+
+    assert_eq!(entity.country_id, Some("auto".to_string()));
+}
+
+// =============================================================================
+// TEST 6: Vec field set from a borrowed slice
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Survey {
+    pub id: PatientId,
+    pub scores: Vec<i32>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Survey)]
+pub struct SurveyFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub scores: Vec<i32>,
+}
+
+#[test]
+fn test_with_slice_clones_into_vec() {
+    let source = [3, 1, 4];
+
+    let survey = SurveyFactory::new().with_scores_slice(&source).build();
+
+    assert_eq!(survey.scores, vec![3, 1, 4]);
+}
+
+// =============================================================================
+// TEST 7: Typestate builder (#[factory(typestate)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Doctor {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub name: String,
+    pub license: String,
+    pub nickname: Option<String>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Doctor, typestate)]
+pub struct DoctorFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    #[required]
+    pub name: Option<String>,
+
+    #[required]
+    pub license: Option<String>,
+
+    pub nickname: Option<String>,
+}
+
+#[test]
+fn test_typestate_builder_builds_once_required_fields_set() {
+    // Required setters can be called in any order
+    let doctor = DoctorFactory::builder()
+        .with_license("LIC-1")
+        .configure(|f| f.with_practice_id(PracticeId(5)).with_nickname("Doc"))
+        .with_name("Grey")
+        .build();
+
+    assert_eq!(doctor.name, "Grey");
+    assert_eq!(doctor.license, "LIC-1");
+    assert_eq!(doctor.practice_id, PracticeId(5));
+    assert_eq!(doctor.nickname, Some("Doc".to_string()));
+}
+
+// =============================================================================
+// TEST 8: #[parse] fields set from strings via FromStr
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email(String);
+
+impl std::str::FromStr for Email {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains('@') {
+            Ok(Email(s.to_string()))
+        } else {
+            Err(format!("invalid email: {s}"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub id: PatientId,
+    pub email: Email,
+    pub backup_email: Option<Email>,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Contact)]
+pub struct ContactFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[parse]
+    pub email: Email,
+
+    #[parse]
+    pub backup_email: Option<Email>,
+}
+
+impl Default for ContactFactory {
+    fn default() -> Self {
+        Self {
+            id: PatientId::sentinel(),
+            email: Email("default@example.com".to_string()),
+            backup_email: None,
+        }
+    }
+}
+
+#[test]
+fn test_parse_setter_parses_value_object() {
+    let contact = ContactFactory::new()
+        .with_email_parse("alice@example.com")
+        .unwrap()
+        .with_backup_email_parse("bob@example.com")
+        .unwrap()
+        .build();
+
+    assert_eq!(contact.email, Email("alice@example.com".to_string()));
+    assert_eq!(
+        contact.backup_email,
+        Some(Email("bob@example.com".to_string()))
+    );
+}
+
+#[test]
+fn test_parse_setter_returns_parse_error() {
+    let result = ContactFactory::new().with_email_parse("not-an-email");
+
+    assert_eq!(
+        result.err(),
+        Some("invalid email: not-an-email".to_string())
+    );
+}
+
+// =============================================================================
+// TEST 9: Partial factory over a Default entity (#[factory(partial)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub id: PatientId,
+    pub theme: String,
+    pub language: String,
+    pub page_size: i32,
+    pub beta: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            id: PatientId::sentinel(),
+            theme: "light".to_string(),
+            language: "en".to_string(),
+            page_size: 25,
+            beta: false,
+        }
+    }
+}
+
+/// Only declares 2 of the 5 entity fields
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Settings, partial)]
+pub struct SettingsFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub theme: String,
+}
+
+#[test]
+fn test_partial_factory_fills_rest_from_default() {
+    let settings = SettingsFactory::new().with_theme("dark").build();
+
+    assert_eq!(settings.theme, "dark");
+    assert_eq!(settings.language, "en");
+    assert_eq!(settings.page_size, 25);
+    assert!(!settings.beta);
+}
+
+#[tokio::test]
+async fn test_partial_factory_build_with_fks_fills_rest_from_default() {
+    let settings = SettingsFactory::new()
+        .with_theme("dark")
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(settings.theme, "dark");
+    assert_eq!(settings.language, "en");
+}
+
+// =============================================================================
+// TEST 10: #[enum_repr] fields set from integer discriminants
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[repr(i32)]
+pub enum Status {
+    #[default]
+    Active = 1,
+    Archived = 2,
+}
+
+impl TryFrom<i32> for Status {
+    type Error = i32;
+
+    fn try_from(raw: i32) -> Result<Self, Self::Error> {
+        match raw {
+            1 => Ok(Status::Active),
+            2 => Ok(Status::Archived),
+            other => Err(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub id: PatientId,
+    pub status: Status,
+    pub previous_status: Option<Status>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Account)]
+pub struct AccountFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[enum_repr]
+    pub status: Status,
+
+    #[enum_repr]
+    pub previous_status: Option<Status>,
+}
+
+#[test]
+fn test_enum_repr_setter_converts_discriminant() {
+    let account = AccountFactory::new()
+        .with_status_raw(2)
+        .with_previous_status_raw(1)
+        .build();
+
+    assert_eq!(account.status, Status::Archived);
+    assert_eq!(account.previous_status, Some(Status::Active));
+}
+
+#[test]
+#[should_panic(expected = "invalid status discriminant: 7")]
+fn test_enum_repr_setter_panics_on_invalid_discriminant() {
+    AccountFactory::new().with_status_raw(7);
+}
+
+// =============================================================================
+// TEST 11: Generated mock create (#[factory(mock)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clinic {
+    pub id: PracticeId,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Clinic, mock)]
+pub struct ClinicFactory {
+    #[pk]
+    pub id: PracticeId,
+
+    pub name: String,
+}
+
+#[tokio::test]
+async fn test_mock_create_returns_built_entity_with_fake_pk() {
+    let first = ClinicFactory::new()
+        .with_name("North")
+        .create(&MockPool)
+        .await
+        .unwrap();
+    let second = ClinicFactory::new()
+        .with_name("South")
+        .create(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(first.name, "North");
+    assert_eq!(second.name, "South");
+    assert!(!first.id.is_sentinel());
+    assert!(second.id.0 > first.id.0, "fake pks should increase");
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ward {
+    pub id: PatientId,
+    pub clinic_id: PracticeId,
+    pub backup_clinic_id: Option<PracticeId>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Ward, mock)]
+pub struct WardFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Clinic, "id", ClinicFactory)]
+    pub clinic_id: PracticeId,
+
+    #[fk(Clinic, "id", ClinicFactory)]
+    pub backup_clinic_id: Option<PracticeId>,
+}
+
+#[tokio::test]
+async fn test_mock_pool_resolves_fks_in_memory() {
+    let ward = WardFactory::new().build_with_fks(&MockPool).await.unwrap();
+
+    assert!(!ward.clinic_id.is_sentinel(), "FK should get a fake id");
+    assert!(ward.backup_clinic_id.is_some());
+    assert_ne!(Some(ward.clinic_id), ward.backup_clinic_id);
+
+    // The generated mock create goes through build_with_fks() too
+    let created = WardFactory::new().create(&MockPool).await.unwrap();
+    assert!(!created.id.is_sentinel());
+    assert!(!created.clinic_id.is_sentinel());
+}
+
+// =============================================================================
+// TEST 12: Deprecated alias setters (#[alias("...")])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pharmacy {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub display_name: String,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Pharmacy)]
+pub struct PharmacyFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    #[alias("clinic_id")]
+    pub practice_id: PracticeId,
+
+    #[alias("name")]
+    #[alias("title")]
+    pub display_name: String,
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_alias_setters_delegate_to_canonical_setters() {
+    let pharmacy = PharmacyFactory::new()
+        .with_clinic_id(PracticeId(3))
+        .with_name("Old Name")
+        .build();
+
+    assert_eq!(pharmacy.practice_id, PracticeId(3));
+    assert_eq!(pharmacy.display_name, "Old Name");
+
+    let pharmacy = PharmacyFactory::new()
+        .with_practice_id(PracticeId(3))
+        .with_title("Older Name")
+        .build();
+    assert_eq!(pharmacy.display_name, "Older Name");
+}
+
+// =============================================================================
+// TEST 13: Integer setters convert from other integer types via TryInto
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub id: PatientId,
+    pub systolic: i32,
+    pub diastolic: Option<i32>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Measurement)]
+pub struct MeasurementFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub systolic: i32,
+
+    pub diastolic: Option<i32>,
+}
+
+#[test]
+fn test_integer_setters_convert_from_other_integer_types() {
+    let systolic: u8 = 120;
+    let diastolic: usize = 80;
+
+    let measurement = MeasurementFactory::new()
+        .with_systolic_from(systolic)
+        .with_diastolic_from(diastolic)
+        .build();
+
+    assert_eq!(measurement.systolic, 120);
+    assert_eq!(measurement.diastolic, Some(80));
+}
+
+#[test]
+fn test_integer_setters_still_accept_literals() {
+    let measurement = MeasurementFactory::new()
+        .with_systolic(-5)
+        .with_diastolic(70)
+        .build();
+
+    assert_eq!(measurement.systolic, -5);
+    assert_eq!(measurement.diastolic, Some(70));
+}
+
+#[test]
+#[should_panic(expected = "systolic value out of range for i32")]
+fn test_integer_setter_panics_on_overflow() {
+    MeasurementFactory::new().with_systolic_from(u64::MAX);
+}
+
+#[test]
+fn test_try_integer_setter_returns_error_on_overflow() {
+    assert!(
+        MeasurementFactory::new()
+            .try_with_diastolic(u64::MAX)
+            .is_err()
+    );
+
+    let factory = MeasurementFactory::new()
+        .try_with_diastolic(90_u16)
+        .unwrap();
+    assert_eq!(factory.diastolic, Some(90));
+}
+
+// =============================================================================
+// TEST 14: Factory inheritance (#[factory(base)] / extends = BaseFactory)
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audit {
+    pub created_by: String,
+    pub revision: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invoice {
+    pub id: PatientId,
+    pub amount: i64,
+    pub audit: Audit,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Audit, base)]
+pub struct AuditFactory {
+    pub created_by: String,
+    pub revision: i32,
+}
+
+impl Default for AuditFactory {
+    fn default() -> Self {
+        Self {
+            created_by: "system".to_string(),
+            revision: 1,
+        }
+    }
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Invoice, extends = AuditFactory)]
+pub struct InvoiceFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub amount: i64,
+
+    pub audit: AuditFactory,
+}
+
+#[test]
+fn test_child_factory_exposes_base_setters() {
+    let invoice = InvoiceFactory::new()
+        .with_amount(250)
+        .with_created_by("alice")
+        .with_revision(3)
+        .build();
+
+    assert_eq!(invoice.amount, 250);
+    assert_eq!(
+        invoice.audit,
+        Audit {
+            created_by: "alice".to_string(),
+            revision: 3,
+        }
+    );
+}
+
+#[test]
+fn test_child_factory_builds_base_defaults() {
+    let invoice = InvoiceFactory::new().build();
+
+    assert_eq!(invoice.audit.created_by, "system");
+    assert_eq!(invoice.audit.revision, 1);
+    assert_eq!(AuditFactory::FACTORY_SCHEMA, &["created_by", "revision"]);
+}
+
+// =============================================================================
+// TEST 15: Environment variable fields (#[env("VAR")])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Integration {
+    pub id: PatientId,
+    pub api_key: String,
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Integration)]
+pub struct IntegrationFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[env("FACTORY_TEST_API_KEY")]
+    pub api_key: String,
+
+    #[env("FACTORY_TEST_ENDPOINT_UNSET")]
+    pub endpoint: Option<String>,
+}
+
+#[test]
+fn test_env_field_reads_environment_variable() {
+    // SAFETY: no other test touches this variable
+    unsafe { std::env::set_var("FACTORY_TEST_API_KEY", "secret-from-env") };
+
+    let integration = IntegrationFactory::new().build();
+    assert_eq!(integration.api_key, "secret-from-env");
+    assert_eq!(integration.endpoint, None);
+
+    let integration = IntegrationFactory::new()
+        .with_api_key("explicit")
+        .with_endpoint("https://example.com")
+        .build();
+    assert_eq!(integration.api_key, "explicit");
+    assert_eq!(integration.endpoint.as_deref(), Some("https://example.com"));
+}
+
+// =============================================================================
+// TEST 16: Empty string/collection setters (with_<field>_empty)
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Questionnaire {
+    pub id: PatientId,
+    pub title: String,
+    pub tags: Option<Vec<String>>,
+    pub answers: HashMap<String, i32>,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Questionnaire)]
+pub struct QuestionnaireFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub title: String,
+
+    pub tags: Option<Vec<String>>,
+
+    pub answers: HashMap<String, i32>,
+}
+
+impl Default for QuestionnaireFactory {
+    fn default() -> Self {
+        Self {
+            id: PatientId::default(),
+            title: "Intake".to_string(),
+            tags: None,
+            answers: HashMap::from([("age".to_string(), 42)]),
+        }
+    }
+}
+
+#[test]
+fn test_with_empty_sets_empty_not_none() {
+    let questionnaire = QuestionnaireFactory::new()
+        .with_title_empty()
+        .with_tags_empty()
+        .with_answers_empty()
+        .build();
+
+    assert_eq!(questionnaire.title, "");
+    assert_eq!(questionnaire.tags, Some(vec![]));
+    assert!(questionnaire.answers.is_empty());
+}
+
+// =============================================================================
+// TEST 17: Fully-qualified std::option::Option / core::option::Option fields
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Referral {
+    pub id: PatientId,
+    pub practice_id: Option<PracticeId>,
+    pub reason: String,
+    pub note: Option<String>,
+    pub priority: Option<i32>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Referral)]
+pub struct ReferralFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: core::option::Option<PracticeId>,
+
+    #[required]
+    pub reason: std::option::Option<String>,
+
+    pub note: std::option::Option<String>,
+
+    pub priority: core::option::Option<i32>,
+}
+
+#[test]
+fn test_qualified_option_fields_get_option_setters() {
+    let referral = ReferralFactory::new()
+        .with_practice_id(PracticeId(4))
+        .with_reason("Cardiology")
+        .with_note("Urgent")
+        .with_priority(2)
+        .build();
+
+    assert_eq!(referral.practice_id, Some(PracticeId(4)));
+    assert_eq!(referral.reason, "Cardiology");
+    assert_eq!(referral.note, Some("Urgent".to_string()));
+    assert_eq!(referral.priority, Some(2));
+}
+
+#[tokio::test]
+async fn test_qualified_option_fk_auto_creates() {
+    let referral = ReferralFactory::new()
+        .with_reason("Dermatology")
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert!(referral.practice_id.is_some());
+    assert_eq!(referral.note, None);
+}
+
+// =============================================================================
+// TEST 18: Partial update struct (#[factory(updates)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prescription {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub drug: String,
+    pub dosage_mg: i32,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Prescription, updates)]
+pub struct PrescriptionFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    #[required]
+    pub drug: Option<String>,
+
+    pub dosage_mg: i32,
+
+    pub notes: Option<String>,
+}
+
+#[test]
+fn test_update_struct_applies_only_set_fields() {
+    let mut prescription = PrescriptionFactory::new()
+        .with_practice_id(PracticeId(2))
+        .with_drug("Ibuprofen")
+        .with_dosage_mg(200)
+        .with_notes("After meals")
+        .build();
+
+    let update = PrescriptionUpdate {
+        dosage_mg: Some(400),
+        notes: Some(None),
+        ..Default::default()
+    };
+    update.apply(&mut prescription);
+
+    assert_eq!(prescription.practice_id, PracticeId(2));
+    assert_eq!(prescription.drug, "Ibuprofen");
+    assert_eq!(prescription.dosage_mg, 400);
+    assert_eq!(prescription.notes, None);
+}
+
+// =============================================================================
+// TEST 19: Option<Vec<T>> fields (with_<field>(IntoIterator) / add_<item>)
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Allergy {
+    pub id: PatientId,
+    pub triggers: Option<Vec<String>>,
+    pub severities: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Allergy)]
+pub struct AllergyFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub triggers: Option<Vec<String>>,
+
+    pub severities: Option<Vec<i32>>,
+}
+
+#[test]
+fn test_option_vec_setter_collects_iterator() {
+    let allergy = AllergyFactory::new()
+        .with_triggers(["pollen", "dust"].iter().map(|t| t.to_string()))
+        .build();
+
+    assert_eq!(
+        allergy.triggers,
+        Some(vec!["pollen".to_string(), "dust".to_string()])
+    );
+    assert_eq!(allergy.severities, None);
+}
+
+#[test]
+fn test_option_vec_add_item_initializes_and_pushes() {
+    let allergy = AllergyFactory::new()
+        .add_trigger("peanuts".to_string())
+        .add_trigger("latex".to_string())
+        .add_severity(3)
+        .build();
+
+    assert_eq!(
+        allergy.triggers,
+        Some(vec!["peanuts".to_string(), "latex".to_string()])
+    );
+    assert_eq!(allergy.severities, Some(vec![3]));
+}
+
+// =============================================================================
+// TEST 20: Named defaults() constructor (#[factory(defaults)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visit {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub tenant_id: Option<TenantId>,
+    pub reason: String,
+    pub notes: Option<String>,
+}
+
+// No Default impl: new() goes through the generated defaults()
+#[derive(Debug, Factory)]
+#[factory(entity = Visit, defaults)]
+pub struct VisitFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    #[fk(Tenant, "id", TenantFactory)]
+    pub tenant_id: Option<TenantId>,
+
+    pub reason: String,
+
+    pub notes: Option<String>,
+}
+
+#[test]
+fn test_defaults_sets_fk_fields_to_sentinel() {
+    let factory = VisitFactory::defaults();
+
+    assert!(factory.practice_id.is_sentinel());
+    assert!(factory.tenant_id.is_sentinel());
+    assert_eq!(factory.reason, "");
+    assert_eq!(factory.notes, None);
+}
+
+#[tokio::test]
+async fn test_defaults_factory_auto_creates_fks() {
+    let visit = VisitFactory::new()
+        .with_reason("Checkup")
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert!(!visit.practice_id.is_sentinel());
+    assert!(visit.tenant_id.is_some());
+    assert_eq!(visit.reason, "Checkup");
+}
+
+// =============================================================================
+// TEST 21: Per-row generators (#[each] / with_<field>_each)
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub id: PatientId,
+    pub email: String,
+    pub nickname: Option<String>,
+    pub age: i32,
+}
+
+#[derive(Debug, Clone, Default, Factory)]
+#[factory(entity = Member)]
+pub struct MemberFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub email: String,
+
+    pub nickname: Option<String>,
+
+    pub age: i32,
+
+    #[each]
+    pub each: MemberFactoryEach,
+}
+
+#[test]
+fn test_each_generators_vary_fields_per_row() {
+    let mut factory = MemberFactory::new()
+        .with_age(30)
+        .with_email_each(|i| format!("user{i}@example.com"))
+        .with_nickname_each(|i| format!("member-{i}"));
+
+    let members: Vec<Member> = (0..3)
+        .map(|i| {
+            factory.apply_each(i);
+            factory.build()
+        })
+        .collect();
+
+    let emails: Vec<&str> = members.iter().map(|m| m.email.as_str()).collect();
+    assert_eq!(
+        emails,
+        [
+            "user0@example.com",
+            "user1@example.com",
+            "user2@example.com"
+        ]
+    );
+    assert_eq!(members[2].nickname.as_deref(), Some("member-2"));
+    assert!(members.iter().all(|m| m.age == 30));
+}
+
+// =============================================================================
+// TEST 22: Provenance labels (#[factory(provenance)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabResult {
+    pub id: PatientId,
+    pub value: i32,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = LabResult, provenance)]
+pub struct LabResultFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub value: i32,
+
+    pub provenance: Option<String>,
+}
+
+#[test]
+fn test_provenance_is_kept_on_factory_not_entity() {
+    let factory = LabResultFactory::new()
+        .with_value(7)
+        .with_provenance("test_provenance_is_kept_on_factory_not_entity");
+
+    assert_eq!(
+        factory.provenance.as_deref(),
+        Some("test_provenance_is_kept_on_factory_not_entity")
+    );
+    assert!(format!("{factory:?}").contains("test_provenance_is_kept_on_factory_not_entity"));
+
+    // LabResult has no provenance field; the entity only carries its own columns
+    let result = factory.build();
+    assert_eq!(
+        result,
+        LabResult {
+            id: PatientId::default(),
+            value: 7,
+        }
+    );
+}
+
+// =============================================================================
+// TEST 23: Concrete factory for a generic entity (entity = Event<String>)
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event<T> {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub payload: T,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Event<String>, updates)]
+pub struct StringEventFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    pub payload: String,
+}
+
+#[tokio::test]
+async fn test_factory_builds_generic_entity() {
+    let event: Event<String> = StringEventFactory::new()
+        .with_practice_id(PracticeId(8))
+        .with_payload("signed-in")
+        .build();
+    assert_eq!(event.payload, "signed-in");
+
+    let mut event: Event<String> = StringEventFactory::new()
+        .with_payload("signed-out")
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert!(!event.practice_id.is_sentinel());
+
+    let update = EventUpdate {
+        payload: Some("edited".to_string()),
+        ..Default::default()
+    };
+    update.apply(&mut event);
+    assert_eq!(event.payload, "edited");
+}
+
+// =============================================================================
+// TEST 24: Range-checked integer setters (#[range(min, max)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vitals {
+    pub id: PatientId,
+    pub age: i32,
+    pub heart_rate: Option<u16>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Vitals)]
+pub struct VitalsFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[range(min = 0, max = 120)]
+    pub age: i32,
+
+    #[range(min = 20)]
+    pub heart_rate: Option<u16>,
+}
+
+#[test]
+fn test_range_setters_accept_values_in_range() {
+    let vitals = VitalsFactory::new()
+        .with_age(120)
+        .with_heart_rate(60)
+        .build();
+
+    assert_eq!(vitals.age, 120);
+    assert_eq!(vitals.heart_rate, Some(60));
+}
+
+#[test]
+#[should_panic(expected = "age value 121 is out of range 0..=120")]
+fn test_range_setter_panics_out_of_range() {
+    VitalsFactory::new().with_age(121);
+}
+
+#[test]
+fn test_try_range_setter_returns_error_out_of_range() {
+    let err = VitalsFactory::new().try_with_age(-1).unwrap_err();
+    assert_eq!(err.to_string(), "age value -1 is out of range 0..=120");
+
+    let err = VitalsFactory::new().try_with_heart_rate(5).unwrap_err();
+    assert_eq!(err.to_string(), "heart_rate value 5 is out of range 20..");
+
+    // Conversion failures are reported through the same error
+    assert!(VitalsFactory::new().try_with_heart_rate(-3i64).is_err());
+}
+
+// =============================================================================
+// TEST 25: Variants of one base factory (#[factory(variants)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Appointment {
+    pub id: PatientId,
+    pub title: String,
+    pub duration_minutes: i32,
+}
+
+#[derive(Debug, Clone, Default, Factory)]
+#[factory(entity = Appointment, variants)]
+pub struct AppointmentFactory {
+    #[pk]
+    pub id: PatientId,
+    pub title: String,
+    pub duration_minutes: i32,
+}
+
+#[test]
+fn test_build_variants_applies_each_override() {
+    let appointments = AppointmentFactory::build_variants(
+        AppointmentFactory::new().with_title("Checkup"),
+        vec![
+            |f| f.with_duration_minutes(15),
+            |f| f.with_duration_minutes(30),
+            |f| f.with_duration_minutes(60),
+        ],
+    );
+
+    let durations: Vec<i32> = appointments.iter().map(|a| a.duration_minutes).collect();
+    assert_eq!(durations, vec![15, 30, 60]);
+    assert!(appointments.iter().all(|a| a.title == "Checkup"));
+}
+
+// =============================================================================
+// TEST 26: Scoped setters for grouped fields (#[group("address")])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Office {
+    pub id: PracticeId,
+    pub name: String,
+    pub street: String,
+    pub city: String,
+    pub postal_code: Option<String>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Office)]
+pub struct OfficeFactory {
+    #[pk]
+    pub id: PracticeId,
+
+    pub name: String,
+
+    #[group("address")]
+    pub street: String,
+
+    #[group("address")]
+    pub city: String,
+
+    #[group("address")]
+    pub postal_code: Option<String>,
+}
+
+#[test]
+fn test_group_sets_fields_via_scoped_builder() {
+    let office = OfficeFactory::new()
+        .with_name("Downtown")
+        .address(|a| {
+            a.with_street("1 Main St")
+                .with_city("Springfield")
+                .with_postal_code("12345")
+        })
+        .build();
+
+    assert_eq!(office.name, "Downtown");
+    assert_eq!(office.street, "1 Main St");
+    assert_eq!(office.city, "Springfield");
+    assert_eq!(office.postal_code, Some("12345".to_string()));
+}
+
+#[test]
+fn test_with_num_stringifies_numeric_code() {
+    let office = OfficeFactory::new()
+        .with_name_num(7)
+        .with_postal_code_num(90210)
+        .build();
+
+    assert_eq!(office.name, "7");
+    assert_eq!(office.postal_code, Some("90210".to_string()));
+}
+
+/// Only `Display`, not `Into<String>`
+pub enum Floor {
+    Ground,
+    Upper(u8),
+}
+
+impl std::fmt::Display for Floor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Floor::Ground => write!(f, "ground floor"),
+            Floor::Upper(level) => write!(f, "floor {level}"),
+        }
+    }
+}
+
+#[test]
+fn test_with_display_uses_display_output() {
+    let office = OfficeFactory::new()
+        .with_name_display(Floor::Ground)
+        .with_postal_code_display(Floor::Upper(3))
+        .build();
+
+    assert_eq!(office.name, "ground floor");
+    assert_eq!(office.postal_code, Some("floor 3".to_string()));
+}
+
+// =============================================================================
+// TEST 27: Named preset constructors (#[factory(preset(...))])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaffMember {
+    pub id: PatientId,
+    pub name: String,
+    pub role: String,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = StaffMember, preset(
+    admin = |f| f.with_role("admin"),
+    guest = |f| f.with_role("guest").with_name("Guest"),
+))]
+pub struct StaffMemberFactory {
+    #[pk]
+    pub id: PatientId,
+    pub name: String,
+    pub role: String,
+}
+
+#[test]
+fn test_preset_constructors_apply_closure() {
+    let admin = StaffMemberFactory::admin().with_name("Alice").build();
+    assert_eq!(admin.role, "admin");
+    assert_eq!(admin.name, "Alice");
+
+    let guest = StaffMemberFactory::guest().build();
+    assert_eq!(guest.role, "guest");
+    assert_eq!(guest.name, "Guest");
+}
+
+// =============================================================================
+// TEST 28: FK read through a getter (#[fk(Entity, "id()", Factory)])
+// =============================================================================
+
+mod registry {
+    use super::*;
+
+    /// Parent whose id is private, readable only via `id()`
+    #[derive(Debug, Clone)]
+    pub struct Registry {
+        id: PracticeId,
+        pub name: String,
+    }
+
+    impl Registry {
+        pub fn new(id: PracticeId, name: &str) -> Self {
+            Self {
+                id,
+                name: name.to_string(),
+            }
+        }
+
+        pub fn id(&self) -> PracticeId {
+            self.id
+        }
+    }
+
+    #[derive(Debug, Default)]
+    pub struct RegistryFactory;
+
+    impl RegistryFactory {
+        pub fn new() -> Self {
+            Self
+        }
+    }
+
+    #[async_trait]
+    impl FactoryCreate<MockPool> for RegistryFactory {
+        type Entity = Registry;
+
+        async fn create(self, _pool: &MockPool) -> Result<Registry, Box<dyn Error + Send + Sync>> {
+            Ok(Registry::new(PracticeId(321), "Auto-created Registry"))
+        }
+    }
+}
+
+use registry::{Registry, RegistryFactory};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryEntry {
+    pub id: PatientId,
+    pub registry_id: PracticeId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = RegistryEntry)]
+pub struct RegistryEntryFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Registry, "id()", RegistryFactory)]
+    pub registry_id: PracticeId,
+}
+
+#[test]
+fn test_fk_getter_in_entity_setter() {
+    let registry = Registry::new(PracticeId(12), "Cancer Registry");
+    let entry = RegistryEntryFactory::new().with_registry(&registry).build();
+
+    assert_eq!(entry.registry_id, PracticeId(12));
+}
+
+#[tokio::test]
+async fn test_fk_getter_in_auto_create() {
+    let entry = RegistryEntryFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(entry.registry_id, PracticeId(321));
+}
+
+// =============================================================================
+// TEST 29: proptest strategies (#[factory(arb)], `proptest` feature)
+// =============================================================================
+
+#[cfg(feature = "proptest")]
+pub mod arb {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Badge {
+        pub id: PatientId,
+        pub practice_id: PracticeId,
+        pub label: String,
+        pub level: u8,
+        pub note: Option<String>,
+        pub active: bool,
+    }
+
+    #[derive(Debug, Default, Factory)]
+    #[factory(entity = Badge, arb)]
+    pub struct BadgeFactory {
+        #[pk]
+        pub id: PatientId,
+
+        #[fk(Practice, "id", PracticeFactory)]
+        pub practice_id: PracticeId,
+
+        #[required]
+        pub label: Option<String>,
+
+        pub level: u8,
+
+        pub note: Option<String>,
+
+        pub active: bool,
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_arb_factories_build(factory in BadgeFactory::arb()) {
+            proptest::prop_assert!(factory.practice_id.is_sentinel());
+
+            let badge = factory.with_practice_id(PracticeId(1)).build();
+            proptest::prop_assert!(!badge.label.is_empty());
+        }
+    }
+}
+
+// =============================================================================
+// TEST 30: Const-generic factories
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buf<const N: usize> {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub bytes: [u8; N],
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Buf<N>)]
+pub struct BufFactory<const N: usize> {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    pub bytes: [u8; N],
+
+    pub label: Option<String>,
+}
+
+impl<const N: usize> Default for BufFactory<N> {
+    fn default() -> Self {
+        Self {
+            id: PatientId(0),
+            practice_id: PracticeId::sentinel(),
+            bytes: [0; N],
+            label: None,
+        }
+    }
+}
+
+#[test]
+fn test_const_generic_factory_builds() {
+    let buf = BufFactory::<4>::new()
+        .with_practice_id(PracticeId(1))
+        .with_bytes([1, 2, 3, 4])
+        .with_label("four")
+        .build();
+
+    assert_eq!(buf.bytes, [1, 2, 3, 4]);
+    assert_eq!(buf.label.as_deref(), Some("four"));
+
+    let empty: Buf<0> = BufFactory::new().with_practice_id(PracticeId(1)).build();
+    assert_eq!(empty.bytes, []);
+}
+
+// =============================================================================
+// TEST 31: Result fields (with_<field>_ok / with_<field>_err)
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub id: PatientId,
+    pub outcome: Result<u32, String>,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Submission)]
+pub struct SubmissionFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub outcome: Result<u32, String>,
+}
+
+impl Default for SubmissionFactory {
+    fn default() -> Self {
+        Self {
+            id: PatientId(0),
+            outcome: Ok(0),
+        }
+    }
+}
+
+#[test]
+fn test_result_field_ok_and_err_setters() {
+    let accepted = SubmissionFactory::new().with_outcome_ok(42).build();
+    assert_eq!(accepted.outcome, Ok(42));
+
+    let rejected = SubmissionFactory::new()
+        .with_outcome_err("too short".to_string())
+        .build();
+    assert_eq!(rejected.outcome, Err("too short".to_string()));
+}
+
+// =============================================================================
+// TEST 32: Invariants (#[factory(invariant = check)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shift {
+    pub id: PatientId,
+    pub starts_at: u32,
+    pub ends_at: u32,
+}
+
+fn starts_before_end(shift: &Shift) -> Result<(), String> {
+    if shift.starts_at < shift.ends_at {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is not before {}",
+            shift.starts_at, shift.ends_at
+        ))
+    }
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Shift, invariant = starts_before_end)]
+pub struct ShiftFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub starts_at: u32,
+
+    pub ends_at: u32,
+}
+
+impl Default for ShiftFactory {
+    fn default() -> Self {
+        Self {
+            id: PatientId(0),
+            starts_at: 9,
+            ends_at: 17,
+        }
+    }
+}
+
+#[test]
+fn test_invariant_passes_for_valid_data() {
+    let shift = ShiftFactory::new().build();
+
+    assert_eq!((shift.starts_at, shift.ends_at), (9, 17));
+}
+
+#[test]
+fn test_invariant_fails_for_bad_data() {
+    let error = ShiftFactory::new()
+        .with_starts_at(18)
+        .try_build()
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        ShiftFactoryError::Invariant("18 is not before 17".to_string())
+    );
+}
+
+#[test]
+#[should_panic(expected = "invariant violated: 18 is not before 17")]
+fn test_invariant_build_panics_for_bad_data() {
+    ShiftFactory::new().with_starts_at(18).build();
+}
+
+// =============================================================================
+// TEST 33: Vec<Id> FKs (many-to-many staging)
+// =============================================================================
+
+define_simple_id!(TagId);
+
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub id: TagId,
+    pub name: String,
+}
+
+/// Never called: `Vec<Id>` FKs aren't auto-created
+#[derive(Debug, Default)]
+pub struct TagFactory;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    pub id: PatientId,
+    pub tag_ids: Vec<TagId>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Article)]
+pub struct ArticleFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Tag, "id", TagFactory)]
+    pub tag_ids: Vec<TagId>,
+}
+
+fn tag(id: i64, name: &str) -> Tag {
+    Tag {
+        id: TagId(id),
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn test_vec_fk_stages_tags_from_slice() {
+    let (rust, sql, tests) = (tag(1, "rust"), tag(2, "sql"), tag(3, "tests"));
+
+    let article = ArticleFactory::new()
+        .with_tags(&[&rust, &sql, &tests])
+        .build();
+
+    assert_eq!(article.tag_ids, vec![TagId(1), TagId(2), TagId(3)]);
+}
+
+#[tokio::test]
+async fn test_vec_fk_add_and_build_with_fks() {
+    let article = ArticleFactory::new()
+        .with_tag_ids(vec![TagId(4)])
+        .add_tag(tag(5, "async"))
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(article.tag_ids, vec![TagId(4), TagId(5)]);
+}
+
+// =============================================================================
+// TEST 34: Per-row templates (#[template("...{i}...")])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscriber {
+    pub id: PatientId,
+    pub username: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Factory)]
+#[factory(entity = Subscriber, mock)]
+pub struct SubscriberFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[template("user-{i}")]
+    pub username: String,
+
+    #[template("user-{i}@example.com")]
+    pub email: Option<String>,
+}
+
+#[tokio::test]
+async fn test_template_substitutes_row_index_in_create_many() {
+    let subscribers = SubscriberFactory::new()
+        .create_many(3, &MockPool)
+        .await
+        .unwrap();
+
+    let usernames: Vec<&str> = subscribers.iter().map(|s| s.username.as_str()).collect();
+    assert_eq!(usernames, ["user-0", "user-1", "user-2"]);
+    assert_eq!(subscribers[2].email.as_deref(), Some("user-2@example.com"));
+}
+
+#[test]
+fn test_template_substitutes_row_index_in_build_many() {
+    let subscribers = SubscriberFactory::new().build_many(2);
+
+    assert_eq!(subscribers[0].username, "user-0");
+    assert_eq!(subscribers[1].email.as_deref(), Some("user-1@example.com"));
+}
+
+// =============================================================================
+// TEST 35: Path-qualified entity types
+// =============================================================================
+
+pub mod models {
+    use super::{PatientId, PracticeId};
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Discharge {
+        pub id: PatientId,
+        pub practice_id: PracticeId,
+        pub reason: String,
+    }
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = crate::models::Discharge, updates)]
+pub struct DischargeFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    pub reason: String,
+}
+
+#[tokio::test]
+async fn test_path_qualified_entity_builds() {
+    let discharge: models::Discharge = DischargeFactory::new()
+        .with_reason("second opinion")
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(discharge.practice_id, PracticeId(999));
+    assert_eq!(discharge.reason, "second opinion");
+
+    let update = DischargeUpdate {
+        reason: Some("follow-up".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(update.reason.as_deref(), Some("follow-up"));
+}
+
+// =============================================================================
+// TEST 36: Path-qualified FK entity and factory types
+// =============================================================================
+
+pub mod labs {
+    use super::TenantId;
+
+    #[derive(Debug, Clone)]
+    pub struct Laboratory {
+        pub id: TenantId,
+        pub name: String,
+    }
+}
+
+pub mod factories {
+    use super::{MockPool, TenantId, labs::Laboratory};
+    use async_trait::async_trait;
+    use factory_m8::FactoryCreate;
+    use std::error::Error;
+
+    #[derive(Debug, Default)]
+    pub struct LaboratoryFactory;
+
+    impl LaboratoryFactory {
+        pub fn new() -> Self {
+            Self
+        }
+    }
+
+    #[async_trait]
+    impl FactoryCreate<MockPool> for LaboratoryFactory {
+        type Entity = Laboratory;
+
+        async fn create(
+            self,
+            _pool: &MockPool,
+        ) -> Result<Laboratory, Box<dyn Error + Send + Sync>> {
+            Ok(Laboratory {
+                id: TenantId(555),
+                name: "Auto-created Laboratory".to_string(),
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub id: PatientId,
+    pub laboratory_id: TenantId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Sample)]
+pub struct SampleFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(crate::labs::Laboratory, "id", factories::LaboratoryFactory)]
+    pub laboratory_id: TenantId,
+}
+
+#[tokio::test]
+async fn test_path_qualified_fk_types() {
+    let sample = SampleFactory::new().build_with_fks(&MockPool).await.unwrap();
+    assert_eq!(sample.laboratory_id, TenantId(555));
+
+    let laboratory = labs::Laboratory {
+        id: TenantId(7),
+        name: "Explicit".to_string(),
+    };
+    let sample = SampleFactory::new()
+        .with_laboratory(&laboratory)
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(sample.laboratory_id, TenantId(7));
+}
+
+// =============================================================================
+// TEST 37: Custom unset predicate for non-Option FKs
+// =============================================================================
+
+/// Rows migrated from the old schema point at placeholder tenant 1 until assigned
+fn is_placeholder_tenant(id: &TenantId) -> bool {
+    id.0 == 1
+}
+
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    pub id: PatientId,
+    pub tenant_id: TenantId,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Ledger)]
+pub struct LedgerFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Tenant, "id", TenantFactory, is_unset = is_placeholder_tenant)]
+    pub tenant_id: TenantId,
+}
+
+impl Default for LedgerFactory {
+    fn default() -> Self {
+        Self {
+            id: PatientId::default(),
+            tenant_id: TenantId(1),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_is_unset_predicate_decides_auto_create() {
+    let ledger = LedgerFactory::new().build_with_fks(&MockPool).await.unwrap();
+    assert_eq!(ledger.tenant_id, TenantId(888));
+
+    let ledger = LedgerFactory::new()
+        .with_tenant_id(TenantId(5))
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(ledger.tenant_id, TenantId(5));
+}
+
+#[test]
+fn test_is_unset_predicate_in_try_build() {
+    let err = LedgerFactory::new().try_build().unwrap_err();
+    assert_eq!(err.fields(), vec!["tenant_id"]);
+
+    let ledger = LedgerFactory::new()
+        .with_tenant_id(TenantId(5))
+        .try_build()
+        .unwrap();
+    assert_eq!(ledger.tenant_id, TenantId(5));
+}
+
+// =============================================================================
+// TEST 38: Numbered string values (#[seq])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volunteer {
+    pub id: PatientId,
+    pub email: String,
+    pub badge: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Factory)]
+#[factory(entity = Volunteer)]
+pub struct VolunteerFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[seq]
+    pub email: String,
+
+    #[seq("{n}")]
+    pub badge: Option<String>,
+}
+
+#[test]
+fn test_seq_numbers_each_built_row() {
+    let volunteers = VolunteerFactory::new()
+        .with_email("user{}@example.com")
+        .build_many(3);
+
+    let emails: std::collections::HashSet<&str> =
+        volunteers.iter().map(|v| v.email.as_str()).collect();
+    assert_eq!(emails.len(), 3);
+    assert!(emails.iter().all(|e| e.starts_with("user") && !e.contains("{}")));
+}
+
+#[test]
+fn test_seq_custom_placeholder_and_plain_values() {
+    let volunteer = VolunteerFactory::new()
+        .with_email("fixed@example.com")
+        .with_badge("B-{n}")
+        .build();
+
+    assert_eq!(volunteer.email, "fixed@example.com");
+    let badge = volunteer.badge.unwrap();
+    assert!(badge.strip_prefix("B-").unwrap().parse::<u64>().is_ok());
+}
+
+// =============================================================================
+// TEST 39: Reading the FK value with an extractor fn (by = path)
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct WarehouseKey {
+    pub id: TenantId,
+    pub region: String,
+}
+
+/// Parent whose id sits inside a composite key
+#[derive(Debug, Clone)]
+pub struct Warehouse {
+    pub key: WarehouseKey,
+}
+
+fn warehouse_id(warehouse: &Warehouse) -> TenantId {
+    warehouse.key.id
+}
+
+#[derive(Debug, Default)]
+pub struct WarehouseFactory;
+
+impl WarehouseFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for WarehouseFactory {
+    type Entity = Warehouse;
+
+    async fn create(self, _pool: &MockPool) -> Result<Warehouse, Box<dyn Error + Send + Sync>> {
+        Ok(Warehouse {
+            key: WarehouseKey {
+                id: TenantId(444),
+                region: "eu".to_string(),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shipment {
+    pub id: PatientId,
+    pub warehouse_id: TenantId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Shipment)]
+pub struct ShipmentFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Warehouse, "id", WarehouseFactory, by = warehouse_id)]
+    pub warehouse_id: TenantId,
+}
+
+#[tokio::test]
+async fn test_by_extracts_fk_value() {
+    let shipment = ShipmentFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(shipment.warehouse_id, TenantId(444));
+
+    let warehouse = Warehouse {
+        key: WarehouseKey {
+            id: TenantId(12),
+            region: "us".to_string(),
+        },
+    };
+    let shipment = ShipmentFactory::new().with_warehouse(&warehouse).build();
+    assert_eq!(shipment.warehouse_id, TenantId(12));
+}
+
+// =============================================================================
+// TEST 40: #[deprecated] fields deprecate their setters
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Pager {
+    pub id: PatientId,
+    pub number: String,
+    pub extension: Option<i32>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Pager)]
+pub struct PagerFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub number: String,
+
+    #[deprecated(note = "pagers no longer have extensions")]
+    pub extension: Option<i32>,
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_field_setter_still_works() {
+    let pager = PagerFactory::new()
+        .with_number("555-0100")
+        .with_extension(12)
+        .build();
+
+    assert_eq!(pager.extension, Some(12));
+}
+
+// =============================================================================
+// TEST 41: #[factory(derive_default)] generates the factory's Default impl
+// =============================================================================
+
+/// Id type whose sentinel isn't its `Default`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ShelterId(pub i64);
+
+impl Sentinel for ShelterId {
+    fn sentinel() -> Self {
+        ShelterId(-1)
+    }
+
+    fn is_sentinel(&self) -> bool {
+        self.0 == -1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shelter {
+    pub id: ShelterId,
+    pub practice_id: PracticeId,
+    pub name: String,
+    pub capacity: Option<i32>,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Shelter, derive_default)]
+pub struct ShelterFactory {
+    #[pk]
+    pub id: ShelterId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    pub name: String,
+
+    pub capacity: Option<i32>,
+}
+
+#[test]
+fn test_derive_default_starts_pk_and_fks_at_sentinel() {
+    let factory = ShelterFactory::default();
+
+    assert_eq!(factory.id, ShelterId(-1));
+    assert!(factory.practice_id.is_sentinel());
+    assert_eq!(factory.name, "");
+    assert_eq!(factory.capacity, None);
+
+    let shelter = ShelterFactory::new()
+        .with_practice_id(PracticeId(4))
+        .with_name("North")
+        .build();
+    assert_eq!(shelter.practice_id, PracticeId(4));
+    assert_eq!(shelter.name, "North");
+}
+
+// =============================================================================
+// TEST 42: Option FK into a Vec entity field (#[fk(..., into_vec)])
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Roster {
+    pub id: PatientId,
+    pub practice_ids: Vec<PracticeId>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Roster)]
+pub struct RosterFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory, into_vec)]
+    pub practice_ids: Option<PracticeId>,
+}
+
+#[tokio::test]
+async fn test_into_vec_maps_optional_fk_into_single_element_vec() {
+    let roster = RosterFactory::new()
+        .with_practice_ids(PracticeId(7))
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(roster.practice_ids, vec![PracticeId(7)]);
+
+    // Unset: auto-created by build_with_fks(), left empty by build()
+    let roster = RosterFactory::new().build_with_fks(&MockPool).await.unwrap();
+    assert_eq!(roster.practice_ids, vec![PracticeId(999)]);
+    assert!(RosterFactory::new().build().practice_ids.is_empty());
+}
+
+// =============================================================================
+// TEST 43: Value-object fields set through their builder (#[nested_builder])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Location {
+    pub street: String,
+    pub city: String,
+}
+
+#[derive(Default)]
+pub struct LocationBuilder {
+    location: Location,
+}
+
+impl LocationBuilder {
+    pub fn street(mut self, street: &str) -> Self {
+        self.location.street = street.to_string();
+        self
+    }
+
+    pub fn city(mut self, city: &str) -> Self {
+        self.location.city = city.to_string();
+        self
+    }
+
+    pub fn build(self) -> Location {
+        self.location
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Courier {
+    pub id: PatientId,
+    pub home: Location,
+    pub depot: Option<Location>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Courier)]
+pub struct CourierFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[nested_builder(LocationBuilder)]
+    pub home: Location,
+
+    #[nested_builder(LocationBuilder)]
+    pub depot: Option<Location>,
+}
+
+#[test]
+fn test_nested_builder_setter_builds_value_object() {
+    let courier = CourierFactory::new()
+        .with_home(|b| b.street("1 Main St").city("Springfield").build())
+        .with_depot(|b| b.city("Shelbyville").build())
+        .build();
+
+    assert_eq!(courier.home.street, "1 Main St");
+    assert_eq!(courier.home.city, "Springfield");
+    assert_eq!(courier.depot.unwrap().city, "Shelbyville");
+}
+
+// =============================================================================
+// TEST 44: Shared FKs create one parent per build (#[fk(..., shared)])
+// =============================================================================
+
+static REFEREES_CREATED: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct Referee {
+    pub id: PatientId,
+}
+
+/// Mock factory numbering every referee it creates
+#[derive(Debug, Default)]
+pub struct RefereeFactory;
+
+impl RefereeFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for RefereeFactory {
+    type Entity = Referee;
+
+    async fn create(self, _pool: &MockPool) -> Result<Referee, Box<dyn Error + Send + Sync>> {
+        let n = REFEREES_CREATED.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        Ok(Referee { id: PatientId(n) })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    pub id: PatientId,
+    pub referee_id: PatientId,
+    pub assistant_referee_id: PatientId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Fixture)]
+pub struct FixtureFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Referee, "id", RefereeFactory, shared)]
+    pub referee_id: PatientId,
+
+    #[fk(Referee, "id", RefereeFactory, shared)]
+    pub assistant_referee_id: PatientId,
+}
+
+#[tokio::test]
+async fn test_shared_fks_resolve_to_one_created_parent() {
+    let before = REFEREES_CREATED.load(std::sync::atomic::Ordering::SeqCst);
+
+    let fixture = FixtureFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(fixture.referee_id, fixture.assistant_referee_id);
+    assert_eq!(REFEREES_CREATED.load(std::sync::atomic::Ordering::SeqCst), before + 1);
+}
+
+// =============================================================================
+// TEST 45: Independent FKs resolved concurrently (#[factory(parallel_fks)])
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Stopover {
+    pub id: PatientId,
+}
+
+/// Mock factory counting how many of its creates are in flight at once
+#[derive(Debug, Default)]
+pub struct StopoverFactory;
+
+impl StopoverFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+static STOPOVERS_IN_FLIGHT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+static MAX_STOPOVERS_IN_FLIGHT: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(0);
+
+#[async_trait]
+impl FactoryCreate<MockPool> for StopoverFactory {
+    type Entity = Stopover;
+
+    async fn create(self, _pool: &MockPool) -> Result<Stopover, Box<dyn Error + Send + Sync>> {
+        let in_flight = STOPOVERS_IN_FLIGHT.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        MAX_STOPOVERS_IN_FLIGHT.fetch_max(in_flight, std::sync::atomic::Ordering::SeqCst);
+        // The simulated round-trip: pending once, so concurrent creates overlap
+        tokio::task::yield_now().await;
+        STOPOVERS_IN_FLIGHT.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(Stopover { id: PatientId(7) })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Itinerary {
+    pub id: PatientId,
+    pub first_id: PatientId,
+    pub second_id: PatientId,
+    pub third_id: Option<PatientId>,
+    pub fourth_id: PatientId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Itinerary, parallel_fks)]
+pub struct ItineraryFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub first_id: PatientId,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub second_id: PatientId,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub third_id: Option<PatientId>,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub fourth_id: PatientId,
+}
+
+/// The same entity, resolving its FKs one after another
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Itinerary)]
+pub struct SerialItineraryFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub first_id: PatientId,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub second_id: PatientId,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub third_id: Option<PatientId>,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub fourth_id: PatientId,
+}
+
+#[tokio::test]
+async fn test_parallel_fks_resolve_independent_parents_concurrently() {
+    MAX_STOPOVERS_IN_FLIGHT.store(0, std::sync::atomic::Ordering::SeqCst);
+    let serial = SerialItineraryFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    // One parent create after another
+    assert_eq!(
+        MAX_STOPOVERS_IN_FLIGHT.swap(0, std::sync::atomic::Ordering::SeqCst),
+        1
+    );
+
+    let parallel = ItineraryFactory::new()
+        .with_second_id(PatientId(2))
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    // The three unset FKs' parents are all created at once
+    assert_eq!(
+        MAX_STOPOVERS_IN_FLIGHT.load(std::sync::atomic::Ordering::SeqCst),
+        3
+    );
+
+    assert_eq!(serial.fourth_id, PatientId(7));
+    assert_eq!(parallel.first_id, PatientId(7));
+    assert_eq!(parallel.second_id, PatientId(2));
+    assert_eq!(parallel.third_id, Some(PatientId(7)));
+    assert_eq!(parallel.fourth_id, PatientId(7));
+}
+
+// =============================================================================
+// TEST 46: Self-referential FKs stop auto-creating (#[fk(..., max_depth = N)])
+// =============================================================================
+
+static CATEGORIES_CREATED: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct Category {
+    pub id: PatientId,
+    pub parent_id: Option<PatientId>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Category)]
+pub struct CategoryFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Category, "id", CategoryFactory, max_depth = 2)]
+    pub parent_id: Option<PatientId>,
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for CategoryFactory {
+    type Entity = Category;
+
+    async fn create(self, pool: &MockPool) -> Result<Category, Box<dyn Error + Send + Sync>> {
+        let mut category = self.build_with_fks(pool).await?;
+        category.id =
+            PatientId(CATEGORIES_CREATED.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1);
+        Ok(category)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Folder {
+    pub id: PatientId,
+    pub parent_id: PatientId,
+}
+
+/// A non-Option self-FK has no NULL to stop at, so reaching the depth is an error
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Folder)]
+pub struct FolderFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Folder, "id", FolderFactory, max_depth = 1)]
+    pub parent_id: PatientId,
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for FolderFactory {
+    type Entity = Folder;
+
+    async fn create(self, pool: &MockPool) -> Result<Folder, Box<dyn Error + Send + Sync>> {
+        let mut folder = self.build_with_fks(pool).await?;
+        folder.id = PatientId(1);
+        Ok(folder)
+    }
+}
+
+#[tokio::test]
+async fn test_max_depth_stops_self_referential_fk() {
+    let before = CATEGORIES_CREATED.load(std::sync::atomic::Ordering::SeqCst);
+
+    let category = CategoryFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    // The category's parent and grandparent are created; the grandparent's parent stays None
+    assert!(category.parent_id.is_some());
+    assert_eq!(
+        CATEGORIES_CREATED.load(std::sync::atomic::Ordering::SeqCst),
+        before + 2
+    );
+
+    // The counter is released, so the next build gets the full depth again
+    CategoryFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(
+        CATEGORIES_CREATED.load(std::sync::atomic::Ordering::SeqCst),
+        before + 4
+    );
+
+    let err = FolderFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "parent_id reached max_depth = 1 nested Folder parents - set it explicitly or make it an Option"
+    );
+}
+
+static TOPICS_CREATED: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct Topic {
+    pub id: PatientId,
+    pub parent_id: Option<PatientId>,
+}
+
+/// Like CategoryFactory, but its create yields first, so joined builds interleave
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Topic)]
+pub struct TopicFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Topic, "id", TopicFactory, max_depth = 2)]
+    pub parent_id: Option<PatientId>,
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for TopicFactory {
+    type Entity = Topic;
+
+    async fn create(self, pool: &MockPool) -> Result<Topic, Box<dyn Error + Send + Sync>> {
+        tokio::task::yield_now().await;
+        let mut topic = self.build_with_fks(pool).await?;
+        topic.id = PatientId(TOPICS_CREATED.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1);
+        Ok(topic)
+    }
+}
+
+#[tokio::test]
+async fn test_max_depth_is_counted_per_build() {
+    let before = TOPICS_CREATED.load(std::sync::atomic::Ordering::SeqCst);
+
+    let (first, second) = (TopicFactory::new(), TopicFactory::new());
+    let (first, second) = tokio::join!(
+        first.build_with_fks(&MockPool),
+        second.build_with_fks(&MockPool),
+    );
+
+    assert!(first.unwrap().parent_id.is_some());
+    assert!(second.unwrap().parent_id.is_some());
+    // Each build gets its full depth of two parents
+    assert_eq!(
+        TOPICS_CREATED.load(std::sync::atomic::Ordering::SeqCst),
+        before + 4
+    );
+}
+
+// =============================================================================
+// TEST 47: Setters convert with Into, primitives take the exact type
+// =============================================================================
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sku(String);
+
+impl From<&str> for Sku {
+    fn from(code: &str) -> Self {
+        Self(code.to_uppercase())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Product {
+    pub id: PatientId,
+    pub sku: Sku,
+    pub replaces: Option<Sku>,
+    pub stock: i32,
+    pub barcode: i64,
+    pub weight: f64,
+    pub discontinued: bool,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Product)]
+pub struct ProductFactory {
+    #[pk]
+    pub id: PatientId,
+    pub sku: Sku,
+    pub replaces: Option<Sku>,
+    pub stock: i32,
+    pub barcode: i64,
+    pub weight: f64,
+    pub discontinued: bool,
+}
+
+#[test]
+fn test_setters_convert_newtypes_and_keep_literal_inference() {
+    let product = ProductFactory::new()
+        .with_sku("ab-1")
+        .with_replaces("ab-0")
+        .with_stock(12)
+        .with_barcode(5_000_000_000)
+        .with_weight(0.5)
+        .with_discontinued(true)
+        .build();
+
+    assert_eq!(product.sku, Sku("AB-1".to_string()));
+    assert_eq!(product.replaces, Some(Sku("AB-0".to_string())));
+    assert_eq!(product.stock, 12);
+    assert_eq!(product.barcode, 5_000_000_000);
+    assert_eq!(product.weight, 0.5);
+    assert!(product.discontinued);
+
+    // The exact type still works for newtypes
+    let product = ProductFactory::new().with_sku(Sku("X".to_string())).build();
+    assert_eq!(product.sku, Sku("X".to_string()));
+}
+
+// =============================================================================
+// TEST 48: FK id setters accepting impl Into<Id> (#[factory(into_setters)])
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Consultation {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub tenant_id: Option<TenantId>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Consultation, into_setters)]
+pub struct ConsultationFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    #[fk(Tenant, "id", TenantFactory)]
+    pub tenant_id: Option<TenantId>,
+}
+
+#[test]
+fn test_into_setters_take_raw_ids() {
+    let consultation = ConsultationFactory::new()
+        .with_practice_id(42)
+        .with_tenant_id(7)
+        .build();
+
+    assert_eq!(consultation.practice_id, PracticeId(42));
+    assert_eq!(consultation.tenant_id, Some(TenantId(7)));
+
+    // The id types themselves still work
+    let consultation = ConsultationFactory::new()
+        .with_practice_id(PracticeId(1))
+        .build();
+    assert_eq!(consultation.practice_id, PracticeId(1));
+}
+
+// =============================================================================
+// TEST 49: PhantomData fields are filled in, not set
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Kelvin;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub label: String,
+    pub unit: std::marker::PhantomData<Kelvin>,
+    pub calibration_unit: std::marker::PhantomData<Kelvin>,
+}
+
+/// `unit` is declared (and gets no setter); `calibration_unit` only exists on the entity
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Gauge, phantom(calibration_unit))]
+pub struct GaugeFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    pub label: String,
+    pub unit: std::marker::PhantomData<Kelvin>,
+}
+
+#[tokio::test]
+async fn test_phantom_data_fields_are_filled_in() {
+    let gauge = GaugeFactory::new()
+        .with_practice_id(PracticeId(3))
+        .with_label("boiler")
+        .build();
+    assert_eq!(gauge.label, "boiler");
+    assert_eq!(gauge.unit, std::marker::PhantomData);
+
+    let gauge = GaugeFactory::new()
+        .with_label("boiler")
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(gauge.calibration_unit, std::marker::PhantomData::<Kelvin>);
+    assert!(!gauge.practice_id.is_sentinel());
+}
+
+// =============================================================================
+// TEST 50: Saturating integer setters (#[clamp(min, max)])
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredSurvey {
+    pub id: PatientId,
+    pub score: u8,
+    pub pain_level: Option<i16>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = ScoredSurvey)]
+pub struct ScoredSurveyFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[clamp(0, 100)]
+    pub score: u8,
+
+    #[clamp(-10, 10)]
+    pub pain_level: Option<i16>,
+}
+
+#[test]
+fn test_clamp_setters_saturate_into_bounds() {
+    let survey = ScoredSurveyFactory::new()
+        .with_score(-5)
+        .with_pain_level(7)
+        .build();
+    assert_eq!(survey.score, 0);
+    assert_eq!(survey.pain_level, Some(7));
+
+    // Values that don't even fit the field type saturate too
+    let survey = ScoredSurveyFactory::new()
+        .with_score(1_000_000i64)
+        .with_pain_level(u128::MAX)
+        .build();
+    assert_eq!(survey.score, 100);
+    assert_eq!(survey.pain_level, Some(10));
+
+    let mut factory = ScoredSurveyFactory::new();
+    factory.set_pain_level(i64::MIN);
+    assert_eq!(factory.build().pain_level, Some(-10));
+}
+
+// =============================================================================
+// TEST 51: Child rows created after the parent (#[has_many(ChildFactory)])
+// =============================================================================
+
+static CHART_NOTES_CREATED: std::sync::Mutex<Vec<(PatientId, String)>> =
+    std::sync::Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+pub struct ChartNote {
+    pub id: PatientId,
+    pub chart_id: PatientId,
+    pub body: String,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = ChartNote)]
+pub struct ChartNoteFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Chart, "id", ChartFactory)]
+    pub chart_id: PatientId,
+
+    pub body: String,
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for ChartNoteFactory {
+    type Entity = ChartNote;
+
+    async fn create(self, pool: &MockPool) -> Result<ChartNote, Box<dyn Error + Send + Sync>> {
+        let note = self.build_with_fks(pool).await?;
+        CHART_NOTES_CREATED
+            .lock()
+            .unwrap()
+            .push((note.chart_id, note.body.clone()));
+        Ok(note)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub id: PatientId,
+    pub title: String,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Chart)]
+pub struct ChartFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub title: String,
+
+    #[has_many(ChartNoteFactory)]
+    pub notes: Vec<ChartNoteFactory>,
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for ChartFactory {
+    type Entity = Chart;
+
+    async fn create(self, pool: &MockPool) -> Result<Chart, Box<dyn Error + Send + Sync>> {
+        let mut chart = self.build_with_fks(pool).await?;
+        chart.id = PatientId(51);
+        Ok(chart)
+    }
+}
+
+#[tokio::test]
+async fn test_has_many_children_are_created_after_parent() {
+    let chart = ChartFactory::new()
+        .with_title("intake")
+        .with_note(ChartNoteFactory::new().with_body("first"))
+        .with_note(ChartNoteFactory::new().with_body("second"))
+        .create_with_children(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(chart.id, PatientId(51));
+
+    // Each child points at the created parent, not at an auto-created one
+    let created = CHART_NOTES_CREATED.lock().unwrap();
+    assert_eq!(
+        *created,
+        vec![
+            (PatientId(51), "first".to_string()),
+            (PatientId(51), "second".to_string()),
+        ]
+    );
+}
+
+// =============================================================================
+// TEST 52: Post-insert hook (#[factory(after_create = "hook")])
+// =============================================================================
+
+static GRANTED_DOCUMENTS: std::sync::Mutex<Vec<PatientId>> = std::sync::Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Document, after_create = "grant_owner")]
+pub struct DocumentFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+}
+
+impl DocumentFactory {
+    async fn grant_owner(
+        document: &Document,
+        _pool: &MockPool,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        GRANTED_DOCUMENTS.lock().unwrap().push(document.id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for DocumentFactory {
+    type Entity = Document;
+
+    async fn create(self, pool: &MockPool) -> Result<Document, Box<dyn Error + Send + Sync>> {
+        let mut document = self.build_with_fks(pool).await?;
+        document.id = PatientId(52);
+        Ok(document)
+    }
+}
+
+#[tokio::test]
+async fn test_after_create_hook_runs_on_created_entity() {
+    let document = DocumentFactory::new()
+        .create_and_after(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(document.id, PatientId(52));
+    assert_eq!(document.practice_id, PracticeId(999));
+    assert_eq!(*GRANTED_DOCUMENTS.lock().unwrap(), vec![PatientId(52)]);
+
+    // build_with_fks_and_after() runs the hook on the built (not inserted) entity
+    let document = DocumentFactory::new()
+        .build_with_fks_and_after(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(document.id, PatientId(0));
+    assert_eq!(
+        *GRANTED_DOCUMENTS.lock().unwrap(),
+        vec![PatientId(52), PatientId(0)]
+    );
+}
+
+// =============================================================================
+// TEST 53: Read-only getters (get_<field>())
+// =============================================================================
+
+#[test]
+fn test_getters_expose_factory_state() {
+    let factory = PatientFactory::new().with_tenant_id(TenantId(5));
+    assert!(factory.get_practice_id().is_sentinel());
+    assert_eq!(*factory.get_tenant_id(), Some(TenantId(5)));
+    assert!(factory.get_id().is_sentinel());
+
+    let factory = ChartFactory::new().with_title("intake");
+    assert_eq!(factory.get_title(), "intake");
+}
+
+// =============================================================================
+// TEST 54: Unit struct factories building unit entities
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ping;
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Ping)]
+pub struct PingFactory;
+
+#[test]
+fn test_unit_struct_factory_builds_unit_entity() {
+    assert_eq!(PingFactory::new().build(), Ping);
+}
+
+// =============================================================================
+// TEST 55: Legacy ids without Sentinel (unset_when, #[pk(unset_value)])
+// =============================================================================
+
+/// Ids from the old schema: -1 marks "not set", and there's no `Sentinel` impl
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegacyId(pub i64);
+
+impl LegacyId {
+    fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LegacyAccount {
+    pub id: LegacyId,
+}
+
+#[derive(Debug, Default)]
+pub struct LegacyAccountFactory;
+
+impl LegacyAccountFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for LegacyAccountFactory {
+    type Entity = LegacyAccount;
+
+    async fn create(self, _pool: &MockPool) -> Result<LegacyAccount, Box<dyn Error + Send + Sync>> {
+        Ok(LegacyAccount { id: LegacyId(7) })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub id: LegacyId,
+    pub account_id: LegacyId,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Transfer)]
+pub struct TransferFactory {
+    #[pk(unset_value = LegacyId(-1))]
+    pub id: LegacyId,
+
+    #[fk(LegacyAccount, "id", LegacyAccountFactory, unset_when = "is_negative")]
+    pub account_id: LegacyId,
+}
+
+impl Default for TransferFactory {
+    fn default() -> Self {
+        Self {
+            id: LegacyId(-1),
+            account_id: LegacyId(-1),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_unset_when_and_pk_unset_value() {
+    let transfer = TransferFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(transfer.id, LegacyId(-1));
+    assert_eq!(transfer.account_id, LegacyId(7));
+
+    let transfer = TransferFactory::new()
+        .with_account_id(LegacyId(3))
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(transfer.account_id, LegacyId(3));
+
+    let err = TransferFactory::new().try_build().unwrap_err();
+    assert_eq!(err.fields(), vec!["account_id"]);
+}
+
+// =============================================================================
+// TEST 56: Timestamps from Unix seconds (with_<field>_epoch)
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct ClinicVisit {
+    pub id: PatientId,
+    pub checked_in_at: chrono::DateTime<chrono::Utc>,
+    pub checked_out_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = ClinicVisit)]
+pub struct ClinicVisitFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub checked_in_at: chrono::DateTime<chrono::Utc>,
+
+    pub checked_out_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[test]
+fn test_epoch_setter_builds_timestamp() {
+    let visit = ClinicVisitFactory::new()
+        .with_checked_in_at_epoch(1_700_000_000)
+        .with_checked_out_at_epoch(1_700_003_600)
+        .build();
+
+    assert_eq!(visit.checked_in_at.timestamp(), 1_700_000_000);
+    assert_eq!(
+        visit.checked_in_at.to_rfc3339(),
+        "2023-11-14T22:13:20+00:00"
+    );
+    assert_eq!(
+        visit.checked_out_at.map(|at| at - visit.checked_in_at),
+        Some(chrono::Duration::hours(1))
+    );
+}
+
+#[test]
+#[should_panic(expected = "checked_in_at: 9223372036854775807 is out of range for a timestamp")]
+fn test_epoch_setter_panics_out_of_range() {
+    ClinicVisitFactory::new().with_checked_in_at_epoch(i64::MAX);
+}
+
+// =============================================================================
+// TEST 57: Factories from string maps (#[factory(from_map)])
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Reading {
+    pub id: PatientId,
+    pub label: String,
+    pub value: i32,
+    pub verified: bool,
+    pub note: Option<String>,
+    pub retries: Option<u8>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Reading, from_map)]
+pub struct ReadingFactory {
+    #[pk]
+    pub id: PatientId,
+
+    pub label: String,
+
+    pub value: i32,
+
+    pub verified: bool,
+
+    pub note: Option<String>,
+
+    pub retries: Option<u8>,
+}
+
+fn string_map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_from_map_parses_fields() {
+    let row = string_map(&[
+        ("label", "glucose"),
+        ("value", "-12"),
+        ("verified", "true"),
+        ("note", "fasting"),
+        ("retries", ""),
+        ("unit", "mg/dL"),
+    ]);
+
+    let reading = ReadingFactory::try_from(row).unwrap().build();
+
+    assert_eq!(reading.label, "glucose");
+    assert_eq!(reading.value, -12);
+    assert!(reading.verified);
+    assert_eq!(reading.note.as_deref(), Some("fasting"));
+    assert_eq!(reading.retries, None);
+}
+
+#[test]
+fn test_from_map_reports_parse_error() {
+    let row = string_map(&[("retries", "many")]);
+
+    let err = ReadingFactory::try_from(row).unwrap_err();
+
+    assert_eq!(err.to_string(), "retries: invalid digit found in string");
+}
+
+// =============================================================================
+// TEST 58: FKs resolved in declaration order
+// =============================================================================
+
+static LEGS_CREATED: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct Leg {
+    pub id: PatientId,
+}
+
+/// Mock factory numbering its parents in creation order
+#[derive(Debug, Default)]
+pub struct LegFactory;
+
+impl LegFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<MockPool> for LegFactory {
+    type Entity = Leg;
+
+    async fn create(self, _pool: &MockPool) -> Result<Leg, Box<dyn Error + Send + Sync>> {
+        let id = LEGS_CREATED.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        Ok(Leg { id: PatientId(id) })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub id: PatientId,
+    pub outbound_id: PatientId,
+    pub layover_id: Option<PatientId>,
+    pub inbound_id: PatientId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Route)]
+pub struct RouteFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Leg, "id", LegFactory)]
+    pub outbound_id: PatientId,
+
+    #[fk(Leg, "id", LegFactory)]
+    pub layover_id: Option<PatientId>,
+
+    #[fk(Leg, "id", LegFactory)]
+    pub inbound_id: PatientId,
+}
+
+#[tokio::test]
+async fn test_fk_parents_created_in_declaration_order() {
+    let route = RouteFactory::new().build_with_fks(&MockPool).await.unwrap();
+
+    let outbound = route.outbound_id.0;
+    assert_eq!(route.layover_id, Some(PatientId(outbound + 1)));
+    assert_eq!(route.inbound_id, PatientId(outbound + 2));
+}
+
+// =============================================================================
+// TEST 59: FK ids behind a pointer (Arc<Id>, Option<Box<Id>>)
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Handover {
+    pub id: PatientId,
+    pub from_stop_id: std::sync::Arc<PatientId>,
+    pub to_stop_id: Option<Box<PatientId>>,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Handover)]
+pub struct HandoverFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub from_stop_id: std::sync::Arc<PatientId>,
+
+    #[fk(Stopover, "id", StopoverFactory)]
+    pub to_stop_id: Option<Box<PatientId>>,
+}
+
+#[tokio::test]
+async fn test_pointer_fk_setters_take_plain_ids() {
+    let stop = Stopover { id: PatientId(4) };
+
+    let handover = HandoverFactory::new()
+        .with_from_stop_id(PatientId(3))
+        .with_to_stop(&stop)
+        .build();
+    assert_eq!(*handover.from_stop_id, PatientId(3));
+    assert_eq!(handover.to_stop_id.as_deref(), Some(&PatientId(4)));
+
+    // Unset pointer FKs are auto-created like plain ones
+    let handover = HandoverFactory::new()
+        .with_to_stop_id_opt(None)
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+    assert_eq!(*handover.from_stop_id, PatientId(7));
+    assert_eq!(handover.to_stop_id.as_deref(), Some(&PatientId(7)));
+}
+
+// =============================================================================
+// TEST 60: create_method FKs skip batches and create_tracked's create()
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Depot {
+    pub id: PracticeId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Depot, mock)]
+pub struct DepotFactory {
+    #[pk]
+    pub id: PracticeId,
+}
+
+impl DepotFactory {
+    /// Stands in for a lookup by natural key: always the same depot
+    pub async fn find_or_create(
+        self,
+        _pool: &MockPool,
+    ) -> Result<Depot, Box<dyn Error + Send + Sync>> {
+        Ok(Depot { id: PracticeId(42) })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Consignment {
+    pub id: PatientId,
+    pub origin_id: PracticeId,
+    pub destination_id: PracticeId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Consignment, mock, batch_fks)]
+pub struct ConsignmentFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Depot, "id", DepotFactory, create_method = "find_or_create")]
+    pub origin_id: PracticeId,
+
+    #[fk(Depot, "id", DepotFactory, create_method = "find_or_create")]
+    pub destination_id: PracticeId,
+}
+
+#[tokio::test]
+async fn test_create_method_fks_are_not_batched() {
+    let consignment = ConsignmentFactory::new()
+        .build_with_fks(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(consignment.origin_id, PracticeId(42));
+    assert_eq!(consignment.destination_id, PracticeId(42));
+}
+
+#[tokio::test]
+async fn test_create_tracked_uses_create_method() {
+    let (consignment, created) = ConsignmentFactory::new()
+        .create_tracked(&MockPool)
+        .await
+        .unwrap();
+
+    assert_eq!(consignment.origin_id, PracticeId(42));
+    assert_eq!(created.origin, Some(Depot { id: PracticeId(42) }));
+    assert_eq!(created.destination, Some(Depot { id: PracticeId(42) }));
+}
+
+// =============================================================================
+// TEST 61: Shared FKs under batch_fks and create_tracked
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Umpire {
+    pub id: PracticeId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Umpire, mock)]
+pub struct UmpireFactory {
+    #[pk]
+    pub id: PracticeId,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bout {
+    pub id: PatientId,
+    pub judge_id: PracticeId,
+    pub umpire_id: PracticeId,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Bout, mock, batch_fks)]
+pub struct BoutFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Umpire, "id", UmpireFactory, shared)]
+    pub judge_id: PracticeId,
+
+    #[fk(Umpire, "id", UmpireFactory, shared)]
+    pub umpire_id: PracticeId,
+}
+
+#[tokio::test]
+async fn test_batch_fks_leaves_shared_fks_on_one_parent() {
+    let bout = BoutFactory::new().build_with_fks(&MockPool).await.unwrap();
+
+    assert!(!bout.judge_id.is_sentinel());
+    assert_eq!(bout.judge_id, bout.umpire_id);
+}
+
+#[tokio::test]
+async fn test_create_tracked_shares_one_parent() {
+    let (bout, created) = BoutFactory::new().create_tracked(&MockPool).await.unwrap();
+
+    assert_eq!(bout.judge_id, bout.umpire_id);
+    // The parent is reported once, by the FK that created it
+    assert_eq!(created.judge, Some(Umpire { id: bout.judge_id }));
+    assert_eq!(created.umpire, None);
+}
+
+// =============================================================================
+// TEST 62: create_tracked copies #[from_fk] fields off the parents it creates
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Campus {
+    pub id: PracticeId,
+    pub tenant_id: i64,
+}
+
+#[derive(Debug, Factory)]
+#[factory(entity = Campus, mock)]
+pub struct CampusFactory {
+    #[pk]
+    pub id: PracticeId,
+
+    pub tenant_id: i64,
+}
+
+impl Default for CampusFactory {
+    fn default() -> Self {
+        Self {
+            id: PracticeId::sentinel(),
+            tenant_id: 77,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Lecture {
+    pub id: PatientId,
+    pub campus_id: PracticeId,
+    pub tenant_id: i64,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Lecture, mock)]
+pub struct LectureFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Campus, "id", CampusFactory)]
+    pub campus_id: PracticeId,
+
+    #[from_fk(parent = "campus_id", field = "tenant_id")]
+    pub tenant_id: i64,
+}
+
+#[tokio::test]
+async fn test_create_tracked_inherits_from_created_parent() {
+    let (lecture, created) = LectureFactory::new()
+        .with_tenant_id(5)
+        .create_tracked(&MockPool)
+        .await
+        .unwrap();
+
+    let campus = created
+        .campus
+        .expect("Campus should have been auto-created");
+    assert_eq!(lecture.campus_id, campus.id);
+    assert_eq!(lecture.tenant_id, 77);
+}
+
+// =============================================================================
+// TEST 63: From<&Parent> impls are opt-in (#[factory(fk_from)])
+// =============================================================================
+
+#[derive(Debug, Clone)]
+pub struct Rota {
+    pub id: PatientId,
+    pub practice_id: PracticeId,
+    pub label: String,
+}
+
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Rota, fk_from)]
+pub struct RotaFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    pub label: String,
+}
+
+/// Without fk_from, a hand-written From<&Practice> doesn't conflict
+#[derive(Debug, Default, Factory)]
+#[factory(entity = Rota)]
+pub struct NamedRotaFactory {
+    #[pk]
+    pub id: PatientId,
+
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: PracticeId,
+
+    pub label: String,
+}
+
+impl From<&Practice> for NamedRotaFactory {
+    fn from(practice: &Practice) -> Self {
+        Self::new()
+            .with_practice(practice)
+            .with_label(format!("{} rota", practice.name))
+    }
+}
+
+#[test]
+fn test_fk_from_implements_from_parent() {
+    let practice = Practice {
+        id: PracticeId(8),
+        name: "Harbour".to_string(),
+    };
+
+    let rota = RotaFactory::from(&practice).build();
+    assert_eq!(rota.practice_id, PracticeId(8));
+
+    let rota = NamedRotaFactory::from(&practice).build();
+    assert_eq!(rota.practice_id, PracticeId(8));
+    assert_eq!(rota.label, "Harbour rota");
+}
+
 // =============================================================================
 // WHAT THE MACRO GENERATES (for reference)
 // =============================================================================
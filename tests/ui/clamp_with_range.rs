@@ -0,0 +1,15 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub age: u8,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person)]
+pub struct PersonFactory {
+    #[range(min = 0, max = 120)]
+    #[clamp(0, 120)]
+    pub age: u8,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, column_order(last_name, first_name))]
+pub struct PersonFactory {
+    #[pk]
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+#![deny(deprecated)]
+
+use factory_derive::Factory;
+
+pub struct Pager {
+    pub id: i64,
+    pub extension: Option<i32>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Pager)]
+pub struct PagerFactory {
+    #[pk]
+    pub id: i64,
+
+    #[deprecated(note = "pagers no longer have extensions")]
+    pub extension: Option<i32>,
+
+    #[deprecated(note = "pagers are no longer tagged")]
+    pub tags: Vec<String>,
+}
+
+fn main() {
+    let _ = PagerFactory::new().with_extension(12);
+    let _ = PagerFactory::new().with_extension_from(12u8);
+    let _ = PagerFactory::new().with_tags_slice(&["ward".to_string()]);
+    let _ = PagerFactory::new().with_tags_empty();
+}
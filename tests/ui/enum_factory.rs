@@ -0,0 +1,13 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub first_name: String,
+}
+
+#[derive(Factory)]
+#[factory(entity = Person)]
+pub enum PersonFactory {
+    Named(String),
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub name: String,
+}
+
+mod bases {
+    use super::Person;
+    use factory_derive::Factory;
+
+    #[derive(Default, Factory)]
+    #[factory(entity = Person, base)]
+    pub struct PersonBaseFactory {
+        pub name: String,
+    }
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, extends = bases::PersonBaseFactory)]
+pub struct AdminFactory {
+    pub base: bases::PersonBaseFactory,
+}
+
+fn main() {}
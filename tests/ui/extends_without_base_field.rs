@@ -0,0 +1,19 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, base)]
+pub struct PersonBaseFactory {
+    pub name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, extends = PersonBaseFactory)]
+pub struct AdminFactory {
+    pub name: String,
+}
+
+fn main() {}
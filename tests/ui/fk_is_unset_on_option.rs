@@ -0,0 +1,28 @@
+use factory_derive::Factory;
+
+pub struct Org {
+    pub id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Org)]
+pub struct OrgFactory {
+    pub id: i64,
+}
+
+pub fn is_zero(id: &i64) -> bool {
+    *id == 0
+}
+
+pub struct Team {
+    pub org_id: Option<i64>,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Team)]
+pub struct TeamFactory {
+    #[fk(Org, "id", OrgFactory, is_unset = is_zero)]
+    pub org_id: Option<i64>,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use factory_derive::Factory;
+
+pub struct Category {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Category)]
+pub struct CategoryFactory {
+    pub id: i64,
+    #[fk(Category, "id", CategoryFactory, max_depth = 2, use_last)]
+    pub parent_id: Option<i64>,
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use factory_derive::Factory;
+
+pub struct Org {
+    pub id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Org)]
+pub struct OrgFactory {
+    pub id: i64,
+}
+
+pub struct Team {
+    pub org_id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Team)]
+pub struct TeamFactory {
+    #[fk(Org, "id", OrgFactory, null_on_error)]
+    pub org_id: i64,
+}
+
+fn main() {}
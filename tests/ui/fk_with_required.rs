@@ -0,0 +1,19 @@
+use factory_derive::Factory;
+
+pub struct Note {
+    pub id: i64,
+    pub person_id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Note)]
+pub struct NoteFactory {
+    #[pk]
+    pub id: i64,
+
+    #[fk(Person, "id", PersonFactory)]
+    #[required]
+    pub person_id: i64,
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use factory_derive::Factory;
+
+pub struct Practice {
+    pub id: i64,
+    pub tenant_id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Practice)]
+pub struct PracticeFactory {
+    pub id: i64,
+    pub tenant_id: i64,
+}
+
+pub struct Patient {
+    pub practice_id: i64,
+    pub tenant_id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Patient)]
+pub struct PatientFactory {
+    #[fk(Practice, "id", PracticeFactory)]
+    pub practice_id: i64,
+    #[from_fk(parent = "clinic_id", field = "tenant_id")]
+    pub tenant_id: i64,
+}
+
+fn main() {}
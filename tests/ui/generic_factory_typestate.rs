@@ -0,0 +1,13 @@
+use factory_derive::Factory;
+
+pub struct Event<T> {
+    pub payload: T,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Event<T>, typestate)]
+pub struct EventFactory<T: Default> {
+    pub payload: T,
+}
+
+fn main() {}
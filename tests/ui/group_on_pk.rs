@@ -0,0 +1,18 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person)]
+pub struct PersonFactory {
+    #[pk]
+    #[group("identity")]
+    pub id: i64,
+    #[group("identity")]
+    pub name: String,
+}
+
+fn main() {}
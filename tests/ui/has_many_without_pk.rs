@@ -0,0 +1,25 @@
+use factory_derive::Factory;
+
+pub struct Note {
+    pub patient_id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Note)]
+pub struct NoteFactory {
+    pub patient_id: i64,
+}
+
+pub struct Patient {
+    pub name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Patient)]
+pub struct PatientFactory {
+    pub name: String,
+    #[has_many(NoteFactory)]
+    pub notes: Vec<NoteFactory>,
+}
+
+fn main() {}
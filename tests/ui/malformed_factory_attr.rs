@@ -0,0 +1,14 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person cache)]
+pub struct PersonFactory {
+    #[pk]
+    pub id: i64,
+}
+
+fn main() {}
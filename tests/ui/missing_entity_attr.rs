@@ -0,0 +1,8 @@
+use factory_derive::Factory;
+
+#[derive(Default, Factory)]
+pub struct PersonFactory {
+    pub first_name: String,
+}
+
+fn main() {}
@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+use factory_derive::Factory;
+use factory_m8::FactoryCreate;
+use std::error::Error;
+use std::rc::Rc;
+
+pub struct Pool;
+
+pub struct Practice {
+    pub id: i64,
+}
+
+#[derive(Default)]
+pub struct PracticeFactory;
+
+impl PracticeFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl FactoryCreate<Pool> for PracticeFactory {
+    type Entity = Practice;
+
+    async fn create(self, _pool: &Pool) -> Result<Practice, Box<dyn Error + Send + Sync>> {
+        Ok(Practice { id: 1 })
+    }
+}
+
+// Holds an Rc across an .await, so its future isn't Send
+async fn find_practice(_pool: &Pool) -> Option<i64> {
+    let cached = Rc::new(1);
+    std::future::ready(()).await;
+    Some(*cached)
+}
+
+pub struct Patient {
+    pub id: i64,
+    pub practice_id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Patient)]
+pub struct PatientFactory {
+    #[pk]
+    pub id: i64,
+
+    #[fk(Practice, "id", PracticeFactory, find = find_practice)]
+    pub practice_id: i64,
+}
+
+fn main() {}
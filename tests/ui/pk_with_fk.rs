@@ -0,0 +1,15 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person)]
+pub struct PersonFactory {
+    #[pk]
+    #[fk(Person, "id", PersonFactory)]
+    pub id: i64,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, provenance)]
+pub struct PersonFactory {
+    pub name: String,
+}
+
+fn main() {}
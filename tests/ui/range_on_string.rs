@@ -0,0 +1,14 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person)]
+pub struct PersonFactory {
+    #[range(min = 1)]
+    pub name: String,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub age: u8,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person)]
+pub struct PersonFactory {
+    #[range()]
+    pub age: u8,
+}
+
+fn main() {}
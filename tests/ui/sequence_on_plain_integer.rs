@@ -0,0 +1,14 @@
+use factory_derive::Factory;
+
+pub struct Ticket {
+    pub number: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Ticket)]
+pub struct TicketFactory {
+    #[sequence]
+    pub number: i64,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// The `factories` feature is the user crate's, which this one doesn't declare
+#![allow(unexpected_cfgs)]
+
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+    pub first_name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, test_only)]
+pub struct PersonFactory {
+    #[pk]
+    pub id: i64,
+
+    pub first_name: String,
+}
+
+fn main() {
+    // Outside cfg(test) and without the `factories` feature, nothing is generated
+    let _person = PersonFactory::new().build();
+}
@@ -0,0 +1,11 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub first_name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person)]
+pub struct PersonFactory(String);
+
+fn main() {}
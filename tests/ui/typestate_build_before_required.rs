@@ -0,0 +1,21 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+    pub first_name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, typestate)]
+pub struct PersonFactory {
+    #[pk]
+    pub id: i64,
+
+    #[required]
+    pub first_name: Option<String>,
+}
+
+fn main() {
+    // first_name was never set, so build() must not exist yet
+    let _person = PersonFactory::builder().build();
+}
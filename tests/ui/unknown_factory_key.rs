@@ -0,0 +1,17 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+    pub first_name: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, batch_fk)]
+pub struct PersonFactory {
+    #[pk]
+    pub id: i64,
+
+    pub first_name: String,
+}
+
+fn main() {}
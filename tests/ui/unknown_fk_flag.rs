@@ -0,0 +1,18 @@
+use factory_derive::Factory;
+
+pub struct Note {
+    pub id: i64,
+    pub person_id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Note)]
+pub struct NoteFactory {
+    #[pk]
+    pub id: i64,
+
+    #[fk(Person, "id", PersonFactory, shraed)]
+    pub person_id: i64,
+}
+
+fn main() {}
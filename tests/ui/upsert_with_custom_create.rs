@@ -0,0 +1,16 @@
+use factory_derive::Factory;
+
+pub struct Person {
+    pub id: i64,
+    pub email: String,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Person, table = "person", upsert = "email", custom_create)]
+pub struct PersonFactory {
+    #[pk]
+    pub id: i64,
+    pub email: String,
+}
+
+fn main() {}
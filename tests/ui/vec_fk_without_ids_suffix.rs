@@ -0,0 +1,24 @@
+use factory_derive::Factory;
+
+pub struct Tag {
+    pub id: i64,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Tag)]
+pub struct TagFactory {
+    pub id: i64,
+}
+
+pub struct Post {
+    pub tags: Vec<i64>,
+}
+
+#[derive(Default, Factory)]
+#[factory(entity = Post)]
+pub struct PostFactory {
+    #[fk(Tag, "id", TagFactory)]
+    pub tags: Vec<i64>,
+}
+
+fn main() {}